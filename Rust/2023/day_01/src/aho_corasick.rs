@@ -0,0 +1,197 @@
+use std::collections::VecDeque;
+
+/// A pattern found in the searched text, covering the bytes start..end.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Match {
+    /// Index of the pattern in the order they were given to the matcher.
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Finds every occurrence of a set of byte patterns in a single pass over the text.
+///
+/// The patterns are stored in a trie where each node also has a failure link to the node for the
+/// longest proper suffix of it that is also in the trie. When the next byte doesn't continue the
+/// current node the failure links are followed instead of starting over, so overlapping matches
+/// like "eightwo" are all found and no byte of the text is looked at twice.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    pattern_lengths: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: Vec<(u8, usize)>,
+    fail: usize,
+    /// Patterns ending at this node, including those reached through the failure links. Longest
+    /// pattern first.
+    outputs: Vec<usize>,
+}
+
+impl AhoCorasick {
+    const ROOT: usize = 0;
+
+    /// Builds the matcher. Panics if any pattern is empty.
+    pub fn new<I, P>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<[u8]>,
+    {
+        let mut matcher = AhoCorasick {
+            nodes: vec![Node::default()],
+            pattern_lengths: Vec::new(),
+        };
+        for pattern in patterns {
+            matcher.insert(pattern.as_ref());
+        }
+        matcher.build_failure_links();
+        matcher
+    }
+
+    fn child(&self, node: usize, byte: u8) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|(child_byte, _)| *child_byte == byte)
+            .map(|(_, child)| *child)
+    }
+
+    fn insert(&mut self, pattern: &[u8]) {
+        assert!(!pattern.is_empty(), "Patterns can't be empty");
+
+        let mut node = Self::ROOT;
+        for byte in pattern {
+            node = match self.child(node, *byte) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.push((*byte, child));
+                    child
+                }
+            };
+        }
+        self.nodes[node].outputs.push(self.pattern_lengths.len());
+        self.pattern_lengths.push(pattern.len());
+    }
+
+    /// Sets the failure links breadth first, so the link of a node's parent is always done first.
+    fn build_failure_links(&mut self) {
+        let mut queue: VecDeque<usize> = self.nodes[Self::ROOT]
+            .children
+            .iter()
+            .map(|(_, child)| *child)
+            .collect();
+
+        while let Some(node) = queue.pop_front() {
+            for (byte, child) in self.nodes[node].children.clone() {
+                let fail = match node {
+                    Self::ROOT => Self::ROOT,
+                    _ => self.next_state(self.nodes[node].fail, byte),
+                };
+                self.nodes[child].fail = fail;
+                let inherited = self.nodes[fail].outputs.clone();
+                self.nodes[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+    }
+
+    fn next_state(&self, mut node: usize, byte: u8) -> usize {
+        loop {
+            if let Some(child) = self.child(node, byte) {
+                return child;
+            }
+            if node == Self::ROOT {
+                return Self::ROOT;
+            }
+            node = self.nodes[node].fail;
+        }
+    }
+
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_lengths.len()
+    }
+
+    /// Iterates over all matches, including overlapping ones, ordered by where they end. Matches
+    /// ending at the same byte come longest first.
+    pub fn find_iter<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = Match> + 'a {
+        let mut state = Self::ROOT;
+        text.iter().enumerate().flat_map(move |(index, byte)| {
+            state = self.next_state(state, *byte);
+            self.nodes[state].outputs.iter().map(move |pattern| Match {
+                pattern: *pattern,
+                start: index + 1 - self.pattern_lengths[*pattern],
+                end: index + 1,
+            })
+        })
+    }
+
+    /// Returns the match that starts first, preferring the longest one if several start there.
+    pub fn first_match(&self, text: &[u8]) -> Option<Match> {
+        let longest_pattern = self.pattern_lengths.iter().max().copied().unwrap_or(0);
+        let mut best: Option<Match> = None;
+        for found in self.find_iter(text) {
+            // Any later match would have to be longer than every pattern to start before best.
+            if best.is_some_and(|best| found.end > best.start + longest_pattern) {
+                break;
+            }
+            let is_better = best.is_none_or(|best| {
+                found.start < best.start || (found.start == best.start && found.end > best.end)
+            });
+            if is_better {
+                best = Some(found);
+            }
+        }
+        best
+    }
+
+    /// Returns the match that starts last, preferring the longest one if several start there.
+    pub fn last_match(&self, text: &[u8]) -> Option<Match> {
+        self.find_iter(text)
+            .max_by_key(|found| (found.start, found.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_matches() {
+        let matcher = AhoCorasick::new(["he", "she", "his", "hers"]);
+        let found: Vec<(usize, usize)> = matcher
+            .find_iter(b"ushers")
+            .map(|found| (found.pattern, found.start))
+            .collect();
+        assert_eq!(found, vec![(1, 1), (0, 2), (3, 2)]);
+        assert_eq!(matcher.find_iter(b"xyz").count(), 0);
+    }
+
+    #[test]
+    fn digit_words() {
+        let matcher = AhoCorasick::new(["one", "two", "eight", "2"]);
+        let found: Vec<usize> = matcher
+            .find_iter(b"eightwone2")
+            .map(|found| found.pattern)
+            .collect();
+        assert_eq!(found, vec![2, 1, 0, 3]);
+
+        let first = matcher.first_match(b"xeightwo").unwrap();
+        assert_eq!((first.pattern, first.start, first.end), (2, 1, 6));
+        let last = matcher.last_match(b"xeightwo").unwrap();
+        assert_eq!((last.pattern, last.start, last.end), (1, 5, 8));
+        assert_eq!(matcher.first_match(b"abc"), None);
+    }
+
+    #[test]
+    fn first_match_prefers_earlier_start() {
+        // "bcd" ends first, but the longer "abcde" starts earlier.
+        let matcher = AhoCorasick::new(["bcd", "abcde", "a"]);
+        let first = matcher.first_match(b"abcde").unwrap();
+        assert_eq!((first.pattern, first.end), (1, 5));
+        assert_eq!(matcher.last_match(b"abcde").unwrap().pattern, 0);
+    }
+}
@@ -1,3 +1,5 @@
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod aho_corasick;
 #[macro_use]
 mod macros;
 mod reader;
@@ -73,8 +75,14 @@ numbers too. Meaning 7 is 7, but "seven" is also seven.
 
 I think the best method here is to use the same code as part one, but add some further pattens to
 look out for.
+
+Update: Instead of checking every pattern at every position of the line, the patterns are now
+given to the shared Aho-Corasick matcher. It goes through the line once and reports every pattern
+it finds, including overlapping ones like the "eight" and "two" in "eightwo". Only the first and
+the last match of each line are needed for the calibration value.
 */
 mod part_two {
+    use crate::aho_corasick::AhoCorasick;
     use crate::reader;
     use std::error::Error;
 
@@ -101,37 +109,26 @@ mod part_two {
         ]
     }
 
-    fn get_values(data_str: &str) -> Vec<u64> {
-        let patterns = patterns();
-        let mut values = Vec::new();
-
-        for data_str_index in 0..data_str.len() {
-            let part = &data_str[data_str_index..];
-            for (pattern, value) in patterns.iter() {
-                if part.starts_with(pattern) {
-                    values.push(*value);
-                    break;
-                }
-            }
-        }
-
-        values
-    }
-
-    fn get_calibration_value(data_str: &str) -> Result<u64, Box<dyn Error>> {
-        let values = get_values(data_str);
-
-        if values.is_empty() {
-            Err(format!("No values found in data str: [{}]", data_str).into())
-        } else {
-            // values will always contain at least one element so this will never panic.
-            Ok((values.first().unwrap() * 10) + values.last().unwrap())
+    fn get_calibration_value(
+        data_str: &str,
+        matcher: &AhoCorasick,
+        values: &[u64],
+    ) -> Result<u64, Box<dyn Error>> {
+        let first = matcher.first_match(data_str.as_bytes());
+        let last = matcher.last_match(data_str.as_bytes());
+
+        match first.zip(last) {
+            Some((first, last)) => Ok(values[first.pattern] * 10 + values[last.pattern]),
+            None => Err(format!("No values found in data str: [{}]", data_str).into()),
         }
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
+        let (words, values): (Vec<&str>, Vec<u64>) = patterns().into_iter().unzip();
+        let matcher = AhoCorasick::new(words);
+
         reader::get_lines(data_path)?
-            .map(|line| get_calibration_value(&line))
+            .map(|line| get_calibration_value(&line, &matcher, &values))
             .sum::<Result<u64, _>>()
     }
 }
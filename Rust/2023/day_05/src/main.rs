@@ -1,5 +1,7 @@
 #[macro_use]
 mod macros;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod interval;
 mod reader;
 
 #[cfg(test)]
@@ -13,7 +15,7 @@ pub const PART_ONE_EXPECTED_VALUE: u64 = 403695602;
 #[allow(dead_code)]
 pub const PART_TWO_EXPECTED_TEST_VALUE: u64 = 46;
 #[allow(dead_code)]
-pub const PART_TWO_EXPECTED_VALUE: u64 = 0;
+pub const PART_TWO_EXPECTED_VALUE: u64 = 219529182;
 
 //
 
@@ -172,72 +174,35 @@ second value is the range length.
 
 If the code is good enough then we should be able to simply copy part one and just make some
 changes to the get_seeds() function.
+
+Update: It was not good enough, there are billions of seeds to push through the maps one by one.
+Instead of transforming single values the seed ranges are now pushed through the maps as whole
+ranges, using the shared interval set and range map. Whenever a range crosses the edge of a map
+row it is split in two, so the amount of ranges stays small. The lowest location is then simply
+the start of the first range that comes out the other end.
 */
 mod part_two {
+    use crate::interval::{map_set_through, IntervalSet, RangeMap};
     use crate::reader;
     use std::{error::Error, str::FromStr};
 
-    struct Range {
-        shift_distance: i64,
-        lower: u64,
-        upper: u64,
-    }
+    fn read_next_map(lines: &mut dyn Iterator<Item = String>) -> Result<RangeMap, Box<dyn Error>> {
+        let _map_name_line = lines.next();
 
-    impl Range {
-        fn from_row(row: &str) -> Result<Range, Box<dyn Error>> {
-            let values: Vec<u64> = parse_values(row)?;
-            if values.len() != 3 {
-                return Err(format!("Row [{row}] does not have exactly 3 values!").into());
+        let mut map = RangeMap::new();
+        for line in lines {
+            if line.is_empty() {
+                break;
             }
 
-            let lower = values[1];
-            let upper = values[1] + values[2];
-            let shift_distance = values[1] as i64 - values[0] as i64;
-            Ok(Range {
-                shift_distance,
-                lower,
-                upper,
-            })
-        }
-
-        fn attempt_transform(&self, value: u64) -> Option<u64> {
-            if value < self.lower || value > self.upper {
-                return None;
-            }
-
-            Some((value as i64 - self.shift_distance) as u64)
-        }
-    }
-
-    #[derive(Default)]
-    struct Map {
-        ranges: Vec<Range>,
-    }
-
-    impl Map {
-        fn read_next_map(lines: &mut dyn Iterator<Item = String>) -> Result<Map, Box<dyn Error>> {
-            let _map_name_line = lines.next();
-
-            let mut map = Map::default();
-            for line in lines {
-                if line.is_empty() {
-                    break;
-                }
-
-                map.ranges.push(Range::from_row(&line)?);
+            let values: Vec<u64> = parse_values(&line)?;
+            if values.len() != 3 {
+                return Err(format!("Row [{line}] does not have exactly 3 values!").into());
             }
-
-            Ok(map)
+            map.insert(values[1], values[0], values[2]);
         }
 
-        fn transform_value(&self, value: u64) -> u64 {
-            for range in &self.ranges {
-                if let Some(new_value) = range.attempt_transform(value) {
-                    return new_value;
-                }
-            }
-            value
-        }
+        Ok(map)
     }
 
     fn parse_values<T: FromStr>(row: &str) -> Result<Vec<T>, Box<dyn Error>>
@@ -252,34 +217,32 @@ mod part_two {
             .collect::<Result<Vec<T>, _>>()?)
     }
 
-    fn get_seeds(possible_row: Option<String>) -> Result<Vec<u64>, Box<dyn Error>> {
-        parse_values(&possible_row.ok_or("Missing seeds row!")?)
+    fn get_seeds(possible_row: Option<String>) -> Result<IntervalSet<u64>, Box<dyn Error>> {
+        let values: Vec<u64> = parse_values(&possible_row.ok_or("Missing seeds row!")?)?;
+        if !values.len().is_multiple_of(2) {
+            return Err("The seeds row does not consist of value pairs!".into());
+        }
+
+        Ok(values
+            .chunks(2)
+            .map(|pair| pair[0]..pair[0] + pair[1])
+            .collect())
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
         let mut lines = reader::get_lines(data_path)?;
 
-        let seed_ranges = get_seeds(lines.next())?;
+        let seeds = get_seeds(lines.next())?;
 
         lines.next(); // Skip empty row after seeds line.
 
         let mut maps = Vec::new();
         for _ in 0..7 {
-            maps.push(Map::read_next_map(&mut lines)?);
-        }
-
-        let mut highest = u64::MAX;
-        for i in 0..seed_ranges.len() / 2 {
-            for seed in seed_ranges[i * 2]..seed_ranges[i * 2] + seed_ranges[i * 2 + 1] {
-                let mut value = seed;
-                for map in &maps {
-                    value = map.transform_value(value);
-                }
-                highest = highest.min(value);
-            }
+            maps.push(read_next_map(&mut lines)?);
         }
 
-        Ok(highest)
+        let locations = map_set_through(&maps, &seeds);
+        Ok(locations.min().ok_or("No seeds to plant!")?)
     }
 }
 
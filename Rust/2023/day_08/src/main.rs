@@ -1,5 +1,9 @@
 #[macro_use]
 mod macros;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod interner;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod math;
 mod reader;
 
 #[cfg(test)]
@@ -13,7 +17,7 @@ pub const PART_ONE_EXPECTED_VALUE: u64 = 19099;
 #[allow(dead_code)]
 pub const PART_TWO_EXPECTED_TEST_VALUE: u64 = 6;
 #[allow(dead_code)]
-pub const PART_TWO_EXPECTED_VALUE: u64 = 0;
+pub const PART_TWO_EXPECTED_VALUE: u64 = 17099847107071;
 
 //
 
@@ -44,6 +48,7 @@ Then simply check if the new node is ZZZ, and stop if it is. Otherwise continue.
 When stopped, return the amount of iterations performed to get the result.
 */
 mod part_one {
+    use crate::interner::Interner;
     use crate::reader;
     use std::{collections::HashMap, error::Error};
 
@@ -68,18 +73,19 @@ mod part_one {
 
     fn get_nodes(
         data_lines: &mut dyn Iterator<Item = String>,
-    ) -> Result<HashMap<u32, [u32; 2]>, Box<dyn Error>> {
-        let mut nodes: HashMap<u32, [u32; 2]> = HashMap::new();
+        interner: &mut Interner,
+    ) -> Result<HashMap<usize, [usize; 2]>, Box<dyn Error>> {
+        let mut nodes: HashMap<usize, [usize; 2]> = HashMap::new();
 
         for line in data_lines {
             let mut node_names = line
-                .split(|c: char| !c.is_ascii_uppercase())
+                .split(|c: char| !c.is_ascii_alphanumeric())
                 .filter(|s| !s.is_empty());
             nodes.insert(
-                translate_node(node_names.next().unwrap())?,
+                interner.intern(node_names.next().ok_or("Missing first node name!")?),
                 [
-                    translate_node(node_names.next().ok_or("Missing second node name!")?)?,
-                    translate_node(node_names.next().ok_or("Missing third node name!")?)?,
+                    interner.intern(node_names.next().ok_or("Missing second node name!")?),
+                    interner.intern(node_names.next().ok_or("Missing third node name!")?),
                 ],
             );
         }
@@ -87,26 +93,11 @@ mod part_one {
         Ok(nodes)
     }
 
-    // Translating the nodes is also technically not needed. String implements the required traits
-    // for the hashmap to function using them instead. But I like converting things like this to
-    // integers because it limits the type of nodes, and should in theory be more efficient as a
-    // integer is primitive type compared to a full string.
-    // It is also a fun challenge. :)
-    fn translate_node(node_name: &str) -> Result<u32, Box<dyn Error>> {
-        let (mut multiplier, mut result) = (1, 0);
-        // reversing the order isn't actually needed. The program solves the puzzle just fine
-        // without it. But reversing it means the leftmost char is actually the highest value one
-        // instead of the lowest.
-        for char in node_name.chars().rev() {
-            result += (char as u8 - b'A') as u32 * multiplier;
-            multiplier *= 26;
-        }
-        Ok(result)
-    }
-
-    // Pre-translated nodes to help readability.
-    const AAA: u32 = 0;
-    const ZZZ: u32 = 17575;
+    // The node names are turned into integer ids by the shared interner. Strings would work as
+    // hashmap keys too, but integers are cheaper to hash and compare.
+    // The names used to be translated by hand as base 26 numbers (AAA = 0, ZZZ = 17575), the
+    // interner does the same job without limiting the names to three uppercase letters, so names
+    // with digits like 11A work as well.
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
         let mut lines = reader::get_lines(data_path)?;
@@ -114,12 +105,16 @@ mod part_one {
         let instructions = get_instructions(&lines.next().ok_or("Data file is empty!")?)?;
         lines.next(); // Skip empty row.
 
-        let nodes = get_nodes(&mut lines)?;
+        let mut interner = Interner::new();
+        let nodes = get_nodes(&mut lines, &mut interner)?;
 
         let mut iterations = 0;
-        let mut current_node = AAA;
+        let mut current_node = interner
+            .get("AAA")
+            .ok_or("Start node AAA does not exist!")?;
+        let goal_node = interner.get("ZZZ").ok_or("Goal node ZZZ does not exist!")?;
 
-        while current_node != ZZZ {
+        while current_node != goal_node {
             current_node = nodes
                 .get(&current_node)
                 .ok_or("A requested node did not exist!")?
@@ -160,6 +155,8 @@ logic for selecting the next node into the new for loop. And finally after the f
 check that stops the loop if all nodes currently ends with 'Z'
 */
 mod part_two {
+    use crate::interner::Interner;
+    use crate::math::crt;
     use crate::reader;
     use std::{collections::HashMap, error::Error};
 
@@ -184,18 +181,19 @@ mod part_two {
 
     fn get_nodes(
         data_lines: &mut dyn Iterator<Item = String>,
-    ) -> Result<HashMap<u32, [u32; 2]>, Box<dyn Error>> {
-        let mut nodes: HashMap<u32, [u32; 2]> = HashMap::new();
+        interner: &mut Interner,
+    ) -> Result<HashMap<usize, [usize; 2]>, Box<dyn Error>> {
+        let mut nodes: HashMap<usize, [usize; 2]> = HashMap::new();
 
         for line in data_lines {
             let mut node_names = line
-                .split(|c: char| !c.is_ascii_uppercase())
+                .split(|c: char| !c.is_ascii_alphanumeric())
                 .filter(|s| !s.is_empty());
             nodes.insert(
-                translate_node(node_names.next().unwrap())?,
+                interner.intern(node_names.next().ok_or("Missing first node name!")?),
                 [
-                    translate_node(node_names.next().ok_or("Missing second node name!")?)?,
-                    translate_node(node_names.next().ok_or("Missing third node name!")?)?,
+                    interner.intern(node_names.next().ok_or("Missing second node name!")?),
+                    interner.intern(node_names.next().ok_or("Missing third node name!")?),
                 ],
             );
         }
@@ -203,12 +201,6 @@ mod part_two {
         Ok(nodes)
     }
 
-    // Translating the nodes is also technically not needed. String implements the required traits
-    // for the hashmap to function using them instead. But I like converting things like this to
-    // integers because it limits the type of nodes, and should in theory be more efficient as a
-    // integer is primitive type compared to a full string.
-    // It is also a fun challenge. :)
-    //
     // Edit: Seems like the puzzle instructions mentioning it taking "significantly more steps" to
     // find the goal was a warning.
     // The following code passes the test data but is taking ages with the full data. It will
@@ -216,16 +208,87 @@ mod part_two {
     //
     // I think we need to figure out a better way of doing this. Maybe figure out the exact "loop"
     // each path goes in, then just use math to figure out when all loops are at a end node.
-    fn translate_node(node_name: &str) -> Result<u32, Box<dyn Error>> {
-        let (mut multiplier, mut result) = (1, 0);
-        // reversing the order isn't actually needed. The program solves the puzzle just fine
-        // without it. But reversing it means the leftmost char is actually the highest value one
-        // instead of the lowest.
-        for char in node_name.chars().rev() {
-            result += (char as u8 - b'A') as u32 * multiplier;
-            multiplier *= 26;
+    //
+    // Update: The node ids now come from the shared interner, so the start and end nodes are
+    // found by their names instead of the % 26 trick on the hand translated ids.
+    //
+    // Update: Each path is now walked on its own until it is back at a node it has been at before
+    // with the same instruction up next. From there on it repeats forever, so every end node
+    // reached inside the loop gives a congruence: steps = time (mod loop length).
+    // Picking one end node time from every path and combining them with the chinese remainder
+    // theorem gives the first time all paths are at those end nodes together. The loop lengths
+    // all share the instruction count as a factor, so the shared math module's version that
+    // handles moduli that aren't coprime is needed.
+
+    /// The steps at which a single path stands on an end node.
+    struct Cycle {
+        /// Step at which the path enters its loop.
+        start: u64,
+        length: u64,
+        /// End node steps before the loop is entered. These only happen once.
+        lead_in_ends: Vec<u64>,
+        /// End node steps within the first lap of the loop. These repeat every length steps.
+        loop_ends: Vec<u64>,
+    }
+
+    impl Cycle {
+        fn find(
+            start_node: usize,
+            nodes: &HashMap<usize, [usize; 2]>,
+            instructions: &[bool],
+            end_nodes: &[bool],
+        ) -> Result<Cycle, Box<dyn Error>> {
+            let mut first_seen: HashMap<(usize, usize), u64> = HashMap::new();
+            let mut ends = Vec::new();
+            let mut node = start_node;
+            let mut step: u64 = 0;
+
+            loop {
+                let instruction_index = step as usize % instructions.len();
+                if let Some(loop_start) = first_seen.insert((node, instruction_index), step) {
+                    let (lead_in_ends, loop_ends) = ends.iter().partition(|end| **end < loop_start);
+                    return Ok(Cycle {
+                        start: loop_start,
+                        length: step - loop_start,
+                        lead_in_ends,
+                        loop_ends,
+                    });
+                }
+
+                if end_nodes[node] {
+                    ends.push(step);
+                }
+                let instruction = instructions[instruction_index] as usize;
+                node = nodes.get(&node).ok_or("A requested node did not exist!")?[instruction];
+                step += 1;
+            }
+        }
+
+        fn is_at_end(&self, step: u64) -> bool {
+            if step < self.start {
+                return self.lead_in_ends.contains(&step);
+            }
+            let lap_step = self.start + (step - self.start) % self.length;
+            self.loop_ends.contains(&lap_step)
         }
-        Ok(result)
+    }
+
+    /// Finds the first step at or after min_step where every path is at one of the chosen end
+    /// node steps.
+    fn first_common_step(cycles: &[Cycle], chosen_ends: &[u64], min_step: u64) -> Option<u64> {
+        let congruences: Vec<(i128, i128)> = cycles
+            .iter()
+            .zip(chosen_ends)
+            .map(|(cycle, end)| (*end as i128, cycle.length as i128))
+            .collect();
+        let (step, period) = crt(&congruences).ok()?;
+
+        let min_step = min_step as i128;
+        let laps = match step < min_step {
+            true => (min_step - step + period - 1) / period,
+            false => 0,
+        };
+        u64::try_from(step + laps * period).ok()
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
@@ -234,30 +297,65 @@ mod part_two {
         let instructions = get_instructions(&lines.next().ok_or("Data file is empty!")?)?;
         lines.next(); // Skip empty row.
 
-        let nodes = get_nodes(&mut lines)?;
+        let mut interner = Interner::new();
+        let nodes = get_nodes(&mut lines, &mut interner)?;
 
-        let mut current_nodes = nodes
+        let start_nodes = nodes
             .keys()
-            .filter_map(|n| if n % 26 == 0 { Some(*n) } else { None })
-            .collect::<Vec<u32>>();
+            .filter(|n| interner.name(**n).is_some_and(|name| name.ends_with('A')))
+            .copied()
+            .collect::<Vec<usize>>();
+        let end_nodes: Vec<bool> = interner
+            .iter()
+            .map(|(_, name)| name.ends_with('Z'))
+            .collect();
+
+        let cycles = benchmark!("Finding cycles", {
+            start_nodes
+                .iter()
+                .map(|node| Cycle::find(*node, &nodes, &instructions, &end_nodes))
+                .collect::<Result<Vec<Cycle>, _>>()?
+        });
 
-        let mut iterations = 0;
+        // Before every path has entered its loop the steps are simply checked one by one.
+        let all_looping = cycles.iter().map(|cycle| cycle.start).max().unwrap_or(0);
+        if let Some(step) = (1..all_looping).find(|step| cycles.iter().all(|c| c.is_at_end(*step)))
+        {
+            return Ok(step);
+        }
 
-        benchmark!("Calculation loop", {
-            loop {
-                let instruction = instructions[iterations % instructions.len()] as usize;
-                for node in current_nodes.iter_mut() {
-                    *node = nodes.get(node).ok_or("A requested node did not exist!")?[instruction];
-                }
-                iterations += 1;
+        // After that every combination of end node steps inside the loops is tried.
+        let mut first_step: Option<u64> = None;
+        let mut chosen_ends: Vec<usize> = vec![0; cycles.len()];
+        if cycles.iter().any(|cycle| cycle.loop_ends.is_empty()) {
+            return Err("Some path never reaches an end node again!".into());
+        }
+        loop {
+            let ends: Vec<u64> = cycles
+                .iter()
+                .zip(&chosen_ends)
+                .map(|(cycle, index)| cycle.loop_ends[*index])
+                .collect();
+            if let Some(step) = first_common_step(&cycles, &ends, all_looping.max(1)) {
+                first_step = Some(first_step.map_or(step, |first| first.min(step)));
+            }
 
-                if current_nodes.iter().all(|n| n % 26 == 25) {
+            // Count through the combinations like an odometer.
+            let mut position = 0;
+            while position < cycles.len() {
+                chosen_ends[position] += 1;
+                if chosen_ends[position] < cycles[position].loop_ends.len() {
                     break;
                 }
+                chosen_ends[position] = 0;
+                position += 1;
             }
-        });
+            if position == cycles.len() {
+                break;
+            }
+        }
 
-        Ok(iterations as u64)
+        Ok(first_step.ok_or("The paths are never at end nodes at the same time!")?)
     }
 }
 
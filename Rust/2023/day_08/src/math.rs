@@ -0,0 +1,408 @@
+use std::fmt::{self, Debug, Display};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathError {
+    /// A value or an intermediate result did not fit in the integer type.
+    Overflow,
+    /// A modulus was zero or negative.
+    InvalidModulus,
+    /// The equations have no integer solution.
+    NoSolution,
+    /// Both coefficients of a linear equation were zero, making every or no pair a solution.
+    Degenerate,
+}
+
+impl Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MathError::Overflow => write!(f, "Integer overflow"),
+            MathError::InvalidModulus => write!(f, "Modulus must be positive"),
+            MathError::NoSolution => write!(f, "No integer solution exists"),
+            MathError::Degenerate => write!(f, "Both coefficients are zero"),
+        }
+    }
+}
+
+impl std::error::Error for MathError {}
+
+/// Signed integer types the functions in this module work with.
+pub trait Integer: Copy + Ord + Debug + Display {
+    const ZERO: Self;
+    const ONE: Self;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_rem(self, other: Self) -> Option<Self>;
+    fn checked_neg(self) -> Option<Self>;
+    fn checked_rem_euclid(self, other: Self) -> Option<Self>;
+
+    /// Returns self * other % modulus for values already reduced to 0..modulus, without
+    /// overflowing.
+    fn mul_mod(self, other: Self, modulus: Self) -> Self;
+}
+
+macro_rules! impl_integer_common {
+    ($type:ty) => {
+        const ZERO: Self = 0;
+        const ONE: Self = 1;
+        const MIN: Self = <$type>::MIN;
+        const MAX: Self = <$type>::MAX;
+
+        fn checked_add(self, other: Self) -> Option<Self> {
+            <$type>::checked_add(self, other)
+        }
+        fn checked_sub(self, other: Self) -> Option<Self> {
+            <$type>::checked_sub(self, other)
+        }
+        fn checked_mul(self, other: Self) -> Option<Self> {
+            <$type>::checked_mul(self, other)
+        }
+        fn checked_div(self, other: Self) -> Option<Self> {
+            <$type>::checked_div(self, other)
+        }
+        fn checked_rem(self, other: Self) -> Option<Self> {
+            <$type>::checked_rem(self, other)
+        }
+        fn checked_neg(self) -> Option<Self> {
+            <$type>::checked_neg(self)
+        }
+        fn checked_rem_euclid(self, other: Self) -> Option<Self> {
+            <$type>::checked_rem_euclid(self, other)
+        }
+    };
+}
+
+macro_rules! impl_integer {
+    // Multiplies in a wider type, which can't overflow for reduced values.
+    ($type:ty, $wide:ty) => {
+        impl Integer for $type {
+            impl_integer_common!($type);
+
+            fn mul_mod(self, other: Self, modulus: Self) -> Self {
+                (self as $wide * other as $wide % modulus as $wide) as $type
+            }
+        }
+    };
+    // No wider type is available, so the product is built by doubling and adding instead.
+    ($type:ty) => {
+        impl Integer for $type {
+            impl_integer_common!($type);
+
+            fn mul_mod(self, other: Self, modulus: Self) -> Self {
+                // a + b mod m, written so that a + b never has to be stored.
+                let add_mod = |a: Self, b: Self| match a >= modulus - b {
+                    true => a - (modulus - b),
+                    false => a + b,
+                };
+
+                let (mut result, mut base, mut factor) = (0, self, other);
+                while factor > 0 {
+                    if factor & 1 == 1 {
+                        result = add_mod(result, base);
+                    }
+                    base = add_mod(base, base);
+                    factor >>= 1;
+                }
+                result
+            }
+        }
+    };
+}
+
+impl_integer!(i64, i128);
+impl_integer!(i128);
+
+fn overflow<T>(value: Option<T>) -> Result<T, MathError> {
+    value.ok_or(MathError::Overflow)
+}
+
+fn abs<T: Integer>(value: T) -> Result<T, MathError> {
+    match value < T::ZERO {
+        true => overflow(value.checked_neg()),
+        false => Ok(value),
+    }
+}
+
+fn check_modulus<T: Integer>(modulus: T) -> Result<(), MathError> {
+    match modulus > T::ZERO {
+        true => Ok(()),
+        false => Err(MathError::InvalidModulus),
+    }
+}
+
+/// Returns the greatest common divisor of a and b, which is never negative. gcd(0, 0) is 0.
+pub fn gcd<T: Integer>(a: T, b: T) -> Result<T, MathError> {
+    let (mut a, mut b) = (a, b);
+    while b != T::ZERO {
+        // The remainder only overflows for MIN % -1, which is 0.
+        let remainder = a.checked_rem(b).unwrap_or(T::ZERO);
+        (a, b) = (b, remainder);
+    }
+    abs(a)
+}
+
+/// Returns the least common multiple of a and b, which is never negative. lcm(0, x) is 0.
+pub fn lcm<T: Integer>(a: T, b: T) -> Result<T, MathError> {
+    if a == T::ZERO || b == T::ZERO {
+        return Ok(T::ZERO);
+    }
+    let divisor = gcd(a, b)?;
+    abs(overflow(overflow(a.checked_div(divisor))?.checked_mul(b))?)
+}
+
+/// Returns the greatest common divisor of all values, 0 for an empty slice.
+pub fn gcd_all<T: Integer>(values: &[T]) -> Result<T, MathError> {
+    values
+        .iter()
+        .try_fold(T::ZERO, |result, value| gcd(result, *value))
+}
+
+/// Returns the least common multiple of all values, 1 for an empty slice.
+pub fn lcm_all<T: Integer>(values: &[T]) -> Result<T, MathError> {
+    values
+        .iter()
+        .try_fold(T::ONE, |result, value| lcm(result, *value))
+}
+
+/// Returns (g, x, y) where g = gcd(a, b) and a * x + b * y = g.
+pub fn extended_gcd<T: Integer>(a: T, b: T) -> Result<(T, T, T), MathError> {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::ONE, T::ZERO);
+    let (mut old_y, mut y) = (T::ZERO, T::ONE);
+
+    while r != T::ZERO {
+        let quotient = overflow(old_r.checked_div(r))?;
+        let step = |old: T, new: T| overflow(old.checked_sub(overflow(quotient.checked_mul(new))?));
+        (old_r, r) = (r, step(old_r, r)?);
+        (old_x, x) = (x, step(old_x, x)?);
+        (old_y, y) = (y, step(old_y, y)?);
+    }
+
+    // Flip the signs of everything if the divisor came out negative.
+    match old_r < T::ZERO {
+        true => Ok((
+            overflow(old_r.checked_neg())?,
+            overflow(old_x.checked_neg())?,
+            overflow(old_y.checked_neg())?,
+        )),
+        false => Ok((old_r, old_x, old_y)),
+    }
+}
+
+/// Returns the x in 0..modulus for which value * x = 1 (mod modulus).
+pub fn mod_inverse<T: Integer>(value: T, modulus: T) -> Result<T, MathError> {
+    check_modulus(modulus)?;
+    let value = overflow(value.checked_rem_euclid(modulus))?;
+    let (divisor, x, _) = extended_gcd(value, modulus)?;
+    if divisor != T::ONE {
+        return Err(MathError::NoSolution);
+    }
+    overflow(x.checked_rem_euclid(modulus))
+}
+
+/// Returns base^exponent % modulus, in 0..modulus.
+pub fn mod_pow<T: Integer>(base: T, exponent: u64, modulus: T) -> Result<T, MathError> {
+    check_modulus(modulus)?;
+    let mut base = overflow(base.checked_rem_euclid(modulus))?;
+    let mut result = overflow(T::ONE.checked_rem_euclid(modulus))?;
+    let mut exponent = exponent;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.mul_mod(base, modulus);
+        }
+        base = base.mul_mod(base, modulus);
+        exponent >>= 1;
+    }
+    Ok(result)
+}
+
+/// Solves the system x = residue (mod modulus) for every (residue, modulus) pair.
+///
+/// The moduli don't have to be coprime. Returns (x, lcm of the moduli) where x is the smallest
+/// non-negative solution, or NoSolution if the congruences contradict each other.
+pub fn crt<T: Integer>(congruences: &[(T, T)]) -> Result<(T, T), MathError> {
+    let (mut result, mut result_modulus) = (T::ZERO, T::ONE);
+
+    for (residue, modulus) in congruences {
+        check_modulus(*modulus)?;
+        let residue = overflow(residue.checked_rem_euclid(*modulus))?;
+
+        // Look for result + result_modulus * k = residue (mod modulus), which only has a solution
+        // if the difference is divisible by the common factor of the moduli.
+        let divisor = gcd(result_modulus, *modulus)?;
+        let difference = overflow(residue.checked_sub(result))?;
+        if overflow(difference.checked_rem(divisor))? != T::ZERO {
+            return Err(MathError::NoSolution);
+        }
+
+        let reduced_modulus = overflow(modulus.checked_div(divisor))?;
+        let inverse = mod_inverse(
+            overflow(result_modulus.checked_div(divisor))?,
+            reduced_modulus,
+        )?;
+        let k = overflow(
+            overflow(difference.checked_div(divisor))?.checked_rem_euclid(reduced_modulus),
+        )?
+        .mul_mod(inverse, reduced_modulus);
+
+        // Both steps stay below the new modulus, so only the modulus itself can overflow.
+        let combined_modulus = overflow(result_modulus.checked_mul(reduced_modulus))?;
+        result = overflow(result.checked_add(overflow(result_modulus.checked_mul(k))?))?;
+        result_modulus = combined_modulus;
+    }
+
+    Ok((result, result_modulus))
+}
+
+/// Every integer solution of a * x + b * y = c, given as
+/// (x + k * x_step, y + k * y_step) for any integer k.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diophantine<T> {
+    pub x: T,
+    pub y: T,
+    pub x_step: T,
+    pub y_step: T,
+}
+
+/// Finds the integer solutions of a * x + b * y = c.
+pub fn solve_diophantine<T: Integer>(a: T, b: T, c: T) -> Result<Diophantine<T>, MathError> {
+    if a == T::ZERO && b == T::ZERO {
+        return Err(MathError::Degenerate);
+    }
+
+    let (divisor, x, y) = extended_gcd(a, b)?;
+    if overflow(c.checked_rem(divisor))? != T::ZERO {
+        return Err(MathError::NoSolution);
+    }
+
+    let scale = overflow(c.checked_div(divisor))?;
+    Ok(Diophantine {
+        x: overflow(x.checked_mul(scale))?,
+        y: overflow(y.checked_mul(scale))?,
+        x_step: overflow(b.checked_div(divisor))?,
+        y_step: overflow(overflow(a.checked_div(divisor))?.checked_neg())?,
+    })
+}
+
+impl<T: Integer> Diophantine<T> {
+    /// Returns the solution for the given k.
+    pub fn solution(&self, k: T) -> Result<(T, T), MathError> {
+        let step = |start: T, step: T| overflow(start.checked_add(overflow(step.checked_mul(k))?));
+        Ok((step(self.x, self.x_step)?, step(self.y, self.y_step)?))
+    }
+
+    /// Returns the range of k for which both x and y are non-negative, or None if there are no
+    /// such solutions. Sides without a bound are MIN or MAX.
+    pub fn non_negative_range(&self) -> Result<Option<RangeInclusive<T>>, MathError> {
+        let (mut lower, mut upper) = (T::MIN, T::MAX);
+        for (start, step) in [(self.x, self.x_step), (self.y, self.y_step)] {
+            // start + k * step >= 0
+            match step.cmp(&T::ZERO) {
+                std::cmp::Ordering::Greater => {
+                    lower = lower.max(div_ceil(overflow(start.checked_neg())?, step)?)
+                }
+                std::cmp::Ordering::Less => {
+                    upper = upper.min(div_floor(start, overflow(step.checked_neg())?)?)
+                }
+                std::cmp::Ordering::Equal if start < T::ZERO => return Ok(None),
+                std::cmp::Ordering::Equal => {}
+            }
+        }
+        Ok((lower <= upper).then_some(lower..=upper))
+    }
+}
+
+/// Division rounding towards negative infinity, for a positive divisor.
+fn div_floor<T: Integer>(value: T, divisor: T) -> Result<T, MathError> {
+    let remainder = overflow(value.checked_rem_euclid(divisor))?;
+    overflow(overflow(value.checked_sub(remainder))?.checked_div(divisor))
+}
+
+/// Division rounding towards positive infinity, for a positive divisor.
+fn div_ceil<T: Integer>(value: T, divisor: T) -> Result<T, MathError> {
+    let floor = div_floor(value, divisor)?;
+    match overflow(value.checked_rem_euclid(divisor))? == T::ZERO {
+        true => Ok(floor),
+        false => overflow(floor.checked_add(T::ONE)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(48i64, -18), Ok(6));
+        assert_eq!(gcd(0i64, 0), Ok(0));
+        assert_eq!(lcm(4i64, 6), Ok(12));
+        assert_eq!(lcm_all(&[2i64, 3, 4, 5]), Ok(60));
+        assert_eq!(gcd_all(&[12i128, 18, 30]), Ok(6));
+        assert_eq!(gcd(i64::MIN, 0), Err(MathError::Overflow));
+        assert_eq!(lcm(i64::MAX, i64::MAX - 1), Err(MathError::Overflow));
+    }
+
+    #[test]
+    fn extended_gcd_and_inverse() {
+        let (g, x, y) = extended_gcd(240i64, 46).unwrap();
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+        let (g, x, y) = extended_gcd(-240i64, 46).unwrap();
+        assert_eq!(g, 2);
+        assert_eq!(-240 * x + 46 * y, 2);
+
+        assert_eq!(mod_inverse(3i64, 11), Ok(4));
+        assert_eq!(mod_inverse(-3i64, 11), Ok(7));
+        assert_eq!(mod_inverse(4i64, 8), Err(MathError::NoSolution));
+        assert_eq!(mod_inverse(4i64, 0), Err(MathError::InvalidModulus));
+    }
+
+    #[test]
+    fn mod_pow_large_modulus() {
+        assert_eq!(mod_pow(4i64, 13, 497), Ok(445));
+        assert_eq!(mod_pow(2i64, 0, 1), Ok(0));
+
+        // Fermat's little theorem with a prime whose square doesn't fit in 64 bits.
+        let prime: i64 = 2305843009213693951;
+        assert_eq!(mod_pow(123456789i64, prime as u64 - 1, prime), Ok(1));
+
+        // A prime whose square doesn't fit in 128 bits either.
+        let prime: i128 = 170141183460469231731687303715884105727;
+        assert_eq!((prime - 1).mul_mod(prime - 1, prime), 1);
+        assert_eq!(mod_pow(prime - 2, 2, prime), Ok(4));
+    }
+
+    #[test]
+    fn crt_coprime_and_shared_factors() {
+        assert_eq!(crt(&[(2i64, 3), (3, 5), (2, 7)]), Ok((23, 105)));
+        assert_eq!(crt(&[(2i64, 4), (4, 6)]), Ok((10, 12)));
+        assert_eq!(crt(&[(1i64, 4), (2, 6)]), Err(MathError::NoSolution));
+        assert_eq!(crt::<i64>(&[]), Ok((0, 1)));
+        assert_eq!(
+            crt(&[(1i64, i64::MAX), (0, i64::MAX - 1)]),
+            Err(MathError::Overflow)
+        );
+        assert!(crt(&[(1i128, i64::MAX as i128), (0, i64::MAX as i128 - 1)]).is_ok());
+    }
+
+    #[test]
+    fn diophantine_non_negative_solutions() {
+        let solutions = solve_diophantine(3i64, 5, 31).unwrap();
+        let range = solutions.non_negative_range().unwrap().unwrap();
+        let mut found: Vec<(i64, i64)> = range.map(|k| solutions.solution(k).unwrap()).collect();
+        found.sort();
+        assert_eq!(found, vec![(2, 5), (7, 2)]);
+
+        assert_eq!(solve_diophantine(4i64, 6, 7), Err(MathError::NoSolution));
+        assert_eq!(solve_diophantine(0i64, 0, 7), Err(MathError::Degenerate));
+        assert_eq!(
+            solve_diophantine(3i64, 5, 2).unwrap().non_negative_range(),
+            Ok(None)
+        );
+    }
+}
@@ -85,3 +85,12 @@ fn calculate_part_two_full() {
             Err(err) => panic!("Part Two failed with error:\n{}\n", err),
         }
 }
+
+#[test]
+fn calculate_part_two_digit_names() {
+    // The puzzle's own example, where the node names contain digits.
+    match part_two::calculate("testdata_3.txt") {
+        Ok(value) => assert_eq!(value, 6),
+        Err(err) => panic!("Part Two failed with error:\n{}\n", err),
+    }
+}
@@ -1,3 +1,8 @@
+mod bitset;
+use bitset::{BitMatrix, BitSet};
+mod graph;
+use graph::Graph;
+
 mod operations;
 use operations::Operation;
 use operations::OperationResult;
@@ -15,7 +20,8 @@ fn main() {
     let mut read_rules: bool = true;
     let mut total: u32 = 0;
 
-    let mut rules: [[bool; 100]; 100] = [[false; 100]; 100];
+    let mut rules = Graph::with_nodes(100);
+    let mut rule_matrix: Option<BitMatrix> = None;
 
     for line in reader {
         let mut buffer: [u8; 23] = [0; 23];
@@ -42,10 +48,12 @@ fn main() {
         //println!("Buffer: {:?}  |  Lenght: {}", buffer, index);
 
         if read_rules {
-            rules[buffer[0] as usize][buffer[1] as usize] = true;
+            rules.add_edge(buffer[0] as usize, buffer[1] as usize);
         } else {
+            // All rules have been read by now, so the matrix only has to be built once.
+            let rule_matrix = rule_matrix.get_or_insert_with(|| rules.adjacency_matrix());
             //if let Some(_) = get_value_if_valid(buffer, index, &rules) {
-            if get_value_if_valid(buffer, index, &rules).is_some() {
+            if get_value_if_valid(buffer, index, rule_matrix).is_some() {
                 continue;
             }
 
@@ -58,56 +66,42 @@ fn main() {
     println!("Total is: {total}");
 }
 
-fn get_value_if_valid(update: [u8; 23], length: usize, rules: &[[bool; 100]; 100]) -> Option<u8> {
+fn get_value_if_valid(update: [u8; 23], length: usize, rules: &BitMatrix) -> Option<u8> {
     println!("Checking rules...");
 
-    for page_index in 0..length {
-        for i in 0..page_index {
-            // If page_index and i is a rule where page_index must be printed before i return none.
-            // Since i only loops up to page_index it means that if page_index must be printed
-            // before i the order violates the rules.
-            if rules[update[page_index] as usize][update[i] as usize] {
-                return None;
-            }
+    let mut printed = BitSet::new(rules.columns());
+    for page in &update[..length] {
+        // The row of a page holds every page it must be printed before. If any of those has
+        // already been printed the order violates the rules.
+        if rules.row(*page as usize).intersects(&printed) {
+            return None;
         }
+        printed.insert(*page as usize);
     }
 
     Some(update[length / 2])
 }
 
-fn reorder_and_get_value(mut update: [u8; 23], length: usize, rules: &[[bool; 100]; 100]) -> u8 {
-    for page_index in 0..length {
-        for i in 0..page_index {
-            if order_is_wrong(update[page_index], update[i], rules) {
-                update.swap(page_index, i);
-                return reorder_and_get_value(update, length, rules);
-            }
-        }
-    }
-
-    update[length / 2]
-
-    /*
-    for i in (0..length) {
-        for page_number in next_index..length {
+fn reorder_and_get_value(update: [u8; 23], length: usize, rules: &Graph) -> u8 {
+    // The full rule set loops back on itself, but the rules between the pages of a single update
+    // never do. So only the pages in the update are sorted.
+    let pages: Vec<usize> = update[..length].iter().map(|page| *page as usize).collect();
+    let ordered = rules
+        .topological_order_within(&pages)
+        .expect("The rules for a single update should never contain a cycle!");
 
-            //
-        }
-    }*/
-}
-
-fn order_is_wrong(first_value: u8, second_value: u8, rules: &[[bool; 100]; 100]) -> bool {
-    rules[first_value as usize][second_value as usize]
-}
-
-fn swap_index(index_1: usize, index_2: usize, array: &mut [u8; 23]) {
-    array.swap(index_1, index_2);
+    ordered[length / 2] as u8
 }
 
 /*
 
 RULE FORMAT:
-rules[value which must be before][this value]
+rules has a edge from the value which must be before to this value.
+
+Update: Checking if a update is valid used to compare every page with every page before it. The
+rules are now also turned into a bit matrix, where the row of a page has a bit set for each page
+it must come before. Keeping the printed pages in a bit set, a page breaks the rules if its row
+and the printed pages have any bit in common, which checks 64 pages at a time.
 
 Part 2 todo:
 
@@ -3,58 +3,21 @@ use std::io;
 mod reader;
 use reader::get_lines;
 
-mod vector;
-use vector::Vector;
+mod grid_region;
+use grid_region::Region;
 
 mod testing_debug;
 
 const GRIDSIZE: usize = 140;
 
 struct PlotMap {
-    map: [[Plot; GRIDSIZE]; GRIDSIZE],
+    map: [[char; GRIDSIZE]; GRIDSIZE],
     size: usize,
 }
 
-impl PlotMap {
-    fn get_plot(&self, coordinates: Vector) -> Result<Plot, AdventError> {
-        if coordinates.out_of_bounds(self.size) {
-            Err(AdventError::OutOfBounds(String::from(
-                "Requested plot at {coordinates:?} is outside of the grid!",
-            )))
-        } else {
-            Ok(self.map[coordinates.x as usize][coordinates.y as usize])
-        }
-    }
-
-    fn set_plot(&mut self, coordinates: Vector, plot: Plot) -> Result<bool, AdventError> {
-        if coordinates.out_of_bounds(self.size) {
-            Err(AdventError::OutOfBounds(String::from(
-                "Plot placement at {coordinates:?} is outside of the grid!",
-            )))
-        } else {
-            self.map[coordinates.x as usize][coordinates.y as usize] = plot;
-            Ok(true)
-        }
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Plot {
-    plant: char,
-    in_region: bool,
-}
-
-#[derive(Debug)]
-struct Region {
-    area: u16,
-    corners: u16,
-    plant: char,
-}
-
 #[derive(Debug)]
 enum AdventError {
     IoError(io::Error),
-    OutOfBounds(String),
 }
 
 impl From<io::Error> for AdventError {
@@ -66,10 +29,7 @@ impl From<io::Error> for AdventError {
 fn get_map(path: &str) -> Result<PlotMap, AdventError> {
     let lines = get_lines(path)?;
     let mut plot_map = PlotMap {
-        map: [[Plot {
-            plant: ' ',
-            in_region: false,
-        }; GRIDSIZE]; GRIDSIZE],
+        map: [[' '; GRIDSIZE]; GRIDSIZE],
         size: GRIDSIZE,
     };
 
@@ -77,13 +37,7 @@ fn get_map(path: &str) -> Result<PlotMap, AdventError> {
 
     for (y, line) in lines.enumerate() {
         for (x, char) in line.chars().enumerate() {
-            let _ = plot_map.set_plot(
-                Vector::from((x, y)),
-                Plot {
-                    plant: char,
-                    in_region: false,
-                },
-            )?;
+            plot_map.map[x][y] = char;
         }
         size = y;
     }
@@ -93,133 +47,26 @@ fn get_map(path: &str) -> Result<PlotMap, AdventError> {
     Ok(plot_map)
 }
 
-fn scan_region(
-    map: &mut PlotMap,
-    coordinates: Vector,
-    plant: char,
-) -> Result<(u16, u16), AdventError> {
-    let mut plot: Plot = map.get_plot(coordinates)?;
-    plot.in_region = true;
-    let _ = map.set_plot(coordinates, plot)?;
-
-    let (mut area, mut corners) = (1, 0);
-    corners += get_corners(coordinates, map, plant);
-
-    let (result_area, result_corners) = scan_neighbours(coordinates, map, plant)?;
-    area += result_area;
-    corners += result_corners;
-
-    Ok((area, corners))
-}
-
-fn get_corners(coordinates: Vector, map: &PlotMap, plant: char) -> u16 {
-    let mut corners: u16 = 0;
-
-    // Set perimeter_to_neighbour based on the plot to the left to make sure the first neighbour is
-    // compared to the last one. With the loop approach we would otherwise only compare 3 times
-    // instead of 4.
-    let directions: [Vector; 4] = Vector::directions();
-    let mut perimeter_to_neighbour: bool = match map.get_plot(coordinates + directions[3]) {
-        Ok(plot) => plot.plant != plant,
-        Err(_) => true,
-    };
-    for (direction_index, neighbour) in directions.iter().enumerate() {
-        let Ok(plot) = map.get_plot(*neighbour) else {
-            if perimeter_to_neighbour {
-                corners += 1;
-            }
-            perimeter_to_neighbour = true;
-            continue;
-        };
-
-        let perimeter_found: bool = plot.plant != plant;
-        let perform_inwards_corner_scan: bool = perimeter_found != perimeter_to_neighbour;
-
-        if perimeter_found {
-            if perimeter_to_neighbour {
-                corners += 1;
-            }
-            perimeter_to_neighbour = true;
-        } else {
-            perimeter_to_neighbour = false;
-        }
-
-        if perform_inwards_corner_scan {
-            corners += corner_scan(*neighbour, direction_index, map, plant);
-        }
-    }
-    corners
-}
-
-fn scan_neighbours(
-    coordinates: Vector,
-    map: &mut PlotMap,
-    plant: char,
-) -> Result<(u16, u16), AdventError> {
-    let (mut area, mut corners) = (0, 0);
-    for neighbour in coordinates.neighbours() {
-        let Ok(plot) = map.get_plot(neighbour) else {
-            continue;
-        };
-        if plot.in_region || plot.plant != plant {
-            continue;
-        }
-
-        let (result_area, result_corners) = scan_region(map, neighbour, plant)?;
-        area += result_area;
-        corners += result_corners;
-    }
-    Ok((area, corners))
-}
-
-fn corner_scan(neighbour: Vector, direction_index: usize, map: &PlotMap, plant: char) -> u16 {
-    let diagonal_neighbour = neighbour
-        + Vector::directions()[match direction_index {
-            0 => 3,
-            _ => direction_index - 1,
-        }];
-
-    let Ok(plot) = map.get_plot(diagonal_neighbour) else {
-        return 0;
-    };
-    if plot.plant == plant && !plot.in_region {
-        1
-    } else {
-        0
-    }
-}
-
-fn calculate_cost(regions: Vec<Region>) -> u64 {
+fn calculate_cost(regions: &[Region]) -> u64 {
     let mut result: u64 = 0;
 
     for region in regions {
-        result += region.corners as u64 * region.area as u64;
+        result += region.sides * region.area;
     }
     result
 }
 
 fn calculate(path: &str) -> Result<u64, AdventError> {
-    let mut plot_map = get_map(path)?;
-
-    let mut regions: Vec<Region> = Vec::new();
-
-    for y in 0..plot_map.size {
-        for x in 0..plot_map.size {
-            let plot = plot_map.get_plot(Vector::from((x, y)))?;
-            if plot.in_region {
-                continue;
-            }
-
-            let (area, corners) = scan_region(&mut plot_map, Vector::from((x, y)), plot.plant)?;
-            regions.push(Region {
-                area,
-                corners,
-                plant: plot.plant,
-            });
-        }
-    }
+    let plot_map = get_map(path)?;
+
+    let region_map = grid_region::label_regions(
+        &plot_map.map,
+        plot_map.size,
+        plot_map.size,
+        |plant_a, plant_b| plant_a == plant_b,
+    );
 
-    Ok(calculate_cost(regions))
+    Ok(calculate_cost(region_map.regions()))
 }
 
 fn main() {
@@ -326,5 +173,9 @@ For any perimeter direction which does not connect to another perimeter:
 
 ---------------------------------------------------------------------------------------------------------------------------------------------
 
+Update:
+The region scanning and corner counting has been moved to the shared grid_region module so other
+grid puzzles can use it too. It counts the corners of each tile after the region has been labeled
+instead of during the scan, which removes the need for the in_region trick above.
 
 */
@@ -1,5 +1,9 @@
 use std::io;
 
+mod linear_algebra;
+use linear_algebra::{solve, Matrix, Rational};
+mod math;
+
 mod operations;
 use operations::Operation;
 use operations::OperationResult;
@@ -8,8 +12,6 @@ mod reader;
 use reader::get_lines;
 mod testing_debug;
 mod vector;
-use vector::VectorF64;
-use vector::VectorI16;
 use vector::VectorI64;
 
 #[derive(Debug)]
@@ -30,6 +32,8 @@ struct ClawMachine {
     prize: VectorI64,
 }
 
+const PART_TWO_PRIZE_OFFSET: i64 = 10000000000000;
+
 impl From<&mut [i64; 6]> for ClawMachine {
     fn from(values: &mut [i64; 6]) -> Self {
         ClawMachine {
@@ -42,13 +46,16 @@ impl From<&mut [i64; 6]> for ClawMachine {
                 y: values[3],
             },
             prize: VectorI64 {
-                x: values[4] + 10000000000000,
-                y: values[5] + 10000000000000,
+                x: values[4],
+                y: values[5],
             },
         }
     }
 }
 
+const BUTTON_A_COST: i64 = 3;
+const BUTTON_B_COST: i64 = 1;
+
 impl ClawMachine {
     fn calculate_cost(self) -> Option<u64> {
         testonly_println!(
@@ -58,22 +65,55 @@ impl ClawMachine {
             self.prize
         );
 
-        let b_inverted = self.button_b * -1;
-
-        let (button_a_pressed, button_b_pressed) = VectorF64::line_line_intersection_get_scalars(
-            VectorF64 { x: 0.0, y: 0.0 },
-            VectorF64::from(self.button_a),
-            VectorF64::from(self.prize),
-            VectorF64::from(b_inverted),
-        );
+        let (a, b, prize) = (self.button_a, self.button_b, self.prize);
+        let buttons = Matrix::from_rows(vec![
+            vec![Rational::from(a.x), Rational::from(b.x)],
+            vec![Rational::from(a.y), Rational::from(b.y)],
+        ]);
+        let presses = solve(
+            &buttons,
+            &[Rational::from(prize.x), Rational::from(prize.y)],
+        )?;
+
+        if presses.is_unique() {
+            // The buttons point in different directions, so there is exactly one way to reach the
+            // prize. The presses have to be whole numbers though.
+            let presses = presses.solution(&[]);
+            let a_pressed = i64::try_from(presses[0].to_integer()?).ok()?;
+            let b_pressed = i64::try_from(presses[1].to_integer()?).ok()?;
+            if a_pressed < 0 || b_pressed < 0 {
+                return None;
+            }
+            return Some((a_pressed * BUTTON_A_COST + b_pressed * BUTTON_B_COST) as u64);
+        }
 
-        //testonly_println!("Result: {:?}\nWhole number: {:?} {:?}", result, a, b);
+        self.calculate_cost_parallel()
+    }
 
-        if button_a_pressed % 1.0 != 0.0 || button_b_pressed % 1.0 != 0.0 {
-            return None;
-        }
+    /// Cost for machines where both buttons move the claw along the same line as the prize.
+    fn calculate_cost_parallel(self) -> Option<u64> {
+        let (a, b, prize) = (self.button_a, self.button_b, self.prize);
 
-        Some(button_a_pressed as u64 * 3 + button_b_pressed as u64)
+        // Any axis the buttons move along works, both give the same equation.
+        let presses = match (a.x, b.x) {
+            (0, 0) if (a.y, b.y) == (0, 0) => {
+                return (prize == VectorI64 { x: 0, y: 0 }).then_some(0)
+            }
+            (0, 0) => math::solve_diophantine(a.y, b.y, prize.y).ok()?,
+            _ => math::solve_diophantine(a.x, b.x, prize.x).ok()?,
+        };
+
+        // The cost changes by the same amount for each step k, so the cheapest solution is at one
+        // of the ends of the range where both press counts are non-negative.
+        let range = presses.non_negative_range().ok()??;
+        let cost_step = presses.x_step * BUTTON_A_COST + presses.y_step * BUTTON_B_COST;
+        let k = match cost_step >= 0 {
+            true => *range.start(),
+            false => *range.end(),
+        };
+
+        let (a_pressed, b_pressed) = presses.solution(k).ok()?;
+        Some((a_pressed * BUTTON_A_COST + b_pressed * BUTTON_B_COST) as u64)
     }
 }
 /* Sudo code:
@@ -97,11 +137,24 @@ add the above result together with the result of condition 3.
 
 Then you have the cost to win that machine.
 
+Update: The floating point intersection has been replaced with integer math. Writing out the two
+equations:
+a * Va_x + b * Vb_x = P_x
+a * Va_y + b * Vb_y = P_y
+Cramer's rule solves them exactly, and the presses are only valid if the divisions have no
+remainder. This also avoids the rounding errors floats get with the large part two values.
 
+Update: The equations are now solved by the shared linear algebra module, which row reduces them
+using exact fractions. Besides the presses it also tells if there is exactly one solution, no
+solution, or a whole line of them when the buttons point in the same direction.
 
-Challenge part 2:
-
+If the two buttons point in the same direction the equations don't have a single answer. Then
+the shared math module solves a * Va_x + b * Vb_x = P_x as a linear diophantine equation, and the
+cheapest of the solutions with no negative presses is picked.
 
+Challenge part 2:
+Same as part 1 but with 10000000000000 added to the prize coordinates. Thanks to the integer math
+this only needs the offset to be added.
 
 */
 
@@ -111,6 +164,7 @@ fn handle_operation_result(
     operation_result: Option<OperationResult>,
     number_cache: &mut [i64; CACHESIZE],
     index: &mut usize,
+    prize_offset: i64,
 ) -> Option<ClawMachine> {
     let Some(result) = operation_result else {
         return None;
@@ -123,14 +177,17 @@ fn handle_operation_result(
 
             if *index >= CACHESIZE {
                 *index = 0;
-                return Some(ClawMachine::from(number_cache));
+                let mut claw_machine = ClawMachine::from(number_cache);
+                claw_machine.prize.x += prize_offset;
+                claw_machine.prize.y += prize_offset;
+                return Some(claw_machine);
             }
         }
     }
     None
 }
 
-fn calculate(path: &str) -> Result<u64, AdventError> {
+fn calculate(path: &str, prize_offset: i64) -> Result<u64, AdventError> {
     //let machines = get_machines(path)?;
 
     let mut number_cache: [i64; CACHESIZE] = [0; CACHESIZE];
@@ -146,9 +203,12 @@ fn calculate(path: &str) -> Result<u64, AdventError> {
             let (new_state, potential_result) = state.next(char);
             state = new_state;
 
-            let Some(claw_machine) =
-                handle_operation_result(potential_result, &mut number_cache, &mut index)
-            else {
+            let Some(claw_machine) = handle_operation_result(
+                potential_result,
+                &mut number_cache,
+                &mut index,
+                prize_offset,
+            ) else {
                 continue;
             };
 
@@ -159,9 +219,12 @@ fn calculate(path: &str) -> Result<u64, AdventError> {
     }
 
     let (_, potential_result) = state.next(' ');
-    if let Some(claw_machine) =
-        handle_operation_result(potential_result, &mut number_cache, &mut index)
-    {
+    if let Some(claw_machine) = handle_operation_result(
+        potential_result,
+        &mut number_cache,
+        &mut index,
+        prize_offset,
+    ) {
         if let Some(cost) = claw_machine.calculate_cost() {
             tokens += cost;
         }
@@ -171,15 +234,19 @@ fn calculate(path: &str) -> Result<u64, AdventError> {
 }
 
 fn main() {
-    match calculate("data.txt") {
+    match calculate("data.txt", 0) {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
     }
+    match calculate("data.txt", PART_TWO_PRIZE_OFFSET) {
+        Err(err) => println!("An error occured: {err:?}"),
+        Ok(value) => println!("Part two result is: {}", value),
+    }
 }
 
 #[test]
 fn calculate_test() {
-    match calculate("testdata.txt") {
+    match calculate("testdata.txt", 0) {
         Err(err) => panic!("An error occured: {err:?}"),
         Ok(value) => assert_eq!(value, 480),
     }
@@ -187,12 +254,51 @@ fn calculate_test() {
 
 #[test]
 fn calculate_test_small() {
-    match calculate("smalltestdata.txt") {
+    match calculate("smalltestdata.txt", 0) {
         Err(err) => panic!("An error occured: {err:?}"),
         Ok(value) => assert_eq!(value, 280),
     }
 }
 
+#[test]
+fn calculate_test_part_two() {
+    match calculate("testdata.txt", PART_TWO_PRIZE_OFFSET) {
+        Err(err) => panic!("An error occured: {err:?}"),
+        Ok(value) => assert_eq!(value, 875318608908),
+    }
+}
+
+#[test]
+fn calculate_cost_parallel_buttons() {
+    let claw_machine = |a: (i64, i64), b: (i64, i64), prize: (i64, i64)| ClawMachine {
+        button_a: VectorI64 { x: a.0, y: a.1 },
+        button_b: VectorI64 { x: b.0, y: b.1 },
+        prize: VectorI64 {
+            x: prize.0,
+            y: prize.1,
+        },
+    };
+
+    // B is cheaper per step so it is pressed as much as possible: 1 A + 5 B.
+    assert_eq!(
+        claw_machine((3, 6), (2, 4), (13, 26)).calculate_cost(),
+        Some(8)
+    );
+    // A moves 4 times as far as B for 3 times the cost, so A is preferred: 3 A + 1 B.
+    assert_eq!(
+        claw_machine((4, 4), (1, 1), (13, 13)).calculate_cost(),
+        Some(10)
+    );
+    assert_eq!(
+        claw_machine((4, 4), (2, 2), (13, 13)).calculate_cost(),
+        None
+    );
+    assert_eq!(
+        claw_machine((4, 4), (2, 2), (14, 15)).calculate_cost(),
+        None
+    );
+}
+
 /* Sudo code:
 
 Challenge part 1:
@@ -1,5 +1,7 @@
 use std::io;
-use std::{thread, time};
+
+mod math;
+use math::MathError;
 
 mod operations;
 use operations::Operation;
@@ -15,6 +17,7 @@ use vector::VectorI16;
 #[derive(Debug)]
 enum AdventError {
     IoError(io::Error),
+    MathError(MathError),
 }
 
 impl From<io::Error> for AdventError {
@@ -23,6 +26,12 @@ impl From<io::Error> for AdventError {
     }
 }
 
+impl From<MathError> for AdventError {
+    fn from(err: MathError) -> Self {
+        Self::MathError(err)
+    }
+}
+
 const MAXROBOTCOUNT: usize = 500;
 const GRIDSIZEX: usize = 101;
 const GRIDSIZEY: usize = 103;
@@ -207,60 +216,94 @@ fn get_map(path: &str, size_override: (usize, usize)) -> Result<Map, AdventError
 fn calculate(path: &str, size_override: (usize, usize)) -> Result<u64, AdventError> {
     let mut map = get_map(path, size_override)?;
 
-    //map.display();
-
-    // Since there was no indication of how the christmas tree image was supposed to look like, I
-    // felt it was easiest to figure out a potential pattern and search that by printing the map.
-
-    // A strange pattern appears at:
-    // 4 107 210 313 ...
-    // With 103 added between each one.
-    // Running this results in moving 103 seconds ahead, printing the map each time. With a
-    // starting time at 4 seconds.
-    // After some iterations a christmas tree was found.
-    let t = 4;
     for i in 0..map.robot_count {
-        for _ in 0..t {
-            let mut robot = map.robots[i];
-            robot.apply_velocity_seconds(1);
-            robot.constrain_to_map(&map);
-            map.robots[i] = robot;
-        }
-    }
-    map.display();
-    println!("Seconds: {}", t);
-    println!("------------------------------------------------------------------------------------------------------");
-    thread::sleep(time::Duration::from_millis(1000));
-
-    for seconds in 1..3000 {
-        for i in 0..map.robot_count {
-            let mut robot = map.robots[i];
-            robot.apply_velocity_seconds(103);
-            robot.constrain_to_map(&map);
-            map.robots[i] = robot;
-        }
-
-        map.display();
-        println!("Seconds: {}", t + (seconds * 103));
-        println!("------------------------------------------------------------------------------------------------------");
-        thread::sleep(time::Duration::from_millis(500));
+        let mut robot = map.robots[i];
+        robot.apply_velocity_seconds(100);
+        robot.constrain_to_map(&map);
+        map.robots[i] = robot;
     }
 
     let quadrants = map.robots_in_quadrants();
-    println!("q: {:?}", quadrants);
+    testonly_println!("q: {:?}", quadrants);
 
     Ok(quadrants[0] * quadrants[1] * quadrants[2] * quadrants[3])
 }
 
+/// Returns the amount of seconds in 0..period at which the robots are the most bunched up along
+/// one axis. get_axis picks the (position, velocity) of that axis from a robot.
+fn most_clustered_second<F>(map: &Map, period: usize, get_axis: F) -> i64
+where
+    F: Fn(&Robot) -> (i64, i64),
+{
+    let robot_count = map.robot_count as i64;
+    let spread = |seconds: i64| {
+        let (mut sum, mut sum_squared) = (0, 0);
+        for robot in &map.robots[..map.robot_count] {
+            let (position, velocity) = get_axis(robot);
+            let value = (position + velocity * seconds).rem_euclid(period as i64);
+            sum += value;
+            sum_squared += value * value;
+        }
+        // Variance times robot_count squared, which keeps the math in integers.
+        robot_count * sum_squared - sum * sum
+    };
+
+    (0..period as i64)
+        .min_by_key(|seconds| spread(*seconds))
+        .unwrap_or(0)
+}
+
+fn calculate_part_two(path: &str, size_override: (usize, usize)) -> Result<u64, AdventError> {
+    let map = get_map(path, size_override)?;
+
+    let seconds_x = most_clustered_second(&map, map.world_size_x, |robot| {
+        (robot.position.x as i64, robot.velocity.x as i64)
+    });
+    let seconds_y = most_clustered_second(&map, map.world_size_y, |robot| {
+        (robot.position.y as i64, robot.velocity.y as i64)
+    });
+    testonly_println!(
+        "Clustered x at: {}, clustered y at: {}",
+        seconds_x,
+        seconds_y
+    );
+
+    let (seconds, _) = math::crt(&[
+        (seconds_x, map.world_size_x as i64),
+        (seconds_y, map.world_size_y as i64),
+    ])?;
+
+    Ok(seconds as u64)
+}
+
 fn main() {
     match calculate("data.txt", (GRIDSIZEX, GRIDSIZEY)) {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
     }
+
+    match calculate_part_two("data.txt", (GRIDSIZEX, GRIDSIZEY)) {
+        Err(err) => println!("An error occured: {err:?}"),
+        Ok(seconds) => {
+            // Show the picture so the christmas tree can be confirmed by eye.
+            if let Ok(mut map) = get_map("data.txt", (GRIDSIZEX, GRIDSIZEY)) {
+                for i in 0..map.robot_count {
+                    let mut robot = map.robots[i];
+                    // One second at a time, the full amount would overflow the positions.
+                    for _ in 0..seconds {
+                        robot.apply_velocity_seconds(1);
+                        robot.constrain_to_map(&map);
+                    }
+                    map.robots[i] = robot;
+                }
+                map.display();
+            }
+            println!("Part two result is: {}", seconds);
+        }
+    }
 }
 
-// Test disabled due to the nature of part 2.
-//#[test]
+#[test]
 fn calculate_test() {
     match calculate("testdata.txt", (11, 7)) {
         Err(err) => panic!("An error occured: {err:?}"),
@@ -275,7 +318,20 @@ Challenge part 1:
 
 
 Challenge part 2:
-
-
-
+Since there was no indication of how the christmas tree image was supposed to look like, I felt it
+was easiest to figure out a potential pattern and search that by printing the map.
+
+A strange pattern appears at:
+4 107 210 313 ...
+With 103 added between each one.
+Moving 103 seconds ahead at a time from 4 seconds and printing the map each time, a christmas tree
+was found after some iterations.
+
+Update: The printing loop has been replaced with math. The x positions repeat every 101 seconds
+and the y positions every 103 seconds. The strange pattern is the robots bunching up along one of
+the axes, which can be found by looking for the second where the positions along that axis have
+the lowest variance. Doing that for both axes gives:
+seconds = x_second (mod 101)
+seconds = y_second (mod 103)
+Which the chinese remainder theorem from the shared math module turns into the actual second.
 */
@@ -1,10 +1,16 @@
-use a_star_pathfinder::{PathFinderError, Paths};
+use std::env;
 use std::io;
 
 mod reader;
 use reader::get_lines;
+mod input_generator;
 mod maze;
+mod random;
 use maze::{Maze, MazeBuilder, Node, Tile};
+mod movement;
+use movement::{MovementRules, RulesError};
+mod search;
+use search::{PathDag, SearchError};
 mod testing_debug;
 mod vector;
 use vector::VectorI16;
@@ -13,7 +19,8 @@ use vector::VectorI16;
 enum AdventError {
     IoError(io::Error),
     CorruptData(String),
-    GoalUnreachable(PathFinderError),
+    GoalUnreachable(SearchError),
+    InvalidRules(RulesError),
 }
 
 impl From<io::Error> for AdventError {
@@ -22,13 +29,19 @@ impl From<io::Error> for AdventError {
     }
 }
 
-impl From<PathFinderError> for AdventError {
-    fn from(err: PathFinderError) -> Self {
+impl From<SearchError> for AdventError {
+    fn from(err: SearchError) -> Self {
         Self::GoalUnreachable(err)
     }
 }
 
-fn get_maze(path: &str) -> Result<Maze, AdventError> {
+impl From<RulesError> for AdventError {
+    fn from(err: RulesError) -> Self {
+        Self::InvalidRules(err)
+    }
+}
+
+fn get_maze(path: &str, rules: MovementRules) -> Result<Maze, AdventError> {
     let lines = get_lines(path)?;
 
     let mut tile_grid = [[Tile::Wall; maze::GRIDSIZE]; maze::GRIDSIZE];
@@ -40,6 +53,8 @@ fn get_maze(path: &str) -> Result<Maze, AdventError> {
             match char {
                 '#' => (), // Do nothing as a wall is already the default tile in the maze,
                 '.' => tile_grid[x][y] = Tile::Empty,
+                // Digits are open tiles that take that many steps worth of cost to cross.
+                '1'..='9' => tile_grid[x][y] = Tile::Weighted(char as u8 - b'0'),
                 'S' => {
                     tile_grid[x][y] = Tile::Empty;
                     start = VectorI16::from((x, y));
@@ -64,10 +79,11 @@ fn get_maze(path: &str) -> Result<Maze, AdventError> {
         .start(start)
         .goal(goal)
         .size_override(size_override)
-        .build())
+        .rules(rules)
+        .build()?)
 }
 
-fn display_maze(maze: &Maze, paths: &Paths<Node>) {
+fn display_maze(maze: &Maze, paths: &PathDag<Node>) {
     let mut output_string =
         String::with_capacity((maze.size_override() * maze.size_override()) + maze.size_override());
 
@@ -82,17 +98,27 @@ fn display_maze(maze: &Maze, paths: &Paths<Node>) {
 }
 
 fn calculate(path: &str) -> Result<u64, AdventError> {
-    let mut maze = get_maze(path)?;
+    let mut maze = get_maze(path, MovementRules::default())?;
 
     let paths = maze.calculate_path()?;
 
     display_maze(&maze, &paths);
 
     //todo!();
-    Ok(maze.optimal_seats(paths))
+    Ok(maze.optimal_seats(&paths))
 }
 
 fn main() {
+    // "cargo run -- generate <kind> [seed] [size] [amount]" prints a generated input instead.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "generate") {
+        match input_generator::run_command(&args[1..]) {
+            Err(err) => println!("An error occured: {err}"),
+            Ok(lines) => println!("{}", lines.join("\n")),
+        }
+        return;
+    }
+
     match calculate("data.txt") {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
@@ -145,5 +171,19 @@ Calculate all the optimal paths, then iterate over them.
 
 After all paths has been checked the length of the hashset is our result!
 
+Update: The reindeer rules used to be written into the maze. They now come from MovementRules,
+which the MazeBuilder takes: the step cost, which turns are allowed and what each costs, whether
+diagonal steps are allowed, and the start direction. Digits in the maze are tiles that cost that
+many steps to cross. The default rules are still the reindeer. The heuristic is worked out from
+the rules, counting the cheapest steps to the goal plus the cheapest turns needed to face every
+direction the goal lies in, so it never overestimates and A* stays correct whatever the rules.
+The steps have to allow for zigzagging: when diagonal steps cost less than straight ones, two
+diagonals can be the cheaper way to go two tiles straight ahead.
+
+Update: The shared input_generator module makes seeded mazes in the same format as the input:
+perfect ones with a single path, braided ones without dead ends, and ones with open rooms. A test
+runs the maze on a few of each, with the reindeer and with two kinds of diagonal rules, and checks
+A* finds the same cost as a plain Dijkstra over every node. "cargo run -- generate braided 7 41"
+prints one.
 
 */
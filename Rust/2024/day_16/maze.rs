@@ -1,76 +1,24 @@
-use std::collections::HashSet;
-
+use super::movement::{Direction, MovementRules, RulesError};
+use super::search::{self, PathDag, SearchError};
 use super::vector::VectorI16;
-use a_star_pathfinder::{AllPathsFinder, PathFinderError, Paths};
 
 pub const GRIDSIZE: usize = 141;
-const STARTDIRECTION: Direction = Direction(VectorI16 { x: 1, y: 0 });
 
 #[derive(Copy, Clone)]
 pub enum Tile {
     Wall,
     Empty,
+    /// Open, but stepping onto it costs the weight times a normal step.
+    Weighted(u8),
 }
 
 impl Tile {
-    fn is_empty(&self) -> bool {
+    /// What stepping onto the tile costs compared to a empty tile, None for walls.
+    fn weight(&self) -> Option<u64> {
         match self {
-            Tile::Wall => false,
-            Tile::Empty => true,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Ord, Eq)]
-pub struct Direction(VectorI16);
-
-impl Direction {
-    fn east() -> Self {
-        Self(VectorI16::from((1, 0)))
-    }
-    fn south() -> Self {
-        Self(VectorI16::from((0, 1)))
-    }
-    fn west() -> Self {
-        Self(VectorI16::from((-1, 0)))
-    }
-    fn north() -> Self {
-        Self(VectorI16::from((0, -1)))
-    }
-
-    fn next(self) -> Self {
-        match self.0 {
-            VectorI16 { x: 1, y: 0 } => Direction::south(),
-            VectorI16 { x: 0, y: 1 } => Direction::west(),
-            VectorI16 { x: -1, y: 0 } => Direction::north(),
-            VectorI16 { x: 0, y: -1 } => Direction::east(),
-            _ => panic!("Called .next() on an invalid direction! Was it changed externally?"),
-        }
-    }
-
-    fn previous(self) -> Self {
-        match self.0 {
-            VectorI16 { x: 1, y: 0 } => Direction::north(),
-            VectorI16 { x: 0, y: 1 } => Direction::east(),
-            VectorI16 { x: -1, y: 0 } => Direction::south(),
-            VectorI16 { x: 0, y: -1 } => Direction::west(),
-            _ => panic!(
-                "Called .previous() on an invalid direction {:?}! Was it changed externally?",
-                self.0
-            ),
-        }
-    }
-
-    fn char(&self) -> char {
-        match self.0 {
-            VectorI16 { x: 1, y: 0 } => '>',
-            VectorI16 { x: 0, y: 1 } => 'v',
-            VectorI16 { x: -1, y: 0 } => '<',
-            VectorI16 { x: 0, y: -1 } => '^',
-            _ => panic!(
-                "Called .char() on an invalid direction {:?}! Was it changed externally?",
-                self.0
-            ),
+            Tile::Wall => None,
+            Tile::Empty => Some(1),
+            Tile::Weighted(weight) => Some(*weight as u64),
         }
     }
 }
@@ -86,6 +34,9 @@ pub struct Maze {
     size_override: usize,
     start: Node,
     goal: Node,
+    rules: MovementRules,
+    /// Weight of the lightest open tile, for the heuristic.
+    min_weight: u64,
 }
 
 impl Maze {
@@ -112,47 +63,43 @@ impl Maze {
         Some(self.grid[position.x as usize][position.y as usize])
     }
 
+    fn weight(&self, position: VectorI16) -> Option<u64> {
+        self.read_tile(position)?.weight()
+    }
+
     fn get_neighbours_closure(&self) -> impl Fn(&Node) -> Vec<(Node, u64)> + '_ {
         |node: &Node| {
             let mut neighbours = Vec::with_capacity(3);
 
-            // Move forward 1 step.
-            if let Some(tile) = self.read_tile(node.position + node.facing.0) {
-                if tile.is_empty() {
-                    neighbours.push((
-                        Node {
-                            position: node.position + node.facing.0,
-                            facing: node.facing,
-                        },
-                        1,
-                    ));
-                }
+            // Move forward 1 step. Diagonal steps can't squeeze between two walls.
+            let step = node.facing.vector();
+            let target = node.position + step;
+            let corners_open = !node.facing.is_diagonal()
+                || (self
+                    .weight(node.position + VectorI16::from((step.x, 0)))
+                    .is_some()
+                    && self
+                        .weight(node.position + VectorI16::from((0, step.y)))
+                        .is_some());
+            if let Some(weight) = self.weight(target).filter(|_| corners_open) {
+                neighbours.push((
+                    Node {
+                        position: target,
+                        facing: node.facing,
+                    },
+                    self.rules.step_cost(node.facing, weight),
+                ));
             }
 
-            // Turn clockwise 90 degrees.
-            if let Some(tile) = self.read_tile(node.position + node.facing.next().0) {
-                if tile.is_empty() {
-                    neighbours.push((
-                        Node {
-                            position: node.position,
-                            facing: node.facing.next(),
-                        },
-                        1000,
-                    ));
-                }
-            }
-
-            // Turn counter clockwise 90 degrees.
-            if let Some(tile) = self.read_tile(node.position + node.facing.previous().0) {
-                if tile.is_empty() {
-                    neighbours.push((
-                        Node {
-                            position: node.position,
-                            facing: node.facing.previous(),
-                        },
-                        1000,
-                    ));
-                }
+            // Turn in place.
+            for (facing, cost) in self.rules.turns(node.facing) {
+                neighbours.push((
+                    Node {
+                        position: node.position,
+                        facing,
+                    },
+                    cost,
+                ));
             }
 
             neighbours
@@ -160,8 +107,8 @@ impl Maze {
     }
 
     fn get_heuristic_closure(&self) -> impl Fn(&Node) -> u64 + '_ {
-        //
-        |node: &Node| 0
+        let estimate = self.rules.estimator(self.goal.position, self.min_weight);
+        move |node: &Node| estimate(node.position, node.facing)
     }
 
     fn get_is_goal_closure(&self) -> impl Fn(&Node) -> bool + '_ {
@@ -169,7 +116,7 @@ impl Maze {
         |node: &Node| node.position == self.goal.position
     }
 
-    pub fn calculate_path(&mut self) -> Result<Paths<Node>, PathFinderError> {
+    pub fn calculate_path(&mut self) -> Result<PathDag<Node>, SearchError> {
         let neighbours_closure = self.get_neighbours_closure();
         let heuristic_closure = self.get_heuristic_closure();
         let is_goal_closure = self.get_is_goal_closure();
@@ -179,25 +126,15 @@ impl Maze {
             self.start.position, self.goal.position, self.size_override
         );
 
-        let mut pathfinder = AllPathsFinder::new(self.start, &heuristic_closure);
-
-        //let path = pathfinder.calculate_path(neighbours_closure, heuristic_closure, is_goal_closure)?;
-        let paths = match pathfinder.calculate_all_optimal_paths(
+        search::shortest_path_dag(
+            self.start,
             neighbours_closure,
             heuristic_closure,
             is_goal_closure,
-        ) {
-            Ok(result) => result,
-            Err(err) => {
-                //println!("Nodes searched: {}", pathfinder.view().0.len());
-                return Err(err);
-            }
-        };
-
-        Ok(paths)
+        )
     }
 
-    pub fn get_display_grid(&self, paths: &Paths<Node>) -> [[char; GRIDSIZE]; GRIDSIZE + 1] {
+    pub fn get_display_grid(&self, paths: &PathDag<Node>) -> [[char; GRIDSIZE]; GRIDSIZE + 1] {
         let mut display_grid: [[char; GRIDSIZE]; GRIDSIZE + 1] = [['#'; GRIDSIZE]; GRIDSIZE + 1];
 
         println!("so: {}", self.size_override);
@@ -206,16 +143,14 @@ impl Maze {
                 display_grid[x][y] = match self.grid[x][y] {
                     Tile::Wall => '#',
                     Tile::Empty => ' ',
+                    Tile::Weighted(weight) => char::from_digit(weight as u32, 36).unwrap_or('?'),
                 }
             }
             display_grid[self.size_override][y] = '\n';
         }
 
-        for path in paths.all_paths() {
-            for node in path.nodes.iter().rev() {
-                display_grid[node.position.x as usize][node.position.y as usize] =
-                    node.facing.char();
-            }
+        for node in paths.nodes() {
+            display_grid[node.position.x as usize][node.position.y as usize] = node.facing.char();
         }
 
         display_grid[self.start.position.x as usize][self.start.position.y as usize] = 'S';
@@ -224,16 +159,8 @@ impl Maze {
         display_grid
     }
 
-    pub fn optimal_seats(&self, paths: Paths<Node>) -> u64 {
-        let mut path_tiles: HashSet<VectorI16> = HashSet::new();
-        for path in paths.all_paths() {
-            for node in path.nodes {
-                //
-                path_tiles.insert(node.position);
-            }
-        }
-
-        path_tiles.len() as u64
+    pub fn optimal_seats(&self, paths: &PathDag<Node>) -> u64 {
+        paths.tiles(|node| node.position).len() as u64
     }
 }
 
@@ -242,6 +169,7 @@ pub struct MazeBuilder {
     size_override: usize,
     start_position: VectorI16,
     goal_position: VectorI16,
+    rules: MovementRules,
 }
 
 impl MazeBuilder {
@@ -251,6 +179,7 @@ impl MazeBuilder {
             size_override: 0,
             start_position: VectorI16::default(),
             goal_position: VectorI16::default(),
+            rules: MovementRules::default(),
         }
     }
 
@@ -274,18 +203,152 @@ impl MazeBuilder {
         self
     }
 
-    pub fn build(&self) -> Maze {
-        Maze {
+    pub fn rules(mut self, rules: MovementRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn build(&self) -> Result<Maze, RulesError> {
+        self.rules.validate()?;
+        let open_weights: Vec<u64> = self.grid[..self.size_override]
+            .iter()
+            .flat_map(|column| &column[..self.size_override])
+            .filter_map(|tile| tile.weight())
+            .collect();
+        if open_weights.contains(&0) {
+            return Err(RulesError::ZeroCost);
+        }
+
+        let facing = self.rules.start_direction();
+        Ok(Maze {
             grid: self.grid,
             size_override: self.size_override,
             start: Node {
                 position: self.start_position,
-                facing: STARTDIRECTION,
+                facing,
             },
             goal: Node {
                 position: self.goal_position,
-                facing: STARTDIRECTION,
+                facing,
             },
+            rules: self.rules.clone(),
+            min_weight: open_weights.into_iter().min().unwrap_or(1),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_generator::{self, MazeStyle};
+    use crate::random::Random;
+
+    fn maze(lines: &[&str], rules: MovementRules) -> Result<Maze, RulesError> {
+        let mut grid = [[Tile::Wall; GRIDSIZE]; GRIDSIZE];
+        let (mut start, mut goal) = (VectorI16::default(), VectorI16::default());
+        for (y, line) in lines.iter().enumerate() {
+            for (x, char) in line.chars().enumerate() {
+                grid[x][y] = match char {
+                    '#' => Tile::Wall,
+                    '1'..='9' => Tile::Weighted(char as u8 - b'0'),
+                    _ => Tile::Empty,
+                };
+                match char {
+                    'S' => start = VectorI16::from((x, y)),
+                    'E' => goal = VectorI16::from((x, y)),
+                    _ => {}
+                }
+            }
+        }
+        MazeBuilder::new()
+            .grid(grid)
+            // The maze is square, any extra rows stay walls.
+            .size_override(lines.len().max(lines[0].len()))
+            .start(start)
+            .goal(goal)
+            .rules(rules)
+            .build()
+    }
+
+    fn cost(lines: &[&str], rules: MovementRules) -> u64 {
+        maze(lines, rules).unwrap().calculate_path().unwrap().cost()
+    }
+
+    const OPEN: [&str; 5] = ["#####", "#..E#", "#...#", "#S..#", "#####"];
+
+    #[test]
+    fn reindeer_rules() {
+        // Two steps east, a quarter turn, two steps north.
+        assert_eq!(cost(&OPEN, MovementRules::default()), 1004);
+        // Turning only clockwise takes three turns to face north.
+        let clockwise = MovementRules::new(1).turn(90, 1000);
+        assert_eq!(cost(&OPEN, clockwise), 3004);
+    }
+
+    #[test]
+    fn diagonal_steps() {
+        let rules = MovementRules::new(10).diagonal(14).turn(45, 1).turn(-45, 1);
+        assert_eq!(cost(&OPEN, rules.clone()), 1 + 14 + 14);
+
+        // A wall beside every diagonal blocks it, so it goes straight with three quarter turns.
+        let blocked = ["#####", "#.#E#", "#...#", "#S#.#", "#####"];
+        assert_eq!(cost(&blocked, rules), 4 * 10 + 3 * 2);
+
+        // Diagonals cheaper than straight steps, so it zigzags to a goal straight ahead.
+        let zigzag = MovementRules::new(10).diagonal(1).turn(45, 1).turn(-45, 1);
+        let ahead = ["#####", "#...#", "#S.E#", "#...#", "#####"];
+        assert_eq!(cost(&ahead, zigzag), 1 + 1 + 2 + 1);
+    }
+
+    #[test]
+    fn weighted_tiles() {
+        // Going through the 9s is shorter, but going around is cheaper.
+        let swamp = ["#######", "#.....#", "#.999.#", "#S999E#", "#######"];
+        let rules = MovementRules::new(1).turn(90, 1).turn(-90, 1);
+        assert_eq!(cost(&swamp, rules.clone()), 8 + 3);
+        let light = ["#######", "#.....#", "#.222.#", "#S222E#", "#######"];
+        assert_eq!(cost(&light, rules.clone()), 2 * 3 + 1);
+
+        assert_eq!(
+            MazeBuilder::new()
+                .grid({
+                    let mut grid = [[Tile::Empty; GRIDSIZE]; GRIDSIZE];
+                    grid[1][1] = Tile::Weighted(0);
+                    grid
+                })
+                .size_override(3)
+                .rules(rules)
+                .build()
+                .err(),
+            Some(RulesError::ZeroCost)
+        );
+    }
+
+    #[test]
+    fn generated_mazes_match_dijkstra() {
+        let mut random = Random::new(16);
+        let diagonal = MovementRules::new(10)
+            .diagonal(14)
+            .turn(45, 300)
+            .turn(-45, 300)
+            .turn(180, 500);
+        let zigzag = MovementRules::new(10).diagonal(1).turn(45, 1).turn(-45, 1);
+        for style in [MazeStyle::Perfect, MazeStyle::Braided, MazeStyle::Rooms] {
+            for rules in [MovementRules::default(), diagonal.clone(), zigzag.clone()] {
+                let lines = input_generator::maze(31, style, &mut random).unwrap();
+                let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+                let mut maze = maze(&lines, rules).unwrap();
+
+                // Without a heuristic every reachable node gets its exact cost, so the A*
+                // result shows whether the derived heuristic ever overestimated.
+                let distances = search::distance_field(maze.start, maze.get_neighbours_closure());
+                let best = distances
+                    .iter()
+                    .filter(|(node, _)| node.position == maze.goal.position)
+                    .map(|(_, cost)| *cost)
+                    .min();
+                assert_eq!(maze.calculate_path().ok().map(|paths| paths.cost()), best);
+            }
         }
     }
 }
@@ -0,0 +1,370 @@
+use std::fmt::Display;
+
+use super::vector::VectorI16;
+
+/// The eight compass directions in clockwise order, starting east. y grows downwards, so south
+/// comes a quarter turn after east.
+const COMPASS: [VectorI16; 8] = [
+    VectorI16 { x: 1, y: 0 },
+    VectorI16 { x: 1, y: 1 },
+    VectorI16 { x: 0, y: 1 },
+    VectorI16 { x: -1, y: 1 },
+    VectorI16 { x: -1, y: 0 },
+    VectorI16 { x: -1, y: -1 },
+    VectorI16 { x: 0, y: -1 },
+    VectorI16 { x: 1, y: -1 },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Ord, Eq)]
+pub struct Direction(VectorI16);
+
+impl Direction {
+    pub fn east() -> Self {
+        Self(COMPASS[0])
+    }
+    pub fn south() -> Self {
+        Self(COMPASS[2])
+    }
+    pub fn west() -> Self {
+        Self(COMPASS[4])
+    }
+    pub fn north() -> Self {
+        Self(COMPASS[6])
+    }
+
+    pub fn vector(&self) -> VectorI16 {
+        self.0
+    }
+
+    fn compass_index(&self) -> usize {
+        COMPASS
+            .iter()
+            .position(|vector| *vector == self.0)
+            .unwrap_or_else(|| panic!("Invalid direction {:?}! Was it changed externally?", self.0))
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.0.x != 0 && self.0.y != 0
+    }
+
+    /// Turns clockwise by eighths of a full turn, counter clockwise if negative.
+    pub fn rotated(self, eighths: i8) -> Self {
+        let index = (self.compass_index() as i8 + eighths).rem_euclid(8);
+        Self(COMPASS[index as usize])
+    }
+
+    pub fn char(&self) -> char {
+        ['>', '\\', 'v', '/', '<', '\\', '^', '/'][self.compass_index()]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RulesError {
+    /// Turns have to be a multiple of 45 degrees, or 90 without diagonal moves, between -180
+    /// and 180 and not 0.
+    InvalidTurn(i16),
+    /// Only diagonal moves can leave a diagonal start direction.
+    DiagonalStart,
+    /// Every step and turn has to cost something, or the search could go around in circles for
+    /// free.
+    ZeroCost,
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::InvalidTurn(degrees) => write!(f, "Can't turn {degrees} degrees"),
+            RulesError::DiagonalStart => {
+                write!(f, "Starts facing diagonally without diagonal moves")
+            }
+            RulesError::ZeroCost => write!(f, "A step, turn or tile costs nothing"),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Turn {
+    /// Clockwise, negative for counter clockwise.
+    degrees: i16,
+    cost: u64,
+}
+
+/// How a walker moves through a maze and what each move costs.
+///
+/// The walker always faces a direction and can only step forward. Turning happens in place and
+/// only by the turns that have been allowed. Stepping onto a tile costs the step cost times the
+/// weight of that tile. The default is the reindeer from day 16: steps cost 1, quarter turns
+/// either way cost 1000, and it starts facing east.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovementRules {
+    start_facing: Direction,
+    step_cost: u64,
+    /// None when diagonal moves aren't allowed.
+    diagonal_step_cost: Option<u64>,
+    turns: Vec<Turn>,
+}
+
+impl Default for MovementRules {
+    fn default() -> Self {
+        Self::new(1).turn(90, 1000).turn(-90, 1000)
+    }
+}
+
+impl MovementRules {
+    /// Rules with only straight steps of the given cost and no turns, facing east.
+    pub fn new(step_cost: u64) -> Self {
+        Self {
+            start_facing: Direction::east(),
+            step_cost,
+            diagonal_step_cost: None,
+            turns: Vec::new(),
+        }
+    }
+
+    pub fn start_facing(mut self, direction: Direction) -> Self {
+        self.start_facing = direction;
+        self
+    }
+
+    /// Allows diagonal steps. A diagonal step can't cut a corner, both tiles beside it have to be
+    /// open too.
+    pub fn diagonal(mut self, step_cost: u64) -> Self {
+        self.diagonal_step_cost = Some(step_cost);
+        self
+    }
+
+    /// Allows turning in place, clockwise for positive degrees. Allowing the same turn twice
+    /// keeps the last cost.
+    pub fn turn(mut self, degrees: i16, cost: u64) -> Self {
+        self.turns.retain(|turn| turn.degrees != degrees);
+        self.turns.push(Turn { degrees, cost });
+        self
+    }
+
+    pub fn start_direction(&self) -> Direction {
+        self.start_facing
+    }
+
+    pub fn allows_diagonals(&self) -> bool {
+        self.diagonal_step_cost.is_some()
+    }
+
+    pub fn validate(&self) -> Result<(), RulesError> {
+        let multiple = if self.allows_diagonals() { 45 } else { 90 };
+        for turn in &self.turns {
+            if turn.degrees == 0 || turn.degrees.abs() > 180 || turn.degrees % multiple != 0 {
+                return Err(RulesError::InvalidTurn(turn.degrees));
+            }
+            if turn.cost == 0 {
+                return Err(RulesError::ZeroCost);
+            }
+        }
+        if !self.allows_diagonals() && self.start_facing.is_diagonal() {
+            return Err(RulesError::DiagonalStart);
+        }
+        if self.step_cost == 0 || self.diagonal_step_cost == Some(0) {
+            return Err(RulesError::ZeroCost);
+        }
+        Ok(())
+    }
+
+    /// Cost of stepping onto a tile of the given weight while facing the direction.
+    pub fn step_cost(&self, facing: Direction, weight: u64) -> u64 {
+        if facing.is_diagonal() {
+            self.diagonal_step_cost.unwrap_or(self.step_cost) * weight
+        } else {
+            self.step_cost * weight
+        }
+    }
+
+    /// Every allowed turn from the direction, with its cost.
+    pub fn turns(&self, facing: Direction) -> impl Iterator<Item = (Direction, u64)> + '_ {
+        self.turns
+            .iter()
+            .map(move |turn| (facing.rotated((turn.degrees / 45) as i8), turn.cost))
+    }
+
+    /// Cheapest way to turn clockwise by each number of eighths, using any combination of the
+    /// allowed turns. None if the allowed turns can't get there.
+    fn rotation_costs(&self) -> [Option<u64>; 8] {
+        let mut costs = [None; 8];
+        costs[0] = Some(0);
+        // No cheapest rotation needs more than 7 turns.
+        for _ in 0..7 {
+            for from in 0..8 {
+                let Some(cost) = costs[from] else {
+                    continue;
+                };
+                for turn in &self.turns {
+                    let to = (from as i16 + turn.degrees / 45).rem_euclid(8) as usize;
+                    if costs[to].is_none_or(|known| cost + turn.cost < known) {
+                        costs[to] = Some(cost + turn.cost);
+                    }
+                }
+            }
+        }
+        costs
+    }
+
+    /// Returns a lower bound on the cost of getting from a position and direction to the goal,
+    /// that never overestimates however the walls are placed.
+    ///
+    /// Every step costs at least the lightest tile. Each step moves at most one tile along each
+    /// axis, so the steps are counted at the cheapest way to cover both: a diagonal or two
+    /// straight steps for the shorter side, and a straight step or half a zigzag of diagonals for
+    /// the rest.
+    ///
+    /// Unless the goal is straight ahead the walker has to turn. Without diagonals it has to face
+    /// every direction the goal lies in, in one order or the other, so it pays for the cheapest
+    /// rotations through them.
+    pub fn estimator(
+        &self,
+        goal: VectorI16,
+        min_weight: u64,
+    ) -> impl Fn(VectorI16, Direction) -> u64 + '_ {
+        let rotations = self.rotation_costs();
+        let rotation = move |from: Direction, to: Direction| {
+            rotations[(to.compass_index() + 8 - from.compass_index()) % 8]
+        };
+
+        move |position: VectorI16, facing: Direction| {
+            let offset = goal - position;
+            let (dx, dy) = (
+                offset.x.unsigned_abs() as u64,
+                offset.y.unsigned_abs() as u64,
+            );
+            let (long, short) = (dx.max(dy), dx.min(dy));
+            let steps = match self.diagonal_step_cost {
+                // Two diagonals zigzagging cover two straight steps, so the rest of the long
+                // side may be cheaper that way when diagonal steps cost less than straight ones.
+                Some(diagonal) => {
+                    short * diagonal.min(self.step_cost * 2)
+                        + (long - short) * diagonal.min(self.step_cost)
+                }
+                None => (dx + dy) * self.step_cost,
+            };
+
+            // Unreachable directions mean the goal can't be reached at all, so any estimate is
+            // fine there. They count as free to keep the sums from overflowing.
+            let turns = if straight_ahead(offset, facing) {
+                0
+            } else if self.allows_diagonals() {
+                (1..8)
+                    .filter_map(|eighths| rotation(facing, facing.rotated(eighths)))
+                    .min()
+                    .unwrap_or(0)
+            } else {
+                let needed: Vec<Direction> = [(offset.x.signum(), 0), (0, offset.y.signum())]
+                    .into_iter()
+                    .filter(|vector| *vector != (0, 0))
+                    .map(|vector| Direction(VectorI16::from(vector)))
+                    .collect();
+                match needed[..] {
+                    [only] => rotation(facing, only).unwrap_or(0),
+                    [a, b] => [(a, b), (b, a)]
+                        .iter()
+                        .filter_map(|(first, second)| {
+                            Some(rotation(facing, *first)? + rotation(*first, *second)?)
+                        })
+                        .min()
+                        .unwrap_or(0),
+                    _ => 0,
+                }
+            };
+
+            steps * min_weight + turns
+        }
+    }
+}
+
+/// True when the offset is a whole number of steps in the direction, including 0 steps.
+fn straight_ahead(offset: VectorI16, facing: Direction) -> bool {
+    let vector = facing.vector();
+    let steps = if vector.x != 0 {
+        offset.x / vector.x
+    } else {
+        offset.y / vector.y
+    };
+    steps >= 0 && vector * steps == offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_through_the_compass() {
+        let east = Direction::east();
+        assert_eq!(east.rotated(2), Direction::south());
+        assert_eq!(east.rotated(-2), Direction::north());
+        assert_eq!(east.rotated(4), Direction::west());
+        assert_eq!(east.rotated(-1).vector(), VectorI16::from((1, -1)));
+        assert_eq!(Direction::north().rotated(3).char(), '\\');
+    }
+
+    #[test]
+    fn validates_rules() {
+        assert_eq!(MovementRules::default().validate(), Ok(()));
+        assert_eq!(
+            MovementRules::new(1).turn(45, 1).validate(),
+            Err(RulesError::InvalidTurn(45))
+        );
+        assert_eq!(
+            MovementRules::new(1).diagonal(2).turn(45, 1).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            MovementRules::new(1).turn(270, 1).validate(),
+            Err(RulesError::InvalidTurn(270))
+        );
+        assert_eq!(
+            MovementRules::new(1).turn(90, 0).validate(),
+            Err(RulesError::ZeroCost)
+        );
+        assert_eq!(
+            MovementRules::new(1)
+                .start_facing(Direction::east().rotated(1))
+                .validate(),
+            Err(RulesError::DiagonalStart)
+        );
+    }
+
+    #[test]
+    fn estimates_turns_and_steps() {
+        let rules = MovementRules::default();
+        let estimate = rules.estimator(VectorI16::default(), 1);
+        let at = |x, y| VectorI16::from((x, y));
+        // Straight ahead, only steps.
+        assert_eq!(estimate(at(-5, 0), Direction::east()), 5);
+        // Ahead and to the side, one turn.
+        assert_eq!(estimate(at(-5, -3), Direction::east()), 1008);
+        // Straight behind, two quarter turns.
+        assert_eq!(estimate(at(5, 0), Direction::east()), 2005);
+        // Behind and to the side, facing south first is cheaper than facing west first.
+        assert_eq!(
+            rules.estimator(VectorI16::default(), 2)(at(5, -3), Direction::east()),
+            2016
+        );
+
+        // Only able to turn one way, so a quarter turn counter clockwise takes three turns.
+        let right_only = MovementRules::new(1).turn(90, 10);
+        let estimate = right_only.estimator(VectorI16::default(), 1);
+        assert_eq!(estimate(at(-5, 0), Direction::south()), 5 + 30);
+
+        let diagonal = MovementRules::new(2).diagonal(3).turn(45, 10).turn(-45, 10);
+        let estimate = diagonal.estimator(VectorI16::from((4, 6)), 1);
+        assert_eq!(
+            estimate(at(0, 0), Direction::east().rotated(1)),
+            4 * 3 + 2 * 2 + 10
+        );
+        assert_eq!(estimate(at(0, 2), Direction::east().rotated(1)), 4 * 3);
+
+        // Diagonals cheaper than straight steps, zigzagging is the cheapest way straight ahead.
+        let zigzag = MovementRules::new(10).diagonal(1).turn(45, 1).turn(-45, 1);
+        let estimate = zigzag.estimator(VectorI16::from((2, 0)), 1);
+        assert_eq!(estimate(at(0, 0), Direction::east()), 2);
+        assert_eq!(estimate(at(0, 1), Direction::east()), 1 + 1 + 1);
+    }
+}
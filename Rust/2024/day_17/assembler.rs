@@ -0,0 +1,320 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use crate::instruction::{ComboOperand, Opcode, OperandKind};
+
+/// Largest value that fits in the 3 bits of a operand, which also limits how far jnz can jump.
+const MAX_OPERAND: u8 = 7;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssembleErrorKind {
+    UnknownMnemonic(String),
+    MissingOperand(Opcode),
+    /// Something other than a single operand followed the mnemonic.
+    UnexpectedOperand(String),
+    InvalidOperand(String),
+    InvalidLabel(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+    /// The label is placed past the addresses a 3-bit jnz operand can reach.
+    AddressOutOfRange(String, usize),
+}
+
+/// A problem with the assembly source. line starts at 1.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+        match &self.kind {
+            AssembleErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "Unknown mnemonic '{mnemonic}'")
+            }
+            AssembleErrorKind::MissingOperand(opcode) => write!(f, "{opcode} needs a operand"),
+            AssembleErrorKind::UnexpectedOperand(text) => write!(f, "Unexpected '{text}'"),
+            AssembleErrorKind::InvalidOperand(text) => write!(f, "Invalid operand '{text}'"),
+            AssembleErrorKind::InvalidLabel(label) => write!(f, "Invalid label name '{label}'"),
+            AssembleErrorKind::UnknownLabel(label) => write!(f, "Unknown label '{label}'"),
+            AssembleErrorKind::DuplicateLabel(label) => {
+                write!(f, "Label '{label}' is defined more than once")
+            }
+            AssembleErrorKind::AddressOutOfRange(label, address) => write!(
+                f,
+                "Label '{label}' is at address {address}, jnz can only reach 0 to {MAX_OPERAND}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// A parsed line waiting for its labels to be resolved.
+enum Statement<'a> {
+    Instruction(Opcode, Option<&'a str>),
+    Bytes(Vec<u8>),
+}
+
+/// Turns assembly source into program bytes.
+///
+/// Each line holds at most one instruction, written as its mnemonic followed by the operand:
+/// a number from 0 to 7, a register name A, B or C for combo operands, or a label for jnz. bxc
+/// ignores its operand so it may be left out. A line may start with "name:" to label the address
+/// of the next instruction, and anything after a ';' is a comment. ".byte 1, 2" emits raw values,
+/// which lets the disassembly of any program be assembled again.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut statements: Vec<(usize, Statement)> = Vec::new();
+    let mut address = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |kind| AssembleError {
+            line: line_number,
+            kind,
+        };
+
+        let mut code = line.split(';').next().unwrap_or_default().trim();
+        if let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            if !is_valid_label(label) {
+                return Err(error(AssembleErrorKind::InvalidLabel(label.to_string())));
+            }
+            if labels.insert(label, address).is_some() {
+                return Err(error(AssembleErrorKind::DuplicateLabel(label.to_string())));
+            }
+            code = rest.trim();
+        }
+        if code.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let operands = operands.trim();
+        let statement = match mnemonic {
+            ".byte" => Statement::Bytes(parse_bytes(operands).map_err(error)?),
+            _ => {
+                let opcode = Opcode::from_mnemonic(mnemonic).ok_or_else(|| {
+                    error(AssembleErrorKind::UnknownMnemonic(mnemonic.to_string()))
+                })?;
+                if operands.contains(char::is_whitespace) || operands.contains(',') {
+                    return Err(error(AssembleErrorKind::UnexpectedOperand(
+                        operands.to_string(),
+                    )));
+                }
+                Statement::Instruction(opcode, (!operands.is_empty()).then_some(operands))
+            }
+        };
+
+        address += match &statement {
+            Statement::Instruction(..) => 2,
+            Statement::Bytes(bytes) => bytes.len(),
+        };
+        statements.push((line_number, statement));
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        match statement {
+            Statement::Bytes(bytes) => program.extend(bytes),
+            Statement::Instruction(opcode, operand) => {
+                let operand = encode_operand(opcode, operand, &labels)
+                    .map_err(|kind| AssembleError { line, kind })?;
+                program.push(opcode.as_u8());
+                program.push(operand);
+            }
+        }
+    }
+    Ok(program)
+}
+
+fn is_valid_label(label: &str) -> bool {
+    let mut chars = label.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+fn parse_bytes(operands: &str) -> Result<Vec<u8>, AssembleErrorKind> {
+    operands
+        .split(',')
+        .map(str::trim)
+        .map(|value| {
+            value
+                .parse::<u8>()
+                .map_err(|_| AssembleErrorKind::InvalidOperand(value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_literal(text: &str) -> Option<u8> {
+    text.parse::<u8>()
+        .ok()
+        .filter(|value| *value <= MAX_OPERAND)
+}
+
+fn encode_operand(
+    opcode: Opcode,
+    operand: Option<&str>,
+    labels: &HashMap<&str, usize>,
+) -> Result<u8, AssembleErrorKind> {
+    let Some(text) = operand else {
+        return match opcode.operand_kind() {
+            OperandKind::Ignored => Ok(0),
+            _ => Err(AssembleErrorKind::MissingOperand(opcode)),
+        };
+    };
+    let invalid = || AssembleErrorKind::InvalidOperand(text.to_string());
+
+    match opcode.operand_kind() {
+        OperandKind::Literal | OperandKind::Ignored => parse_literal(text).ok_or_else(invalid),
+        OperandKind::Combo => parse_literal(text)
+            .or_else(|| ComboOperand::from_register(text).map(ComboOperand::as_u8))
+            .ok_or_else(invalid),
+        OperandKind::Address => {
+            if let Some(value) = parse_literal(text) {
+                return Ok(value);
+            }
+            let address = *labels
+                .get(text)
+                .ok_or_else(|| AssembleErrorKind::UnknownLabel(text.to_string()))?;
+            u8::try_from(address)
+                .ok()
+                .filter(|address| *address <= MAX_OPERAND)
+                .ok_or_else(|| AssembleErrorKind::AddressOutOfRange(text.to_string(), address))
+        }
+    }
+}
+
+/// Turns program bytes into a assembly listing that assemble accepts again.
+///
+/// Every jnz target that starts a instruction gets a label, and each instruction is followed by
+/// a comment with its address and what it does. Anything that can't be decoded, like a invalid
+/// opcode or a trailing value without operand, is written as .byte.
+pub fn disassemble(program: &[u8]) -> String {
+    let jump_targets: HashSet<usize> = program
+        .chunks(2)
+        .filter(|pair| pair.len() == 2 && Opcode::from_u8(pair[0]) == Some(Opcode::Jnz))
+        .map(|pair| pair[1] as usize)
+        .filter(|target| target % 2 == 0 && *target <= program.len())
+        .collect();
+    let label = |address: usize| format!("L{address}");
+
+    let mut listing = String::new();
+    for (index, pair) in program.chunks(2).enumerate() {
+        let address = index * 2;
+        if jump_targets.contains(&address) {
+            listing.push_str(&format!("{}:\n", label(address)));
+        }
+
+        let decoded = match pair {
+            [opcode, operand] if *operand <= MAX_OPERAND => {
+                Opcode::from_u8(*opcode).map(|opcode| (opcode, *operand))
+            }
+            _ => None,
+        };
+        let (code, comment) = match decoded {
+            Some((opcode, operand)) => {
+                let operand_text = match opcode.operand_kind() {
+                    OperandKind::Combo => ComboOperand::from_u8(operand)
+                        .map(|combo| combo.to_string())
+                        .unwrap_or_else(|| operand.to_string()),
+                    OperandKind::Address if jump_targets.contains(&(operand as usize)) => {
+                        label(operand as usize)
+                    }
+                    OperandKind::Ignored if operand == 0 => String::new(),
+                    _ => operand.to_string(),
+                };
+                (
+                    format!("{opcode} {operand_text}").trim_end().to_string(),
+                    opcode.describe(operand),
+                )
+            }
+            None => {
+                let values: Vec<String> = pair.iter().map(|value| value.to_string()).collect();
+                (
+                    format!(".byte {}", values.join(", ")),
+                    "not a valid instruction".to_string(),
+                )
+            }
+        };
+        listing.push_str(&format!("    {code:<12}; {address:>2}: {comment}\n"));
+    }
+
+    if jump_targets.contains(&program.len()) {
+        listing.push_str(&format!("{}:\n", label(program.len())));
+    }
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY_17_PROGRAM: [u8; 16] = [2, 4, 1, 3, 7, 5, 4, 2, 0, 3, 1, 5, 5, 5, 3, 0];
+
+    #[test]
+    fn assemble_with_labels_and_registers() {
+        let source = "
+            ; The day 17 program.
+            start:
+                bst A       ; B = A % 8
+                bxl 3
+                cdv B
+                bxc 2
+                adv 3
+                bxl 5
+                out B
+                jnz start
+        ";
+        assert_eq!(assemble(source), Ok(DAY_17_PROGRAM.to_vec()));
+    }
+
+    #[test]
+    fn disassemble_round_trips() {
+        let listing = disassemble(&DAY_17_PROGRAM);
+        assert!(listing.starts_with("L0:\n    bst A       ;  0: B = A % 8\n"));
+        assert!(listing.contains("jnz L0      ; 14: if A != 0 jump to 0"));
+        assert_eq!(assemble(&listing), Ok(DAY_17_PROGRAM.to_vec()));
+
+        // Invalid opcodes, the reserved combo operand and a trailing value still round trip.
+        let odd_program = [9, 1, 6, 7, 3, 3, 5];
+        let listing = disassemble(&odd_program);
+        assert!(listing.contains(".byte 9, 1"));
+        assert!(listing.contains("bdv 7"));
+        assert_eq!(assemble(&listing), Ok(odd_program.to_vec()));
+    }
+
+    #[test]
+    fn assemble_errors() {
+        let error = |source: &str| assemble(source).unwrap_err();
+        assert_eq!(
+            error("bst A\nfoo 1"),
+            AssembleError {
+                line: 2,
+                kind: AssembleErrorKind::UnknownMnemonic("foo".to_string())
+            }
+        );
+        assert_eq!(
+            error("out").kind,
+            AssembleErrorKind::MissingOperand(Opcode::Out)
+        );
+        assert_eq!(
+            error("bxl A").kind,
+            AssembleErrorKind::InvalidOperand("A".to_string())
+        );
+        assert_eq!(
+            error("jnz end").kind,
+            AssembleErrorKind::UnknownLabel("end".to_string())
+        );
+        let far_label = "adv 1\nadv 1\nadv 1\nadv 1\nend: out A\njnz end";
+        assert_eq!(
+            error(far_label).kind,
+            AssembleErrorKind::AddressOutOfRange("end".to_string(), 8)
+        );
+    }
+}
@@ -1,3 +1,68 @@
+use std::fmt::Display;
+
+use crate::instruction::{ComboOperand, Opcode};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ComputerError {
+    /// The value at the address isn't one of the eight opcodes.
+    InvalidOpcode {
+        address: usize,
+        opcode: u8,
+    },
+    /// The instruction at the address is the last value of the program, so it has no operand.
+    MissingOperand {
+        address: usize,
+    },
+    /// The instruction at the address uses the reserved combo operand 7, or a value above it.
+    InvalidComboOperand {
+        address: usize,
+        operand: u8,
+    },
+    /// A division by 2^x where x came out negative, which isn't a whole number.
+    NegativeExponent {
+        address: usize,
+        exponent: i64,
+    },
+    UnknownRegister(char),
+    /// The program ran more instructions than the budget allows, likely because it never halts.
+    InstructionBudgetExceeded(u64),
+}
+
+impl Display for ComputerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComputerError::InvalidOpcode { address, opcode } => {
+                write!(f, "Invalid opcode {opcode} at address {address}")
+            }
+            ComputerError::MissingOperand { address } => {
+                write!(f, "Instruction at address {address} has no operand")
+            }
+            ComputerError::InvalidComboOperand { address, operand } => {
+                write!(f, "Invalid combo operand {operand} at address {address}")
+            }
+            ComputerError::NegativeExponent { address, exponent } => {
+                write!(f, "Division by 2^{exponent} at address {address}")
+            }
+            ComputerError::UnknownRegister(register) => {
+                write!(f, "Register '{register}' does not exist")
+            }
+            ComputerError::InstructionBudgetExceeded(budget) => {
+                write!(f, "Program did not halt within {budget} instructions")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComputerError {}
+
+/// Snapshot of the three registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers {
+    pub a: i64,
+    pub b: i64,
+    pub c: i64,
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug)]
 pub struct ChronoSpatialComputer {
@@ -6,19 +71,9 @@ pub struct ChronoSpatialComputer {
     C: i64,
     instruction_pointer: usize,
     program: Vec<u8>,
-}
-
-impl Iterator for &mut ChronoSpatialComputer {
-    type Item = u8;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        while self.instruction_pointer < self.program.len() {
-            if let Some(output) = self.process_next() {
-                return Some(output);
-            }
-        }
-        None
-    }
+    /// Instructions executed since the program was last loaded or reset.
+    executed: u64,
+    instruction_budget: Option<u64>,
 }
 
 impl ChronoSpatialComputer {
@@ -30,105 +85,230 @@ impl ChronoSpatialComputer {
             C,
             instruction_pointer: 0,
             program: Vec::new(),
+            executed: 0,
+            instruction_budget: None,
+        }
+    }
+
+    /// Limits how many instructions may run before the program is considered stuck. None, the
+    /// default, runs until the program halts.
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self.A,
+            b: self.B,
+            c: self.C,
         }
     }
 
-    pub fn run_program(&mut self) -> Vec<u8> {
-        self.into_iter().collect::<Vec<u8>>()
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
     }
 
-    pub fn regit(&mut self, register: char, value: i64) {
+    /// The opcode and operand at the address, or None if there aren't two values left there.
+    pub fn instruction_at(&self, address: usize) -> Option<(u8, u8)> {
+        match self.program.get(address..address + 2) {
+            Some([opcode, operand]) => Some((*opcode, *operand)),
+            _ => None,
+        }
+    }
+
+    /// Instructions executed since the program was last loaded or reset.
+    pub fn executed(&self) -> u64 {
+        self.executed
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.instruction_pointer >= self.program.len()
+    }
+
+    /// Runs until the program halts, returning everything it output.
+    pub fn run_program(&mut self) -> Result<Vec<u8>, ComputerError> {
+        let mut output = Vec::new();
+        while let Some(value) = self.next_output()? {
+            output.push(value);
+        }
+        Ok(output)
+    }
+
+    /// Runs until the next output, or returns None if the program halts first.
+    pub fn next_output(&mut self) -> Result<Option<u8>, ComputerError> {
+        while !self.is_halted() {
+            if let Some(output) = self.step()? {
+                return Ok(Some(output));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn regit(&mut self, register: char, value: i64) -> Result<(), ComputerError> {
         match register {
             'A' => self.A = value,
             'B' => self.B = value,
             'C' => self.C = value,
-            _ => panic!("Register '{}' does not exist!", register),
+            _ => return Err(ComputerError::UnknownRegister(register)),
         }
+        Ok(())
     }
 
     pub fn program(&mut self, program: Vec<u8>) {
-        self.instruction_pointer = 0;
         self.program = program;
+        self.reset_program();
     }
 
     pub fn reset_program(&mut self) {
         self.instruction_pointer = 0;
+        self.executed = 0;
     }
 
-    fn process_next(&mut self) -> Option<u8> {
-        match self.program[self.instruction_pointer] {
-            0 => self.adv(),
-            1 => self.bxl(),
-            2 => self.bst(),
-            3 => self.jnz(),
-            4 => self.bxc(),
-            5 => {
-                return Some(self.out());
+    /// Executes a single instruction, returning its output if it has one. Does nothing once the
+    /// program has halted.
+    pub fn step(&mut self) -> Result<Option<u8>, ComputerError> {
+        if self.is_halted() {
+            return Ok(None);
+        }
+        if let Some(budget) = self.instruction_budget {
+            if self.executed >= budget {
+                return Err(ComputerError::InstructionBudgetExceeded(budget));
             }
-            6 => self.bdv(),
-            7 => self.cdv(),
-            _ => panic!(
-                "ChronoSpatial Computer crashed due to a invalid instruction_pointer! {}",
-                self.instruction_pointer
-            ),
         }
-        None
-    }
 
-    fn adv(&mut self) {
-        self.A /= 2_i64.pow(self.combo_operand() as u32);
-        self.instruction_pointer += 2;
-    }
-    fn bxl(&mut self) {
-        self.B ^= self.literal_operand();
-        self.instruction_pointer += 2;
-    }
-    fn bst(&mut self) {
-        self.B = self.combo_operand() % 8;
-        self.instruction_pointer += 2;
-    }
-    fn jnz(&mut self) {
-        if self.A == 0 {
-            self.instruction_pointer += 2;
-        } else {
-            self.instruction_pointer = self.literal_operand() as usize;
+        let address = self.instruction_pointer;
+        let opcode = self.program[address];
+        let opcode =
+            Opcode::from_u8(opcode).ok_or(ComputerError::InvalidOpcode { address, opcode })?;
+        if address + 1 >= self.program.len() {
+            return Err(ComputerError::MissingOperand { address });
+        }
+
+        let mut output = None;
+        match opcode {
+            Opcode::Adv => self.A = self.divide_a()?,
+            Opcode::Bxl => self.B ^= self.literal_operand(),
+            Opcode::Bst => self.B = self.combo_operand()?.rem_euclid(8),
+            Opcode::Jnz => {
+                if self.A != 0 {
+                    self.instruction_pointer = self.literal_operand() as usize;
+                    self.executed += 1;
+                    return Ok(None);
+                }
+            }
+            Opcode::Bxc => self.B ^= self.C,
+            Opcode::Out => output = Some(self.combo_operand()?.rem_euclid(8) as u8),
+            Opcode::Bdv => self.B = self.divide_a()?,
+            Opcode::Cdv => self.C = self.divide_a()?,
         }
-    }
-    fn bxc(&mut self) {
-        self.B ^= self.C;
-        self.instruction_pointer += 2;
-    }
-    fn out(&mut self) -> u8 {
-        let output = (self.combo_operand() % 8) as u8;
-        self.instruction_pointer += 2;
-        output
-    }
-    fn bdv(&mut self) {
-        self.B = self.A / 2_i64.pow(self.combo_operand() as u32);
         self.instruction_pointer += 2;
+        self.executed += 1;
+        Ok(output)
     }
-    fn cdv(&mut self) {
-        self.C = self.A / 2_i64.pow(self.combo_operand() as u32);
-        self.instruction_pointer += 2;
+
+    /// A / 2^combo, rounded towards zero like the puzzle expects.
+    fn divide_a(&self) -> Result<i64, ComputerError> {
+        let exponent = self.combo_operand()?;
+        match exponent {
+            ..0 => Err(ComputerError::NegativeExponent {
+                address: self.instruction_pointer,
+                exponent,
+            }),
+            // Anything in a i64 is smaller than 2^64, and 2^63 doesn't fit in one.
+            64.. => Ok(0),
+            _ => Ok((self.A as i128 / (1i128 << exponent)) as i64),
+        }
     }
 
     fn literal_operand(&self) -> i64 {
         self.program[self.instruction_pointer + 1] as i64
     }
 
-    fn combo_operand(&self) -> i64 {
+    fn combo_operand(&self) -> Result<i64, ComputerError> {
         let operand = self.program[self.instruction_pointer + 1];
 
-        match operand {
-            0..=3 => operand as i64,
-            4 => self.A,
-            5 => self.B,
-            6 => self.C,
-            _ => panic!("Invalid combo operand"),
+        match ComboOperand::from_u8(operand) {
+            Some(ComboOperand::Literal(value)) => Ok(value as i64),
+            Some(ComboOperand::A) => Ok(self.A),
+            Some(ComboOperand::B) => Ok(self.B),
+            Some(ComboOperand::C) => Ok(self.C),
+            None => Err(ComputerError::InvalidComboOperand {
+                address: self.instruction_pointer,
+                operand,
+            }),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(a: i64, program: &[u8]) -> Result<Vec<u8>, ComputerError> {
+        let mut computer = ChronoSpatialComputer::new(a, 0, 0);
+        computer.program(program.to_vec());
+        computer.set_instruction_budget(Some(1000));
+        computer.run_program()
+    }
+
+    #[test]
+    fn run_example_program() {
+        assert_eq!(
+            run(729, &[0, 1, 5, 4, 3, 0]),
+            Ok(vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0])
+        );
+    }
+
+    #[test]
+    fn errors_instead_of_panics() {
+        assert_eq!(
+            run(1, &[5, 0, 8, 0]),
+            Err(ComputerError::InvalidOpcode {
+                address: 2,
+                opcode: 8
+            })
+        );
+        assert_eq!(
+            run(1, &[5, 7]),
+            Err(ComputerError::InvalidComboOperand {
+                address: 0,
+                operand: 7
+            })
+        );
+        assert_eq!(
+            run(1, &[1, 2, 5]),
+            Err(ComputerError::MissingOperand { address: 2 })
+        );
+        // jnz back to the start with A never changing.
+        assert_eq!(
+            run(1, &[3, 0]),
+            Err(ComputerError::InstructionBudgetExceeded(1000))
+        );
+
+        let mut computer = ChronoSpatialComputer::new(0, 0, 0);
+        assert_eq!(
+            computer.regit('D', 1),
+            Err(ComputerError::UnknownRegister('D'))
+        );
+    }
+
+    #[test]
+    fn large_and_negative_exponents() {
+        // A = A / 2^A with A = 100 shifts everything out.
+        assert_eq!(run(100, &[0, 4, 5, 4]), Ok(vec![0]));
+        // B = B ^ C makes B negative, then A is divided by 2^B.
+        let mut computer = ChronoSpatialComputer::new(8, 0, -1);
+        computer.program(vec![4, 0, 0, 5]);
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::NegativeExponent {
+                address: 2,
+                exponent: -1
+            })
+        );
+    }
+}
+
 // Chronospatial computer.
 //
 // Each instruction reads the following 3-bit number after itself as an input.
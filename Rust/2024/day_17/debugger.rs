@@ -0,0 +1,292 @@
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+use crate::chronospatial_computer::{ChronoSpatialComputer, ComputerError, Registers};
+use crate::instruction::{ComboOperand, Opcode, OperandKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Register {
+    A,
+    B,
+    C,
+}
+
+impl Register {
+    const ALL: [Register; 3] = [Register::A, Register::B, Register::C];
+
+    fn read(self, registers: &Registers) -> i64 {
+        match self {
+            Register::A => registers.a,
+            Register::B => registers.b,
+            Register::C => registers.c,
+        }
+    }
+}
+
+impl Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// How many instructions ran before this one since the program was reset.
+    pub step: u64,
+    pub address: usize,
+    pub opcode: Opcode,
+    pub operand: u8,
+    pub before: Registers,
+    pub after: Registers,
+    pub output: Option<u8>,
+}
+
+impl TraceEntry {
+    /// The registers the instruction changed, with their old and new values.
+    pub fn changes(&self) -> Vec<(Register, i64, i64)> {
+        Register::ALL
+            .into_iter()
+            .map(|register| {
+                (
+                    register,
+                    register.read(&self.before),
+                    register.read(&self.after),
+                )
+            })
+            .filter(|(_, old, new)| old != new)
+            .collect()
+    }
+
+    fn instruction_text(&self) -> String {
+        let operand = match self.opcode.operand_kind() {
+            OperandKind::Combo => ComboOperand::from_u8(self.operand)
+                .map(|combo| combo.to_string())
+                .unwrap_or_else(|| self.operand.to_string()),
+            _ => self.operand.to_string(),
+        };
+        format!("{} {}", self.opcode, operand)
+    }
+}
+
+/// Why the debugger stopped running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Halted,
+    /// The instruction pointer reached a breakpoint. The instruction there hasn't run yet.
+    Breakpoint(usize),
+    /// The last instruction changed a watched register from old to new.
+    Watchpoint {
+        register: Register,
+        old: i64,
+        new: i64,
+    },
+}
+
+/// Runs a ChronoSpatial computer one instruction at a time, recording what each one did.
+///
+/// Breakpoints stop the run before the instruction at their address executes, watchpoints stop
+/// it right after a instruction changes the watched register.
+pub struct Debugger {
+    computer: ChronoSpatialComputer,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<Register>,
+    trace: Vec<TraceEntry>,
+    output: Vec<u8>,
+}
+
+impl Debugger {
+    pub fn new(computer: ChronoSpatialComputer) -> Self {
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            trace: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    pub fn computer(&self) -> &ChronoSpatialComputer {
+        &self.computer
+    }
+
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn watch(&mut self, register: Register) {
+        self.watchpoints.insert(register);
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// Everything output since the debugger was created.
+    pub fn output(&self) -> &[u8] {
+        &self.output
+    }
+
+    /// Executes a single instruction, returning None if the program has already halted.
+    pub fn step(&mut self) -> Result<Option<&TraceEntry>, ComputerError> {
+        if self.computer.is_halted() {
+            return Ok(None);
+        }
+
+        let step = self.computer.executed();
+        let address = self.computer.instruction_pointer();
+        let before = self.computer.registers();
+        // Stepping reports any invalid instruction, so after it succeeds both values are valid.
+        let output = self.computer.step()?;
+        let Some((opcode, operand)) = self.decoded_instruction_at(address) else {
+            unreachable!("The computer executed a instruction that can't be decoded");
+        };
+
+        if let Some(value) = output {
+            self.output.push(value);
+        }
+        self.trace.push(TraceEntry {
+            step,
+            address,
+            opcode,
+            operand,
+            before,
+            after: self.computer.registers(),
+            output,
+        });
+        Ok(self.trace.last())
+    }
+
+    fn decoded_instruction_at(&self, address: usize) -> Option<(Opcode, u8)> {
+        let (opcode, operand) = self.computer.instruction_at(address)?;
+        Some((Opcode::from_u8(opcode)?, operand))
+    }
+
+    /// Runs until the program halts, reaches a breakpoint or changes a watched register. Always
+    /// executes at least one instruction, so continuing from a breakpoint moves past it.
+    pub fn run(&mut self) -> Result<StopReason, ComputerError> {
+        let mut first = true;
+        loop {
+            let address = self.computer.instruction_pointer();
+            if !first && self.breakpoints.contains(&address) {
+                return Ok(StopReason::Breakpoint(address));
+            }
+            first = false;
+
+            let Some(entry) = self.step()? else {
+                return Ok(StopReason::Halted);
+            };
+            let watched = entry
+                .changes()
+                .into_iter()
+                .find(|(register, _, _)| self.watchpoints.contains(register));
+            if let Some((register, old, new)) = watched {
+                return Ok(StopReason::Watchpoint { register, old, new });
+            }
+        }
+    }
+
+    /// Writes the trace as a table with one row per executed instruction.
+    pub fn format_trace(&self) -> String {
+        let mut table = format!(
+            "{:>6} {:>4}  {:<12} {:<40} {}\n",
+            "step", "ip", "instruction", "changes", "out"
+        );
+        // Rows without output would otherwise end in the padding of the changes column.
+        for entry in &self.trace {
+            let changes: Vec<String> = entry
+                .changes()
+                .iter()
+                .map(|(register, old, new)| format!("{register}: {old} -> {new}"))
+                .collect();
+            let output = entry.output.map(|value| value.to_string());
+            let row = format!(
+                "{:>6} {:>4}  {:<12} {:<40} {}",
+                entry.step,
+                entry.address,
+                entry.instruction_text(),
+                changes.join(", "),
+                output.unwrap_or_default()
+            );
+            table.push_str(row.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    pub fn save_trace<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.format_trace())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from the puzzle: A = A / 2, output A % 8, repeat while A isn't 0.
+    fn example_debugger() -> Debugger {
+        let mut computer = ChronoSpatialComputer::new(729, 0, 0);
+        computer.program(vec![0, 1, 5, 4, 3, 0]);
+        Debugger::new(computer)
+    }
+
+    #[test]
+    fn step_records_trace() {
+        let mut debugger = example_debugger();
+        let entry = debugger.step().unwrap().unwrap().clone();
+        assert_eq!(
+            (entry.step, entry.address, entry.opcode),
+            (0, 0, Opcode::Adv)
+        );
+        assert_eq!(entry.changes(), vec![(Register::A, 729, 364)]);
+
+        let entry = debugger.step().unwrap().unwrap();
+        assert_eq!(entry.output, Some(4));
+        assert!(entry.changes().is_empty());
+        assert_eq!(debugger.output(), &[4]);
+    }
+
+    #[test]
+    fn breakpoints_and_watchpoints() {
+        let mut debugger = example_debugger();
+        debugger.add_breakpoint(4);
+        assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(4)));
+        assert_eq!(debugger.trace().len(), 2);
+        // Continuing runs past the breakpoint and stops at it on the next loop.
+        assert_eq!(debugger.run(), Ok(StopReason::Breakpoint(4)));
+        assert_eq!(debugger.output(), &[4, 6]);
+
+        let mut debugger = example_debugger();
+        debugger.watch(Register::A);
+        assert_eq!(
+            debugger.run(),
+            Ok(StopReason::Watchpoint {
+                register: Register::A,
+                old: 729,
+                new: 364
+            })
+        );
+        // A is halved once per loop until it reaches 0.
+        let mut stops = 1;
+        while debugger.run() != Ok(StopReason::Halted) {
+            stops += 1;
+        }
+        assert_eq!(stops, 10);
+        assert_eq!(debugger.output(), &[4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+        assert_eq!(debugger.step(), Ok(None));
+    }
+
+    #[test]
+    fn trace_table() {
+        let mut debugger = example_debugger();
+        debugger.step().unwrap();
+        debugger.step().unwrap();
+        let table = debugger.format_trace();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("adv 1") && lines[1].contains("A: 729 -> 364"));
+        assert!(lines[2].contains("out A") && lines[2].ends_with(" 4"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt::Display;
+
+/// The eight instructions of the ChronoSpatial computer, numbered by their opcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Opcode {
+    Adv,
+    Bxl,
+    Bst,
+    Jnz,
+    Bxc,
+    Out,
+    Bdv,
+    Cdv,
+}
+
+/// How a instruction reads the 3-bit value following it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperandKind {
+    /// The value itself.
+    Literal,
+    /// 0-3 are the value itself, 4-6 are register A, B and C. 7 is reserved.
+    Combo,
+    /// A jump target, which is a literal.
+    Address,
+    /// Read but not used.
+    Ignored,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 8] = [
+        Opcode::Adv,
+        Opcode::Bxl,
+        Opcode::Bst,
+        Opcode::Jnz,
+        Opcode::Bxc,
+        Opcode::Out,
+        Opcode::Bdv,
+        Opcode::Cdv,
+    ];
+
+    pub fn from_u8(value: u8) -> Option<Opcode> {
+        Self::ALL.get(value as usize).copied()
+    }
+
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Adv => "adv",
+            Opcode::Bxl => "bxl",
+            Opcode::Bst => "bst",
+            Opcode::Jnz => "jnz",
+            Opcode::Bxc => "bxc",
+            Opcode::Out => "out",
+            Opcode::Bdv => "bdv",
+            Opcode::Cdv => "cdv",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Opcode> {
+        Self::ALL
+            .into_iter()
+            .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+    }
+
+    pub fn operand_kind(self) -> OperandKind {
+        match self {
+            Opcode::Bxl => OperandKind::Literal,
+            Opcode::Jnz => OperandKind::Address,
+            Opcode::Bxc => OperandKind::Ignored,
+            Opcode::Adv | Opcode::Bst | Opcode::Out | Opcode::Bdv | Opcode::Cdv => {
+                OperandKind::Combo
+            }
+        }
+    }
+
+    /// Describes what the instruction does with the operand, like "B = A % 8".
+    pub fn describe(self, operand: u8) -> String {
+        let combo = ComboOperand::from_u8(operand)
+            .map(|combo| combo.to_string())
+            .unwrap_or_else(|| format!("<invalid combo {operand}>"));
+        match self {
+            Opcode::Adv => format!("A = A / 2^{combo}"),
+            Opcode::Bxl => format!("B = B ^ {operand}"),
+            Opcode::Bst => format!("B = {combo} % 8"),
+            Opcode::Jnz => format!("if A != 0 jump to {operand}"),
+            Opcode::Bxc => "B = B ^ C".to_string(),
+            Opcode::Out => format!("output {combo} % 8"),
+            Opcode::Bdv => format!("B = A / 2^{combo}"),
+            Opcode::Cdv => format!("C = A / 2^{combo}"),
+        }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic())
+    }
+}
+
+/// A decoded combo operand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComboOperand {
+    Literal(u8),
+    A,
+    B,
+    C,
+}
+
+impl ComboOperand {
+    /// Returns None for the reserved operand 7 and anything that isn't 3 bits.
+    pub fn from_u8(value: u8) -> Option<ComboOperand> {
+        match value {
+            0..=3 => Some(ComboOperand::Literal(value)),
+            4 => Some(ComboOperand::A),
+            5 => Some(ComboOperand::B),
+            6 => Some(ComboOperand::C),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ComboOperand::Literal(value) => value,
+            ComboOperand::A => 4,
+            ComboOperand::B => 5,
+            ComboOperand::C => 6,
+        }
+    }
+
+    /// Parses a register name. Literals are handled by the caller since they are plain numbers.
+    pub fn from_register(name: &str) -> Option<ComboOperand> {
+        match name {
+            "A" | "a" => Some(ComboOperand::A),
+            "B" | "b" => Some(ComboOperand::B),
+            "C" | "c" => Some(ComboOperand::C),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ComboOperand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ComboOperand::Literal(value) => write!(f, "{value}"),
+            ComboOperand::A => write!(f, "A"),
+            ComboOperand::B => write!(f, "B"),
+            ComboOperand::C => write!(f, "C"),
+        }
+    }
+}
@@ -1,11 +1,16 @@
+use std::env;
 use std::io;
+use std::num::ParseIntError;
 
-mod operations;
-use operations::Operation;
-use operations::OperationResult;
-
+mod assembler;
+use assembler::{assemble, disassemble, AssembleError};
 mod chronospatial_computer;
-use chronospatial_computer::ChronoSpatialComputer;
+use chronospatial_computer::{ChronoSpatialComputer, ComputerError};
+mod debugger;
+use debugger::{Debugger, Register, StopReason};
+mod instruction;
+mod quine;
+use quine::{QuineError, QuineSolver};
 mod reader;
 use reader::get_lines;
 mod testing_debug;
@@ -13,6 +18,11 @@ mod testing_debug;
 #[derive(Debug)]
 enum AdventError {
     IoError(io::Error),
+    ParseError(ParseIntError),
+    CorruptedData(String),
+    ComputerError(ComputerError),
+    QuineError(QuineError),
+    AssembleError(AssembleError),
 }
 
 impl From<io::Error> for AdventError {
@@ -21,47 +31,56 @@ impl From<io::Error> for AdventError {
     }
 }
 
-fn save_value(file_data: &mut Vec<i64>, operation_result: OperationResult) {
-    if let OperationResult::Integer(value) = operation_result {
-        file_data.push(value);
+impl From<ComputerError> for AdventError {
+    fn from(err: ComputerError) -> Self {
+        Self::ComputerError(err)
     }
 }
 
-fn read_data(path: &str) -> Result<(ChronoSpatialComputer, Vec<u8>), AdventError> {
-    let lines = get_lines(path)?;
-
-    let mut file_data: Vec<i64> = Vec::new();
-    let mut operation: Operation = Operation::None;
-    for line in lines {
-        for char in line.chars() {
-            let (next_op, potential_result) = operation.next(char);
-            operation = next_op;
-
-            let Some(operation_result) = potential_result else {
-                continue;
-            };
-
-            save_value(&mut file_data, operation_result);
-        }
+impl From<QuineError> for AdventError {
+    fn from(err: QuineError) -> Self {
+        Self::QuineError(err)
+    }
+}
 
-        let (next_op, operation_result) = operation.collect_operation();
-        operation = next_op;
+impl From<AssembleError> for AdventError {
+    fn from(err: AssembleError) -> Self {
+        Self::AssembleError(err)
+    }
+}
 
-        save_value(&mut file_data, operation_result);
+impl From<ParseIntError> for AdventError {
+    fn from(err: ParseIntError) -> Self {
+        Self::ParseError(err)
     }
+}
 
-    if file_data.len() < 4 {
-        panic!(
-            "Data file read didn't find enough values for the computer! {}\n{:?}",
-            file_data.len(),
-            file_data
-        );
+/// Reads the "Register X: value" lines and the "Program: 0,1,2" line.
+fn read_data(path: &str) -> Result<(ChronoSpatialComputer, Vec<u8>), AdventError> {
+    let mut registers: Vec<i64> = Vec::new();
+    let mut program: Vec<u8> = Vec::new();
+    for line in get_lines(path)? {
+        let Some((name, values)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim() {
+            "Program" => {
+                program = values
+                    .split(',')
+                    .map(|value| value.trim().parse::<u8>())
+                    .collect::<Result<_, _>>()?
+            }
+            _ => registers.push(values.trim().parse()?),
+        }
     }
 
-    Ok((
-        ChronoSpatialComputer::new(file_data[0], file_data[1], file_data[2]),
-        file_data.iter().skip(3).map(|value| *value as u8).collect(),
-    ))
+    let [a, b, c] = registers[..] else {
+        return Err(AdventError::CorruptedData(format!(
+            "Expected 3 registers but found {:?}",
+            registers
+        )));
+    };
+    Ok((ChronoSpatialComputer::new(a, b, c), program))
 }
 
 fn assemble_result(result_vec: Vec<u8>) -> String {
@@ -77,7 +96,7 @@ fn assemble_result(result_vec: Vec<u8>) -> String {
     output_string
 }
 
-fn program_contains(program: &Vec<u8>, result: &Vec<u8>) -> bool {
+fn program_contains(program: &[u8], result: &[u8]) -> bool {
     let diff = program.len() - result.len();
     for (index, value) in result.iter().enumerate() {
         if program[index + diff] != *value {
@@ -92,34 +111,23 @@ fn calculate(path: &str) -> Result<i64, AdventError> {
     println!("Working...  ");
     let (mut computer, program) = read_data(path)?;
 
-    let mut a_value = 0;
-    computer.program(program.clone());
-    for i in (0..program.len()).rev() {
-        computer.regit('A', a_value);
-        let mut a_cache = 0;
-        loop {
-            computer.reset_program();
-            computer.regit('A', a_cache + (a_value << 3));
-            let result = computer.run_program();
-            if !result.is_empty() && result[0] == program[i] && program_contains(&program, &result)
-            {
-                break;
-            }
-            a_cache += 1;
-        }
-        a_value = a_cache + (a_value << 3);
-    }
+    let a_value = QuineSolver::new(&program)?.solve()?;
     println!("DONE!");
 
-    confirm_result(&mut computer, &program, a_value);
+    computer.program(program.clone());
+    confirm_result(&mut computer, &program, a_value)?;
 
     Ok(a_value)
 }
 
-fn confirm_result(computer: &mut ChronoSpatialComputer, expected_result: &Vec<u8>, a_value: i64) {
+fn confirm_result(
+    computer: &mut ChronoSpatialComputer,
+    expected_result: &[u8],
+    a_value: i64,
+) -> Result<(), ComputerError> {
     computer.reset_program();
-    computer.regit('A', a_value);
-    let result = computer.run_program();
+    computer.regit('A', a_value)?;
+    let result = computer.run_program()?;
     if !program_contains(expected_result, &result) {
         panic!(
             "Result did not match program!\np: {}\nr: {}",
@@ -127,22 +135,79 @@ fn confirm_result(computer: &mut ChronoSpatialComputer, expected_result: &Vec<u8
             assemble_result(result)
         );
     }
+    Ok(())
+}
+
+/// Prints the program as assembly, after checking that the listing assembles back into it.
+fn print_listing(path: &str) -> Result<(), AdventError> {
+    let (_, program) = read_data(path)?;
+    let listing = disassemble(&program);
+    if assemble(&listing)? != program {
+        return Err(AdventError::CorruptedData(
+            "The listing doesn't assemble back into the program".to_string(),
+        ));
+    }
+    println!("Program:\n{listing}");
+    Ok(())
 }
 
-fn view_bits(value: i64) {
-    let mut i = 2;
-    for n in (0..64).rev() {
-        print!("{}", (value >> n) & 1);
-        i += 1;
-        if i >= 3 {
-            i = 0;
-            print!(" ");
+const TRACE_PATH: &str = "trace.txt";
+
+/// Runs the program one instruction at a time, printing every change of A, which happens once per
+/// loop, and the registers at each breakpoint. The whole trace is saved to trace.txt.
+///
+/// args are the value for A, the one in the data if left out, followed by breakpoint addresses.
+fn trace_program(path: &str, args: &[String]) -> Result<(), AdventError> {
+    let (mut computer, program) = read_data(path)?;
+    computer.program(program);
+    if let Some(a_value) = args.first() {
+        computer.regit('A', a_value.parse()?)?;
+    }
+
+    let mut debugger = Debugger::new(computer);
+    for address in &args[args.len().min(1)..] {
+        debugger.add_breakpoint(address.parse()?);
+    }
+    debugger.watch(Register::A);
+    loop {
+        match debugger.run()? {
+            StopReason::Halted => break,
+            StopReason::Breakpoint(address) => {
+                let registers = debugger.computer().registers();
+                println!(
+                    "Breakpoint at {address}: A: {}, B: {}, C: {}",
+                    registers.a, registers.b, registers.c
+                );
+            }
+            StopReason::Watchpoint { register, old, new } => {
+                println!(
+                    "{register}: {old} -> {new}, output: {:?}",
+                    debugger.output()
+                )
+            }
         }
     }
-    println!(" = {}", value);
+    debugger.save_trace(TRACE_PATH)?;
+    println!(
+        "Saved {} instructions to {TRACE_PATH}",
+        debugger.trace().len()
+    );
+    Ok(())
 }
 
 fn main() {
+    // "cargo run -- trace [a] [address]..." steps through the program instead.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "trace") {
+        if let Err(err) = trace_program("data.txt", &args[1..]) {
+            println!("An error occured: {err:?}");
+        }
+        return;
+    }
+
+    if let Err(err) = print_listing("data.txt") {
+        println!("An error occured: {err:?}");
+    }
     match calculate("data.txt") {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: \n{}", value),
@@ -173,6 +238,25 @@ Challenge part 1:
 
 Result: 1,7,6,5,1,0,5,0,7
 
+Update: The computer used to panic on anything unexpected, like a invalid opcode or the reserved
+combo operand 7. Stepping and running now return a ComputerError instead, and a instruction
+budget can be set so a program that never halts stops with a error rather than hanging the search
+in part two.
+
+Update: Instead of dumping values with view_bits, the debugger module can step through a program
+one instruction at a time. It stops at breakpoints on the instruction pointer or when a watched
+register changes, and records a trace of every instruction with the registers it changed and what
+it output. The trace can be printed as a table or saved to a file. "cargo run -- trace [a]
+[address]..." runs my input that way, printing A once per loop and the registers at every
+breakpoint, and saves the trace to trace.txt.
+
+Update: The search for part two moved to the quine module and no longer assumes the shape of my
+input. It first checks the program is a single loop ending in jnz 0, shifting A by a constant
+number of bits with one adv, outputting one value per pass, and setting B and C from A before
+reading them. Each output then only depends on the bits of A that are left at that pass, so A is
+built from the last output backwards, trying every value for the next bits and backtracking when
+none of them work. Programs that don't fit get a QuineError saying which assumption failed.
+
 Challenge part 2:
 
 So part two of the challenge is kind of reversed.
@@ -255,6 +339,19 @@ i5: x % 8 = 0 | x -> B
 i1: B xor 5 -> B
 i0: x / (2*2) = x / 8 = 0 ->
 
+Update: This day's assembler module writes listings like the one above from any program. Each
+jnz target gets a label and every instruction a comment with what it does, and the listing can be
+assembled back into the same program bytes, which main checks before printing it:
+L0:
+    bst A       ;  0: B = A % 8
+    bxl 3       ;  2: B = B ^ 3
+    cdv B       ;  4: C = A / 2^B
+    bxc 2       ;  6: B = B ^ C
+    adv 3       ;  8: A = A / 2^3
+    bxl 5       ; 10: B = B ^ 5
+    out B       ; 12: output B % 8
+    jnz L0      ; 14: if A != 0 jump to 0
+
 I have been thinking alot about this, and while I don't like how this is done, I feel like the best way is to make a smarter number iterator.
 Instead of adding one, when we find the correct output, we add one multiplied by the output index times 3. That "should" give me the result I want.
 WRONG.....
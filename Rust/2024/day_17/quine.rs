@@ -0,0 +1,255 @@
+use std::fmt::Display;
+
+use crate::chronospatial_computer::{ChronoSpatialComputer, ComputerError};
+use crate::instruction::{ComboOperand, Opcode, OperandKind};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuineError {
+    /// The value at the address isn't a complete, valid instruction.
+    InvalidInstruction(usize),
+    /// The program doesn't end with "jnz 0", so it isn't a single loop over A.
+    NotALoop,
+    /// A jnz before the end of the program, making the loop body branch.
+    InnerJump(usize),
+    /// A is never shifted, so the loop would never end.
+    NoShift,
+    /// A is shifted by something other than a literal 1 to 3 bits.
+    ShiftNotConstant(usize),
+    /// A is shifted more than once per loop.
+    MultipleShifts(usize),
+    /// The loop has to output exactly one value per iteration.
+    OutputCount(usize),
+    /// The register is read before it is written in the loop, so each output would depend on the
+    /// previous iterations and not only on A.
+    ReadsPreviousIteration {
+        register: char,
+        address: usize,
+    },
+    /// No value of A produces the wanted output.
+    NoSolution,
+    ComputerError(ComputerError),
+}
+
+impl Display for QuineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuineError::InvalidInstruction(address) => {
+                write!(f, "Invalid instruction at address {address}")
+            }
+            QuineError::NotALoop => write!(f, "Program doesn't end with jnz 0"),
+            QuineError::InnerJump(address) => {
+                write!(f, "Jump at address {address} inside the loop")
+            }
+            QuineError::NoShift => write!(f, "A is never shifted by adv"),
+            QuineError::ShiftNotConstant(address) => write!(
+                f,
+                "adv at address {address} doesn't shift by a literal 1 to 3"
+            ),
+            QuineError::MultipleShifts(address) => {
+                write!(f, "A is shifted a second time at address {address}")
+            }
+            QuineError::OutputCount(count) => {
+                write!(f, "Loop outputs {count} values instead of 1")
+            }
+            QuineError::ReadsPreviousIteration { register, address } => write!(
+                f,
+                "Register {register} is read at address {address} before it is written"
+            ),
+            QuineError::NoSolution => write!(f, "No value of A produces the output"),
+            QuineError::ComputerError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for QuineError {}
+
+impl From<ComputerError> for QuineError {
+    fn from(err: ComputerError) -> Self {
+        Self::ComputerError(err)
+    }
+}
+
+/// Finds values of register A that make a program output a given sequence, like itself.
+///
+/// Only works for programs shaped like the puzzle inputs: a single loop ending in "jnz 0", which
+/// shifts A right by a constant k bits and outputs one value per iteration, with B and C worked
+/// out from A each time. Then output i only depends on A >> (k * i), so A can be built k bits at
+/// a time starting from the last output, where only the highest bits of A are left.
+#[derive(Debug)]
+pub struct QuineSolver {
+    program: Vec<u8>,
+    /// How many bits of A each loop iteration consumes.
+    bits_per_output: u32,
+}
+
+impl QuineSolver {
+    /// Checks that the program has the expected loop shape, reporting the first thing that
+    /// doesn't fit.
+    pub fn new(program: &[u8]) -> Result<QuineSolver, QuineError> {
+        if program.len() < 2 || !program.len().is_multiple_of(2) {
+            return Err(QuineError::InvalidInstruction(program.len() & !1));
+        }
+        let (body, end) = program.split_at(program.len() - 2);
+        if end != [Opcode::Jnz.as_u8(), 0] {
+            return Err(QuineError::NotALoop);
+        }
+
+        let mut shift = None;
+        let mut outputs = 0;
+        let (mut b_written, mut c_written) = (false, false);
+        for (index, pair) in body.chunks(2).enumerate() {
+            let address = index * 2;
+            let (opcode, operand) = (pair[0], pair[1]);
+            let opcode = Opcode::from_u8(opcode).ok_or(QuineError::InvalidInstruction(address))?;
+
+            let reads_previous =
+                |register| QuineError::ReadsPreviousIteration { register, address };
+            if opcode.operand_kind() == OperandKind::Combo {
+                match ComboOperand::from_u8(operand) {
+                    None => return Err(QuineError::InvalidInstruction(address)),
+                    Some(ComboOperand::B) if !b_written => return Err(reads_previous('B')),
+                    Some(ComboOperand::C) if !c_written => return Err(reads_previous('C')),
+                    _ => {}
+                }
+            }
+
+            match opcode {
+                Opcode::Bxl | Opcode::Bxc if !b_written => return Err(reads_previous('B')),
+                Opcode::Bxc if !c_written => return Err(reads_previous('C')),
+                Opcode::Jnz => return Err(QuineError::InnerJump(address)),
+                Opcode::Adv => {
+                    if !(1..=3).contains(&operand) {
+                        return Err(QuineError::ShiftNotConstant(address));
+                    }
+                    if shift.replace(operand as u32).is_some() {
+                        return Err(QuineError::MultipleShifts(address));
+                    }
+                }
+                Opcode::Out => outputs += 1,
+                _ => {}
+            }
+
+            match opcode {
+                Opcode::Bxl | Opcode::Bst | Opcode::Bxc | Opcode::Bdv => b_written = true,
+                Opcode::Cdv => c_written = true,
+                _ => {}
+            }
+        }
+
+        let bits_per_output = shift.ok_or(QuineError::NoShift)?;
+        if outputs != 1 {
+            return Err(QuineError::OutputCount(outputs));
+        }
+        Ok(QuineSolver {
+            program: program.to_vec(),
+            bits_per_output,
+        })
+    }
+
+    /// Returns the lowest A that makes the program output itself.
+    pub fn solve(&self) -> Result<i64, QuineError> {
+        self.solve_for(&self.program)
+    }
+
+    /// Returns the lowest A that makes the program output exactly the target.
+    pub fn solve_for(&self, target: &[u8]) -> Result<i64, QuineError> {
+        // The loop body always runs once, so there is at least one output.
+        if target.is_empty() {
+            return Err(QuineError::NoSolution);
+        }
+        let mut computer = ChronoSpatialComputer::new(0, 0, 0);
+        computer.program(self.program.clone());
+        // One pass through the loop per output, plus one to notice any extra output.
+        let iteration_length = self.program.len() as u64 / 2;
+        computer.set_instruction_budget(Some(iteration_length * (target.len() as u64 + 1)));
+
+        self.extend(&mut computer, target, target.len(), 0)?
+            .ok_or(QuineError::NoSolution)
+    }
+
+    /// Tries every value for the next k bits of A, lowest first, so the first complete solution
+    /// found is the lowest one. a_prefix already outputs target[matched..].
+    fn extend(
+        &self,
+        computer: &mut ChronoSpatialComputer,
+        target: &[u8],
+        matched: usize,
+        a_prefix: i64,
+    ) -> Result<Option<i64>, QuineError> {
+        if matched == 0 {
+            return Ok(Some(a_prefix));
+        }
+
+        let Some(shifted) = a_prefix.checked_mul(1 << self.bits_per_output) else {
+            return Ok(None);
+        };
+        for bits in 0..1 << self.bits_per_output {
+            let a = shifted + bits;
+            computer.reset_program();
+            computer.regit('A', a)?;
+            let output = match computer.run_program() {
+                Ok(output) => output,
+                Err(ComputerError::InstructionBudgetExceeded(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            if output == target[matched - 1..] {
+                if let Some(solution) = self.extend(computer, target, matched - 1, a)? {
+                    return Ok(Some(solution));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_puzzle_programs() {
+        // The example: adv 3, out A, jnz 0.
+        let solver = QuineSolver::new(&[0, 3, 5, 4, 3, 0]).unwrap();
+        assert_eq!(solver.bits_per_output, 3);
+        assert_eq!(solver.solve(), Ok(117440));
+
+        let program = [2, 4, 1, 3, 7, 5, 4, 2, 0, 3, 1, 5, 5, 5, 3, 0];
+        assert_eq!(
+            QuineSolver::new(&program).unwrap().solve(),
+            Ok(236555995274861)
+        );
+    }
+
+    #[test]
+    fn other_shifts_and_targets() {
+        // out A, adv 2, jnz 0 outputs A % 8 and shifts it by 2 bits, so outputs overlap.
+        let solver = QuineSolver::new(&[5, 4, 0, 2, 3, 0]).unwrap();
+        assert_eq!(solver.bits_per_output, 2);
+        assert_eq!(solver.solve_for(&[6, 5, 3]), Ok(0b11_01_10));
+        // The last output is below 4, as only 2 bits of A are left.
+        assert_eq!(solver.solve_for(&[7]), Err(QuineError::NoSolution));
+    }
+
+    #[test]
+    fn rejects_other_shapes() {
+        let error = |program: &[u8]| QuineSolver::new(program).unwrap_err();
+        assert_eq!(error(&[0, 3, 5, 4]), QuineError::NotALoop);
+        assert_eq!(error(&[0, 3, 3, 2, 5, 4, 3, 0]), QuineError::InnerJump(2));
+        assert_eq!(error(&[5, 4, 3, 0]), QuineError::NoShift);
+        assert_eq!(error(&[0, 4, 5, 4, 3, 0]), QuineError::ShiftNotConstant(0));
+        assert_eq!(
+            error(&[0, 1, 0, 1, 5, 4, 3, 0]),
+            QuineError::MultipleShifts(2)
+        );
+        assert_eq!(error(&[0, 3, 3, 0]), QuineError::OutputCount(0));
+        assert_eq!(
+            error(&[0, 3, 5, 5, 3, 0]),
+            QuineError::ReadsPreviousIteration {
+                register: 'B',
+                address: 2
+            }
+        );
+        assert_eq!(error(&[9, 3, 3, 0]), QuineError::InvalidInstruction(0));
+    }
+}
@@ -1,5 +1,7 @@
+use std::env;
 use std::io;
 
+mod input_generator;
 mod operations;
 use operations::Operation;
 use operations::OperationResult;
@@ -11,6 +13,7 @@ use vector::VectorI16;
 
 mod reader;
 use reader::get_lines;
+mod random;
 mod testing_debug;
 
 #[derive(Debug)]
@@ -168,8 +171,12 @@ fn calculate(
     initial_delay: usize,
     size_override: usize,
 ) -> Result<VectorI16, AdventError> {
-    let mut map = get_map(path, size_override)?;
+    let map = get_map(path, size_override)?;
+    Ok(find_blocking_byte(map, initial_delay))
+}
 
+/// Drops bytes until no path to the goal is left, returning the byte that blocked the last one.
+fn find_blocking_byte(mut map: Map, initial_delay: usize) -> VectorI16 {
     // keep this since we know already that a path exists at this time. 1024 for the full data and
     // 12 for the test_data.
     for _i in 0..initial_delay {
@@ -193,10 +200,20 @@ fn calculate(
         };
     }
 
-    Ok(map.falling_bytes[map.age - 1])
+    map.falling_bytes[map.age - 1]
 }
 
 fn main() {
+    // "cargo run -- generate <kind> [seed] [size] [amount]" prints a generated input instead.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "generate") {
+        match input_generator::run_command(&args[1..]) {
+            Err(err) => println!("An error occured: {err}"),
+            Ok(lines) => println!("{}", lines.join("\n")),
+        }
+        return;
+    }
+
     let initial_delay = 1024;
     let size_override = 71;
     match calculate("data.txt", initial_delay, size_override) {
@@ -215,6 +232,58 @@ fn calculate_test() {
     }
 }
 
+#[test]
+fn calculate_generated_test() {
+    // Every free tile gets a byte, so the way is blocked before they run out.
+    let size = 15;
+    let lines =
+        input_generator::byte_fall(size, size * size - 2, &mut random::Random::new(18)).unwrap();
+    let falling_bytes: Vec<VectorI16> = lines
+        .iter()
+        .map(|line| {
+            let (x, y) = line.split_once(',').unwrap();
+            VectorI16::from((x.parse::<i16>().unwrap(), y.parse::<i16>().unwrap()))
+        })
+        .collect();
+    let blocking = find_blocking_byte(Map::new(size, falling_bytes.clone()), 0);
+
+    // A plain flood fill agrees that the way is open until the blocking byte lands.
+    let age = falling_bytes
+        .iter()
+        .position(|byte| *byte == blocking)
+        .unwrap()
+        + 1;
+    assert!(path_exists(size, &falling_bytes[..age - 1]));
+    assert!(!path_exists(size, &falling_bytes[..age]));
+}
+
+#[cfg(test)]
+fn path_exists(size: usize, corrupted: &[VectorI16]) -> bool {
+    let mut blocked = vec![false; size * size];
+    for byte in corrupted {
+        blocked[byte.y as usize * size + byte.x as usize] = true;
+    }
+    let mut stack = vec![(0, 0)];
+    blocked[0] = true;
+    while let Some((x, y)) = stack.pop() {
+        if (x, y) == (size - 1, size - 1) {
+            return true;
+        }
+        for (x, y) in [
+            (x + 1, y),
+            (x, y + 1),
+            (x.wrapping_sub(1), y),
+            (x, y.wrapping_sub(1)),
+        ] {
+            if x < size && y < size && !blocked[y * size + x] {
+                blocked[y * size + x] = true;
+                stack.push((x, y));
+            }
+        }
+    }
+    false
+}
+
 /* Sudo code:
 
 Challenge part 1:
@@ -243,4 +312,13 @@ Basically, we calculate the path, if a path is found we step time once and calcu
 When no path is found we return the last byte fallen.
 A slight optimization is to start the simulation from a age of 1024 for the full data, and 12 for the test_data.
 
+Update: "cargo run -- generate bytes <seed> <size> <amount>" prints a seeded list of falling
+bytes from the shared input_generator module. Every byte lands on its own tile and the corners stay
+free. The default amount is every free tile, size * size - 2, so the path is always cut at some
+point, while a smaller amount may leave it open. The generator doesn't check this day's limits:
+main reads data.txt as a 71 by 71 grid and drops the first 1024 bytes before searching. So a
+generated data.txt needs size 71, as larger sizes don't fit GRIDSIZE, and enough bytes to cut the
+path after those 1024, otherwise the search panics when it runs out of bytes. A test runs the
+search on a generated list and checks the answer with a plain flood fill.
+
 */
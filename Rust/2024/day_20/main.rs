@@ -1,11 +1,18 @@
 use std::collections::HashMap;
+use std::env;
 use std::io;
 
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod bitset;
+use bitset::BitSet;
+mod input_generator;
 mod vector;
 use vector::VectorI16;
 
 mod reader;
 use reader::get_lines;
+mod random;
+mod search;
 mod testing_debug;
 
 #[derive(Debug)]
@@ -51,55 +58,55 @@ impl ValidLocation {
         self.0
     }
 
+    /// Position of the location in the track bits.
+    fn index(&self) -> usize {
+        self.0.y as usize * GRIDSIZE + self.0.x as usize
+    }
+
     fn steps_to(&self, other: &ValidLocation) -> u32 {
         let other = other.extract();
         (self.0.x.abs_diff(other.x) + self.0.y.abs_diff(other.y)) as u32
     }
 }
 
-#[derive(Clone, Copy)]
-enum Tile {
-    Blocked,
-    Empty { time: u32 },
-    Start { time: u32 },
-    Goal { time: u32 },
-}
-
 struct Map {
-    grid: [[Tile; GRIDSIZE]; GRIDSIZE],
+    /// Set for every tile of the track, S and E included. Everything else is a wall.
+    track: BitSet,
     start: ValidLocation,
     goal: ValidLocation,
 }
 
 fn get_map(path: &str) -> Result<Map, AdventError> {
-    let lines = get_lines(path)?;
+    parse_map(get_lines(path)?)
+}
 
-    let mut grid: [[Tile; GRIDSIZE]; GRIDSIZE] = [[Tile::Blocked; GRIDSIZE]; GRIDSIZE];
+fn parse_map(lines: impl IntoIterator<Item = String>) -> Result<Map, AdventError> {
+    let mut track = BitSet::new(GRIDSIZE * GRIDSIZE);
     let mut start: ValidLocation = ValidLocation::new(VectorI16::from((0, 0)))
         .expect("0,0 should always be a valid location!");
     let mut goal: ValidLocation = ValidLocation::new(VectorI16::from((0, 0)))
         .expect("0,0 should always be a valid location!");
-    for (y, line) in lines.enumerate() {
+    for (y, line) in lines.into_iter().enumerate() {
         for (x, char) in line.chars().enumerate() {
             match char {
-                '.' => grid[x][y] = Tile::Empty { time: 0 },
+                '.' => track.insert(y * GRIDSIZE + x),
                 'S' => {
                     start = ValidLocation::new(VectorI16::from((x, y)))
                         .expect("Unexpected invalid location for Start! x:{x}, y:{y}");
-                    grid[x][y] = Tile::Start { time: 0 };
+                    track.insert(start.index());
                 }
                 'E' => {
                     goal = ValidLocation::new(VectorI16::from((x, y)))
                         .expect("Unexpected invalid location for Start! x:{x}, y:{y}");
-                    grid[x][y] = Tile::Goal { time: 0 }
+                    track.insert(goal.index());
                 }
-                '#' => {} // Blocked is the default already.
+                '#' => {} // Walls are the default already.
                 _ => return Err(AdventError::CorruptedDataFile),
             }
         }
     }
 
-    Ok(Map { grid, start, goal })
+    Ok(Map { track, start, goal })
 }
 
 fn get_neighbours(current: ValidLocation) -> Vec<ValidLocation> {
@@ -113,129 +120,23 @@ fn get_neighbours(current: ValidLocation) -> Vec<ValidLocation> {
     result
 }
 
-fn view<'a>(map: &'a [[Tile; GRIDSIZE]; GRIDSIZE], location: &ValidLocation) -> &'a Tile {
-    &map[location.0.x as usize][location.0.y as usize]
-}
-
-fn edit(map: &mut [[Tile; GRIDSIZE]; GRIDSIZE], location: &ValidLocation, tile: Tile) {
-    map[location.0.x as usize][location.0.y as usize] = tile;
-}
-
-fn scan_map(map: &mut [[Tile; GRIDSIZE]; GRIDSIZE], current: ValidLocation) {
-    let mut cache = vec![current];
-    let mut iteration_count: u32 = 1;
-
-    while !cache.is_empty() {
-        let mut next = Vec::new();
-        for location in &cache {
-            for neighbour in get_neighbours(*location) {
-                match view(map, &neighbour) {
-                    Tile::Empty { time } => {
-                        if *time != 0 {
-                            continue;
-                        }
-                        edit(
-                            map,
-                            &neighbour,
-                            Tile::Empty {
-                                time: iteration_count,
-                            },
-                        );
-                        next.push(neighbour);
-                    }
-                    Tile::Start { time } => {
-                        if *time != 0 {
-                            continue;
-                        }
-                        edit(
-                            map,
-                            &neighbour,
-                            Tile::Start {
-                                time: iteration_count,
-                            },
-                        );
-                    }
-                    _ => {}
-                }
-            }
-        }
-        cache = next;
-        iteration_count += 1;
-    }
-}
-
-fn process_neighbours_with_cheats(
-    location: &ValidLocation,
-    new_map: &mut [[Tile; GRIDSIZE]; GRIDSIZE],
-    existing_map: &[[Tile; GRIDSIZE]; GRIDSIZE],
-    iteration_count: u32,
-    expected_time: u32,
-    cheats: &mut HashMap<u32, u32>,
-    next: &mut Vec<ValidLocation>,
-) {
-    for neighbour in get_neighbours(*location) {
-        let Tile::Empty { time } = view(new_map, &neighbour) else {
-            continue;
-        };
-        if *time != 0 {
-            continue;
-        }
-        edit(
-            new_map,
-            &neighbour,
-            Tile::Empty {
-                time: iteration_count,
-            },
-        );
-        next.push(neighbour);
-        cheat(
-            existing_map,
-            neighbour,
-            iteration_count,
-            expected_time,
-            cheats,
-        );
-    }
-}
-
-fn scan_map_and_cheat(
-    new_map: &mut [[Tile; GRIDSIZE]; GRIDSIZE],
-    existing_map: &[[Tile; GRIDSIZE]; GRIDSIZE],
-    current: ValidLocation,
-    expected_time: u32,
-) -> HashMap<u32, u32> {
-    let mut cache = vec![current];
-    let mut iteration_count: u32 = 1;
-    let mut cheats: HashMap<u32, u32> = HashMap::new();
-
-    cheat(existing_map, current, 0, expected_time, &mut cheats);
-
-    while !cache.is_empty() {
-        let mut next = Vec::new();
-        for location in &cache {
-            process_neighbours_with_cheats(
-                location,
-                new_map,
-                existing_map,
-                iteration_count,
-                expected_time,
-                &mut cheats,
-                &mut next,
-            );
-        }
-        cache = next;
-        iteration_count += 1;
-    }
-
-    cheats
+/// Maps out the time required to reach every track tile from the provided location.
+fn scan_map(track: &BitSet, current: ValidLocation) -> HashMap<VectorI16, u64> {
+    search::distance_field(current.extract(), |location: &VectorI16| {
+        get_neighbours(ValidLocation(*location))
+            .into_iter()
+            .filter(|neighbour| track.contains(neighbour.index()))
+            .map(|neighbour| (neighbour.extract(), 1))
+            .collect()
+    })
 }
 
 fn cheat(
-    map: &[[Tile; GRIDSIZE]; GRIDSIZE],
+    times_to_goal: &HashMap<VectorI16, u64>,
     start_point: ValidLocation,
-    time_at_cheat_origin: u32,
-    expected_time: u32,
-    cheats: &mut HashMap<u32, u32>,
+    time_at_cheat_origin: u64,
+    expected_time: u64,
+    cheats: &mut HashMap<u64, u32>,
 ) {
     let x_min = (start_point.extract().x - 20).clamp(0, GRIDSIZE as i16);
     let x_max = (start_point.extract().x + 21).clamp(0, GRIDSIZE as i16);
@@ -253,11 +154,11 @@ fn cheat(
             let goal_point =
                 ValidLocation::new(VectorI16::from((x, y))).expect("This should never fail.");
 
-            let (Tile::Empty { time } | Tile::Goal { time }) = view(map, &goal_point) else {
+            let Some(time) = times_to_goal.get(&goal_point.extract()) else {
                 continue;
             };
 
-            let cheat_steps = start_point.steps_to(&goal_point);
+            let cheat_steps = start_point.steps_to(&goal_point) as u64;
             if cheat_steps > 20 {
                 continue;
             }
@@ -271,22 +172,32 @@ fn cheat(
     }
 }
 
-fn calculate(path: &str, minimum_cheat_save: u32) -> Result<u64, AdventError> {
+fn calculate(path: &str, minimum_cheat_save: u64) -> Result<u64, AdventError> {
     println!("Working...");
 
-    let mut map = get_map(path)?;
-    let mut new_grid = map.grid;
+    let map = get_map(path)?;
+    Ok(count_cheats(&map, minimum_cheat_save))
+}
 
-    scan_map(&mut map.grid, map.goal);
+/// Counts the cheats of up to 20 steps that save at least minimum_cheat_save picoseconds.
+fn count_cheats(map: &Map, minimum_cheat_save: u64) -> u64 {
+    let times_to_goal = scan_map(&map.track, map.goal);
+    let times_from_start = scan_map(&map.track, map.start);
 
-    let Tile::Start {
-        time: best_default_cost,
-    } = view(&map.grid, &map.start)
-    else {
+    let Some(best_default_cost) = times_to_goal.get(&map.start.extract()) else {
         panic!("Start does not exist!");
     };
 
-    let cheats = scan_map_and_cheat(&mut new_grid, &map.grid, map.start, *best_default_cost);
+    let mut cheats: HashMap<u64, u32> = HashMap::new();
+    for (location, time_at_cheat_origin) in &times_from_start {
+        cheat(
+            &times_to_goal,
+            ValidLocation(*location),
+            *time_at_cheat_origin,
+            *best_default_cost,
+            &mut cheats,
+        );
+    }
 
     let mut result: u64 = 0;
 
@@ -301,10 +212,20 @@ fn calculate(path: &str, minimum_cheat_save: u32) -> Result<u64, AdventError> {
         }
     }
 
-    Ok(result)
+    result
 }
 
 fn main() {
+    // "cargo run -- generate <kind> [seed] [size] [amount]" prints a generated input instead.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|command| command == "generate") {
+        match input_generator::run_command(&args[1..]) {
+            Err(err) => println!("An error occured: {err}"),
+            Ok(lines) => println!("{}", lines.join("\n")),
+        }
+        return;
+    }
+
     match calculate("data.txt", 100) {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
@@ -319,6 +240,52 @@ fn calculate_test() {
     }
 }
 
+#[test]
+fn calculate_generated_test() {
+    let lines = input_generator::race_track(41, &mut random::Random::new(20)).unwrap();
+
+    // The track is a single corridor, so walking it from the start gives the time to each tile.
+    let tiles: Vec<Vec<char>> = lines.iter().map(|line| line.chars().collect()).collect();
+    let start = (0..tiles.len())
+        .flat_map(|y| (0..tiles[y].len()).map(move |x| (x, y)))
+        .find(|(x, y)| tiles[*y][*x] == 'S')
+        .unwrap();
+    let mut track = vec![start];
+    while let Some(&(x, y)) = track.last().filter(|(x, y)| tiles[*y][*x] != 'E') {
+        let next = [
+            (x + 1, y),
+            (x, y + 1),
+            (x.wrapping_sub(1), y),
+            (x, y.wrapping_sub(1)),
+        ]
+        .into_iter()
+        .filter(|(x, y)| {
+            tiles
+                .get(*y)
+                .and_then(|row| row.get(*x))
+                .is_some_and(|tile| *tile != '#')
+        })
+        .find(|tile| track.len() < 2 || *tile != track[track.len() - 2])
+        .unwrap();
+        track.push(next);
+    }
+
+    // Every pair of tiles close enough to cheat between, later on the track.
+    let mut expected = 0;
+    for (time, (x, y)) in track.iter().enumerate() {
+        for (later_time, (other_x, other_y)) in track.iter().enumerate().skip(time + 1) {
+            let steps = x.abs_diff(*other_x) + y.abs_diff(*other_y);
+            if steps <= 20 && later_time - time >= steps + 50 {
+                expected += 1;
+            }
+        }
+    }
+    assert!(expected > 0);
+
+    let map = parse_map(lines).unwrap();
+    assert_eq!(count_cheats(&map, 50), expected);
+}
+
 /* Sudo code:
 
 Challenge part 1:
@@ -394,4 +361,13 @@ Challenge part 2:
 ...
 1087584 is too high
 
+Update: "cargo run -- generate track <seed> <size>" prints a seeded race track from the shared
+input_generator module. The track is the path between the corners of a random perfect maze, so it
+is a single corridor without branches like the real input. A test checks the cheat count on one of
+them against simply comparing every pair of tiles along the corridor.
+
+Update: The track is kept in a BitSet from the shared bitset module, one bit per tile, instead of a
+grid of Tile values. The tiles used to hold the time they were reached, doubling as visited flags;
+those times now come from the search module's distance field.
+
 */
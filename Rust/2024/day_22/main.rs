@@ -22,12 +22,12 @@ impl From<io::Error> for AdventError {
     }
 }
 
-fn get_sequences(number: i64) -> HashMap<[i8; 4], u64> {
-    // We set the default to 16 so we don't need to make logic to only add the stack to the
-    // sequence_combinations after the first 3 prices. A pattern containing 16 will never occur
-    // due to the max difference in price being 9/-9
-    let mut circular_stack: CircularStack<i8> = CircularStack::with_default(16);
-    let mut local_sequence_combinations: HashMap<[i8; 4], u64> = HashMap::new();
+/// Bits per price change in a window key. Changes range from -9 to 9, which fits in 5 bits.
+const PRICE_CHANGE_BITS: u32 = 5;
+
+fn get_sequences(number: i64) -> HashMap<u64, u64> {
+    let mut circular_stack: CircularStack<i8, 4> = CircularStack::new();
+    let mut local_sequence_combinations: HashMap<u64, u64> = HashMap::new();
     let mut secret_number = SecretNumber::new(number);
     let mut previous_price = secret_number.price();
     for _ in 0..2000 {
@@ -35,15 +35,19 @@ fn get_sequences(number: i64) -> HashMap<[i8; 4], u64> {
         circular_stack.push(secret_number.price() - previous_price);
         previous_price = secret_number.price();
 
+        // No sequence exists until the first four changes have been seen.
+        if !circular_stack.is_full() {
+            continue;
+        }
         local_sequence_combinations
-            .entry(circular_stack.get_queue())
+            .entry(circular_stack.window_key(PRICE_CHANGE_BITS))
             .or_insert_with(|| secret_number.price() as u64);
     }
 
     local_sequence_combinations
 }
 
-fn get_best_sequence_value(sequence_combinations: HashMap<[i8; 4], u64>) -> u64 {
+fn get_best_sequence_value(sequence_combinations: HashMap<u64, u64>) -> u64 {
     let mut result_sum = 0;
     for value in sequence_combinations.values() {
         if *value > result_sum {
@@ -54,7 +58,7 @@ fn get_best_sequence_value(sequence_combinations: HashMap<[i8; 4], u64>) -> u64
 }
 
 fn calculate(path: &str) -> Result<u64, AdventError> {
-    let mut sequence_combinations: HashMap<[i8; 4], u64> = HashMap::new();
+    let mut sequence_combinations: HashMap<u64, u64> = HashMap::new();
     for line in get_lines(path)? {
         let Ok(number) = line.parse() else {
             return Err(AdventError::CorruptedData(line));
@@ -98,4 +102,9 @@ Challenge part 1:
 
 Challenge part 2:
 
+Update: The circular stack now has its capacity as a const generic and tracks how many values have
+been pushed, so sequences are only recorded once four price changes exist instead of filling the
+stack with an impossible value. Each window of four changes is also packed into a single u64 with
+5 bits per change, which is a lot cheaper to hash than the array.
+
 */
@@ -15,12 +15,6 @@ impl Identifier {
         Ok(Identifier(chars))
     }
 
-    pub fn as_u16(&self) -> u16 {
-        let a = ((self.0 .0 as u16) - 97) * 26;
-        let b = (self.0 .1 as u16) - 97;
-        a + b
-    }
-
     fn valid_char(char: char) -> bool {
         (char as u16) >= 97 && (char as u16) <= 122
     }
@@ -35,88 +29,9 @@ impl Display for Identifier {
     }
 }
 
-impl From<u16> for Identifier {
-    fn from(value: u16) -> Self {
-        let c1 = ((value / 26) as u8 + 97) as char;
-        let c2 = ((value % 26) as u8 + 97) as char;
-        Identifier((c1, c2))
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct Computer {
-    pub id: u16,
-    pub connections: Vec<u16>,
-}
-
-impl Computer {
-    pub fn new(id: u16) -> Self {
-        Computer {
-            id,
-            connections: Vec::new(),
-        }
-    }
-
-    pub fn starts_with_t(&self) -> bool {
-        self.id >= 494 && self.id <= 519
-    }
-}
-
-impl From<Identifier> for Computer {
-    fn from(identifier: Identifier) -> Self {
-        Computer::new(identifier.as_u16())
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::computer::Identifier;
-
-    use super::{Computer, ComputerError};
-    const TESTID: u16 = 42;
-    const TESTIDENTIFIER: Identifier = Identifier(('b', 'q')); // bq
-    const TESTCOMPUTER: Computer = Computer {
-        id: TESTID,
-        connections: Vec::new(),
-    };
-
-    #[test]
-    fn new_creates_expected_computer() {
-        let result = Computer::new(TESTID);
-        assert_eq!(
-            result.id, TESTID,
-            "Computer::new({}) returned a computer with ID: {} instead of the expected: {}",
-            TESTID, result.id, TESTID
-        );
-    }
-
-    #[test]
-    fn starts_with_t_success() {
-        assert!(
-            Computer::new(Identifier::parse(('t', 'f')).unwrap().as_u16()).starts_with_t(),
-            "Computer::starts_with_t() returned false even though the identifier is <tf>!"
-        );
-        assert!(
-            Computer::new(Identifier::parse(('t', 'a')).unwrap().as_u16()).starts_with_t(),
-            "Computer::starts_with_t() returned false even though the identifier is <ta>!"
-        );
-        assert!(
-            Computer::new(Identifier::parse(('t', 'z')).unwrap().as_u16()).starts_with_t(),
-            "Computer::starts_with_t() returned false even though the identifier is <tz>!"
-        );
-    }
-
-    #[test]
-    fn starts_with_t_fail() {
-        assert!(
-            !Computer::new(Identifier::parse(('u', 'a')).unwrap().as_u16()).starts_with_t(),
-            "Computer::starts_with_t() returned true even though the identifier is <ua>!"
-        );
-        assert!(
-            !Computer::new(Identifier::parse(('s', 'z')).unwrap().as_u16()).starts_with_t(),
-            "Computer::starts_with_t() returned true even though the identifier is <sz>!"
-        );
-    }
+    use super::{ComputerError, Identifier};
 
     #[test]
     fn identifier_valid_char_success() {
@@ -167,24 +82,4 @@ mod tests {
             Err(error) => assert_eq!(error, ComputerError::IdentifierParseFailed, "identifier::parse({:?}) returned {:?} instead of the expected: {:?}",chars, error, ComputerError::IdentifierParseFailed),
         }
     }
-
-    #[test]
-    fn identifier_as_u16() {
-        let result = TESTIDENTIFIER.as_u16();
-        assert_eq!(
-            result, TESTID,
-            "{:?}.as_u16() returned: {} instead of the expected: {}",
-            TESTIDENTIFIER, result, TESTID
-        );
-    }
-
-    #[test]
-    fn identifier_from_u16() {
-        let result = Identifier::from(TESTID);
-        assert_eq!(
-            result, TESTIDENTIFIER,
-            "Identifier::from({}) returned: {:?} instead of the expected: {:?}",
-            TESTID, result, TESTIDENTIFIER
-        );
-    }
 }
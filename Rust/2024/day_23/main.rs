@@ -1,10 +1,15 @@
 use std::io;
 
 mod computer;
-use computer::Computer;
 use computer::ComputerError;
 use computer::Identifier;
 
+mod bitset;
+mod graph;
+use graph::Graph;
+mod interner;
+use interner::Interner;
+
 mod reader;
 use reader::get_lines;
 mod testing_debug;
@@ -28,134 +33,6 @@ impl From<ComputerError> for AdventError {
     }
 }
 
-#[derive(Debug)]
-struct NetworkGroup {
-    computers: Vec<u16>,
-    potential_connections: Vec<u16>,
-}
-
-impl NetworkGroup {
-    fn new(computer: &Computer) -> NetworkGroup {
-        NetworkGroup {
-            computers: vec![computer.id],
-            potential_connections: computer.connections.clone(),
-        }
-    }
-
-    fn can_connect(&self, id: u16) -> bool {
-        self.potential_connections.contains(&id)
-    }
-
-    fn copy_and_connect(&self, computer: &Computer) -> NetworkGroup {
-        let mut potential_connections = Vec::new();
-        let mut computers = self.computers.clone();
-        for connection in &self.potential_connections {
-            if computer.connections.contains(connection) {
-                potential_connections.push(*connection);
-            }
-        }
-        computers.push(computer.id);
-        NetworkGroup {
-            computers,
-            potential_connections,
-        }
-    }
-}
-
-struct Network {
-    computers: [Computer; 676],
-}
-
-impl Network {
-    fn new() -> Network {
-        Network {
-            computers: core::array::from_fn(|i| Computer::new(i as u16)),
-        }
-    }
-
-    fn add_connection_and_process_sets(
-        &mut self,
-        identifier_1: Identifier,
-        identifier_2: Identifier,
-    ) -> u64 {
-        let mut result: u64 = 0;
-
-        let (id_1, id_2) = (identifier_1.as_u16(), identifier_2.as_u16());
-        let computer_2 = &self.computers[id_2 as usize];
-
-        for computer_2_connection in &computer_2.connections {
-            let computer_3 = &self.computers[*computer_2_connection as usize];
-            for computer_3_connection in &computer_3.connections {
-                if *computer_3_connection == id_1 {
-                    result += 1;
-                }
-            }
-        }
-
-        self.connect_computers(id_1, id_2);
-        result
-    }
-
-    fn connect_computers(&mut self, id_1: u16, id_2: u16) {
-        self.computers[id_1 as usize].connections.push(id_2);
-        self.computers[id_2 as usize].connections.push(id_1);
-    }
-
-    fn largest_group(&mut self) -> NetworkGroup {
-        let mut computed_combinations: [bool; 676] = [false; 676];
-        let (mut largest_group_size, mut largest_group): (u16, Option<NetworkGroup>) = (0, None);
-
-        for computer in &self.computers {
-            let groups: Vec<NetworkGroup> =
-                self.groups_with_computer(computer, computed_combinations, largest_group_size);
-            (largest_group_size, largest_group) =
-                Network::update_largest_group(largest_group_size, largest_group, groups);
-            computed_combinations[computer.id as usize] = true;
-        }
-
-        largest_group.unwrap()
-    }
-
-    fn update_largest_group(
-        mut largest_group_size: u16,
-        mut largest_group: Option<NetworkGroup>,
-        new_groups: Vec<NetworkGroup>,
-    ) -> (u16, Option<NetworkGroup>) {
-        for group in new_groups {
-            if group.computers.len() as u16 > largest_group_size {
-                largest_group_size = group.computers.len() as u16;
-                largest_group = Some(group);
-            }
-        }
-        (largest_group_size, largest_group)
-    }
-
-    fn groups_with_computer(
-        &self,
-        computer: &Computer,
-        computed_combinations: [bool; 676],
-        current_largest_group: u16,
-    ) -> Vec<NetworkGroup> {
-        let mut groups: Vec<NetworkGroup> = Vec::new();
-        groups.push(NetworkGroup::new(computer));
-        for (index, id) in computer.connections.iter().enumerate().rev() {
-            if computed_combinations[*id as usize] {
-                continue;
-            }
-            let mut group_cache = Vec::new();
-            for group in &groups {
-                if group.can_connect(*id)
-                    && group.computers.len() + index + 1 > current_largest_group as usize
-                {
-                    group_cache.push(group.copy_and_connect(&self.computers[*id as usize]));
-                }
-            }
-            groups.append(&mut group_cache);
-        }
-        groups
-    }
-}
-
 fn get_identifiers_from_line(line: String) -> Result<(Identifier, Identifier), AdventError> {
     let mut char_cache = [' '; 5];
     for (index, char) in line.chars().enumerate() {
@@ -170,22 +47,50 @@ fn get_identifiers_from_line(line: String) -> Result<(Identifier, Identifier), A
     ))
 }
 
-fn calculate(path: &str) -> Result<String, AdventError> {
+/// Reads the connections into a undirected graph where each computer name has been given a id.
+fn get_network(path: &str) -> Result<(Graph, Interner), AdventError> {
     let lines = get_lines(path)?;
 
-    let mut network = Network::new();
+    let mut network = Graph::new();
+    let mut interner = Interner::new();
 
     for line in lines {
         let (identifier_1, identifier_2) = get_identifiers_from_line(line)?;
-        let _ = network.add_connection_and_process_sets(identifier_1, identifier_2);
+        network.connect(
+            interner.intern(&identifier_1.to_string()),
+            interner.intern(&identifier_2.to_string()),
+        );
     }
 
-    let mut computers = network.largest_group().computers;
+    Ok((network, interner))
+}
+
+fn calculate_part_one(path: &str) -> Result<u64, AdventError> {
+    let (network, interner) = get_network(path)?;
+
+    let starts_with_t = |id: &usize| interner.name(*id).is_some_and(|name| name.starts_with('t'));
+    let sets = network
+        .k_cliques(3)
+        .iter()
+        .filter(|set| set.iter().any(starts_with_t))
+        .count();
+
+    Ok(sets as u64)
+}
+
+fn calculate(path: &str) -> Result<String, AdventError> {
+    let (network, interner) = get_network(path)?;
+
+    let mut computers: Vec<&str> = network
+        .maximum_clique()
+        .into_iter()
+        .filter_map(|id| interner.name(id))
+        .collect();
     computers.sort();
 
     let mut result = String::new();
     for computer in computers {
-        result.push_str(&Identifier::from(computer).to_string());
+        result.push_str(computer);
         result.push(',');
     }
 
@@ -193,6 +98,10 @@ fn calculate(path: &str) -> Result<String, AdventError> {
 }
 
 fn main() {
+    match calculate_part_one("data.txt") {
+        Err(err) => println!("An error occured: {err:?}"),
+        Ok(value) => println!("Part one result is: {}", value),
+    }
     match calculate("data.txt") {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
@@ -200,17 +109,19 @@ fn main() {
 }
 
 #[test]
-fn calculate_test() {
-    match calculate("testdata.txt") {
+fn calculate_part_one_test() {
+    match calculate_part_one("testdata.txt") {
         Err(err) => panic!("An error occured: {err:?}"),
-        Ok(value) => assert_eq!(value, "co,de,ka,ta,"),
+        Ok(value) => assert_eq!(value, 7),
     }
 }
 
 #[test]
-fn network_new_computer_ids() {
-    let network = Network::new();
-    assert_eq!(network.computers[42].id, 42);
+fn calculate_test() {
+    match calculate("testdata.txt") {
+        Err(err) => panic!("An error occured: {err:?}"),
+        Ok(value) => assert_eq!(value, "co,de,ka,ta,"),
+    }
 }
 
 /* Sudo code:
@@ -285,4 +196,9 @@ Is that something that is worth to do anyway? Maybe. We shall see if any answers
 // This is a interesting problem. AFTER I have solved it try asking chat-gpt. Would be interesting
 // to see how the ai would handle this.
 
+Update:
+The largest set is what graph theory calls a maximum clique, and the sets of three in part one are
+cliques of size 3. Both are now handled by the shared graph module. The computer names are given
+ids by the interner instead of packing the two letters into a index of a 676 slot array.
+
 */
@@ -0,0 +1,514 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
+
+use crate::circuit::{Circuit, GateOperation};
+use crate::compiled_circuit::CompiledCircuit;
+use crate::random::Random;
+
+/// The part a gate plays in a full adder, worked out from its operation and inputs alone. Output
+/// swaps don't change the inputs of a gate, so the role stays the same whatever was swapped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AdderRole {
+    /// x XOR y.
+    HalfSum,
+    /// x AND y.
+    HalfCarry,
+    /// Half sum XOR carry in, driving the z output of the bit.
+    Sum,
+    /// Half sum AND carry in.
+    CarryPropagate,
+    /// Both carries OR'ed into the carry out of the bit.
+    CarryOut,
+}
+
+/// What the output wire of a gate should be connected to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    /// Should be the named wire.
+    Drive(String),
+    /// Should be one of the z outputs between the first and the last.
+    DriveOutput,
+    /// Should feed the Sum and CarryPropagate gates of the next bit.
+    FeedSumAndPropagate,
+    /// Should feed the CarryOut gate of the bit.
+    FeedCarryOut,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expected::Drive(wire) => write!(f, "drive {wire}"),
+            Expected::DriveOutput => write!(f, "drive a z output"),
+            Expected::FeedSumAndPropagate => {
+                write!(f, "feed a Sum and a CarryPropagate gate")
+            }
+            Expected::FeedCarryOut => write!(f, "feed a CarryOut gate"),
+        }
+    }
+}
+
+/// A gate whose output doesn't match the full adder template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deviation {
+    pub gate: usize,
+    pub role: AdderRole,
+    /// The bit the gate belongs to, if it could be worked out from its inputs.
+    pub bit: Option<usize>,
+    pub output: String,
+    pub expected: Expected,
+}
+
+impl Display for Deviation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.bit {
+            Some(bit) => write!(f, "Bit {bit}: ")?,
+            None => write!(f, "Unknown bit: ")?,
+        }
+        write!(
+            f,
+            "{:?} gate {} drives {} but should {}",
+            self.role, self.gate, self.output, self.expected
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AdderError {
+    /// The x and y buses must have the same width n below 64, and z must be n + 1 wide.
+    BusWidth { x: usize, y: usize, z: usize },
+    /// The gate's operation or inputs don't fit any gate of a full adder, which swapping
+    /// outputs can't fix.
+    UnexpectedGate(usize),
+    /// No set of swaps within the limit made the circuit add. Holds the suspected wires.
+    NoFixFound(Vec<String>),
+    /// The circuit still deviates from a adder, or adds the pair wrong.
+    StillBroken {
+        deviations: usize,
+        failing: Option<(u64, u64)>,
+    },
+}
+
+impl Display for AdderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdderError::BusWidth { x, y, z } => write!(
+                f,
+                "Bus widths x: {x}, y: {y}, z: {z} don't form a adder of less than 64 bits"
+            ),
+            AdderError::UnexpectedGate(gate) => {
+                write!(f, "Gate {gate} doesn't fit anywhere in a full adder")
+            }
+            AdderError::NoFixFound(suspects) => write!(
+                f,
+                "No swaps found that fix the adder. Suspected wires: {}",
+                suspects.join(",")
+            ),
+            AdderError::StillBroken {
+                deviations,
+                failing,
+            } => write!(
+                f,
+                "The circuit still has {deviations} deviations, failing addition: {failing:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AdderError {}
+
+/// Checks a circuit against a ripple-carry adder of any width, built from the canonical full
+/// adder:
+///
+/// ```text
+/// half_sum   = x XOR y           half_carry = x AND y
+/// z          = half_sum XOR carry_in
+/// propagate  = half_sum AND carry_in
+/// carry_out  = half_carry OR propagate
+/// ```
+///
+/// Bit 0 is a half adder where half_carry is the carry out, and the last carry out is the top z
+/// bit. Each gate gets a role from its inputs, and each role expects its output wire to feed
+/// certain gates. Gates that don't are deviations, and swapping the outputs of deviating gates
+/// is how the circuit gets repaired.
+pub struct AdderVerifier<'a> {
+    circuit: &'a Circuit,
+    bits: usize,
+    /// Role of every gate by id, with the bit for the two gates reading x and y.
+    roles: HashMap<usize, (AdderRole, Option<usize>)>,
+}
+
+impl<'a> AdderVerifier<'a> {
+    pub fn new(circuit: &'a Circuit) -> Result<Self, AdderError> {
+        let (x, y, z) = (circuit.bus('x'), circuit.bus('y'), circuit.bus('z'));
+        if x.is_empty() || x.len() != y.len() || z.len() != x.len() + 1 || x.len() >= 64 {
+            return Err(AdderError::BusWidth {
+                x: x.len(),
+                y: y.len(),
+                z: z.len(),
+            });
+        }
+
+        let input_bit = |wire: usize, bus: &[usize]| bus.iter().position(|id| *id == wire);
+        let mut roles = HashMap::new();
+        for gate in circuit.gates() {
+            let &[input_1, input_2] = gate.inputs() else {
+                return Err(AdderError::UnexpectedGate(gate.id()));
+            };
+            let is_input = |wire| circuit.wire_source(wire).is_none();
+            let role = match (is_input(input_1), is_input(input_2)) {
+                (true, true) => {
+                    let bit = input_bit(input_1, x)
+                        .filter(|bit| input_bit(input_2, y) == Some(*bit))
+                        .or_else(|| {
+                            input_bit(input_1, y).filter(|bit| input_bit(input_2, x) == Some(*bit))
+                        });
+                    match (gate.operation(), bit) {
+                        (GateOperation::XOR, Some(bit)) => (AdderRole::HalfSum, Some(bit)),
+                        (GateOperation::AND, Some(bit)) => (AdderRole::HalfCarry, Some(bit)),
+                        _ => return Err(AdderError::UnexpectedGate(gate.id())),
+                    }
+                }
+                (false, false) => match gate.operation() {
+                    GateOperation::XOR => (AdderRole::Sum, None),
+                    GateOperation::AND => (AdderRole::CarryPropagate, None),
+                    GateOperation::OR => (AdderRole::CarryOut, None),
+                    _ => return Err(AdderError::UnexpectedGate(gate.id())),
+                },
+                _ => return Err(AdderError::UnexpectedGate(gate.id())),
+            };
+            roles.insert(gate.id(), role);
+        }
+
+        Ok(AdderVerifier {
+            circuit,
+            bits: x.len(),
+            roles,
+        })
+    }
+
+    /// Width of the x and y inputs.
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+
+    pub fn deviations(&self) -> Vec<Deviation> {
+        self.deviations_in(self.circuit)
+    }
+
+    /// Finds the deviations of a copy of the circuit with some outputs swapped. The roles still
+    /// hold, as swapping outputs leaves every gate input alone.
+    fn deviations_in(&self, circuit: &Circuit) -> Vec<Deviation> {
+        let names = circuit.component_names();
+        let z = circuit.bus('z');
+        let role_of = |gate: &usize| self.roles[gate].0;
+
+        let mut deviations = Vec::new();
+        for gate in circuit.gates() {
+            let (role, input_bit) = self.roles[&gate.id()];
+            // Sum and propagate gates belong to the bit of the half sum they read, a carry out
+            // to the bit of the half carry it reads.
+            let bit = input_bit.or_else(|| {
+                let wanted = match role {
+                    AdderRole::CarryOut => AdderRole::HalfCarry,
+                    _ => AdderRole::HalfSum,
+                };
+                gate.inputs()
+                    .iter()
+                    .filter_map(|wire| circuit.wire_source(*wire))
+                    .find(|source| role_of(source) == wanted)
+                    .and_then(|source| self.roles[&source].1)
+            });
+
+            let expected = match (role, bit) {
+                (AdderRole::HalfSum, Some(0)) => Expected::Drive(names[z[0]].to_string()),
+                (AdderRole::HalfCarry, Some(0)) if self.bits == 1 => {
+                    Expected::Drive(names[z[1]].to_string())
+                }
+                (AdderRole::HalfSum, _) | (AdderRole::HalfCarry, Some(0)) => {
+                    Expected::FeedSumAndPropagate
+                }
+                (AdderRole::HalfCarry, _) | (AdderRole::CarryPropagate, _) => {
+                    Expected::FeedCarryOut
+                }
+                (AdderRole::Sum, Some(bit)) => Expected::Drive(names[z[bit]].to_string()),
+                (AdderRole::Sum, None) => Expected::DriveOutput,
+                (AdderRole::CarryOut, Some(bit)) if bit == self.bits - 1 => {
+                    Expected::Drive(names[z[self.bits]].to_string())
+                }
+                (AdderRole::CarryOut, _) => Expected::FeedSumAndPropagate,
+            };
+
+            let output = gate.output();
+            let mut consumers: Vec<AdderRole> = circuit
+                .wire_connections(output)
+                .iter()
+                .map(role_of)
+                .collect();
+            consumers.sort();
+            let matches = match &expected {
+                Expected::Drive(wire) => names[output] == wire,
+                Expected::DriveOutput => z[1..self.bits].contains(&output),
+                Expected::FeedSumAndPropagate => {
+                    consumers == [AdderRole::Sum, AdderRole::CarryPropagate]
+                }
+                Expected::FeedCarryOut => consumers == [AdderRole::CarryOut],
+            };
+            if !matches {
+                deviations.push(Deviation {
+                    gate: gate.id(),
+                    role,
+                    bit,
+                    output: names[output].to_string(),
+                    expected,
+                });
+            }
+        }
+        deviations
+    }
+
+    /// Returns a x and y the circuit adds wrong, trying the edge cases first followed by tests
+    /// random pairs. A circuit with a loop fails on the first try.
+    ///
+    /// Runs on the compiled circuit, which adds 64 pairs per pass.
+    pub fn failing_addition(
+        &self,
+        circuit: &Circuit,
+        random: &mut Random,
+        tests: usize,
+    ) -> Option<(u64, u64)> {
+        let Ok(compiled) = CompiledCircuit::new(circuit) else {
+            return Some((0, 0));
+        };
+
+        let mask = (1 << self.bits) - 1;
+        let edge_cases = [(0, 0), (mask, 1), (1, mask), (mask, mask)];
+        let random_cases =
+            std::iter::repeat_with(|| (random.next_u64() & mask, random.next_u64() & mask));
+        let pairs: Vec<(u64, u64)> = edge_cases
+            .into_iter()
+            .chain(random_cases.take(tests))
+            .collect();
+        let sums = compiled.add_all(&pairs);
+        pairs
+            .into_iter()
+            .zip(sums)
+            .find(|((x, y), sum)| x + y != *sum)
+            .map(|(pair, _)| pair)
+    }
+
+    /// Finds the fewest output swaps, at most max_swaps, that make the circuit match the template
+    /// and add correctly, confirmed by tests random additions. Returns the swapped wire names.
+    ///
+    /// Only the outputs of deviating gates are suspects. A pair of them is worth trying if
+    /// swapping it fixes both gates, and sets of those pairs are tried smallest first.
+    pub fn propose_swaps(
+        &self,
+        max_swaps: usize,
+        random: &mut Random,
+        tests: usize,
+    ) -> Result<Vec<(String, String)>, AdderError> {
+        let deviations = self.deviations();
+        let suspects: BTreeSet<usize> = deviations
+            .iter()
+            .filter_map(|deviation| self.circuit.wire_id(&deviation.output))
+            .collect();
+        let suspects: Vec<usize> = suspects.into_iter().collect();
+
+        let mut pairs = Vec::new();
+        for (index, wire_1) in suspects.iter().enumerate() {
+            for wire_2 in &suspects[index + 1..] {
+                let mut swapped = self.circuit.clone();
+                swapped.swap_wires(*wire_1, *wire_2);
+                let gates = [wire_1, wire_2].map(|wire| swapped.wire_source(*wire));
+                let fixed = self
+                    .deviations_in(&swapped)
+                    .iter()
+                    .all(|deviation| !gates.contains(&Some(deviation.gate)));
+                if fixed {
+                    pairs.push((*wire_1, *wire_2));
+                }
+            }
+        }
+
+        let names = self.circuit.component_names();
+        for swap_count in 0..=max_swaps {
+            let mut chosen = Vec::new();
+            if self.search(&pairs, 0, swap_count, &mut chosen, random, tests) {
+                return Ok(chosen
+                    .into_iter()
+                    .map(|(wire_1, wire_2)| (names[wire_1].to_string(), names[wire_2].to_string()))
+                    .collect());
+            }
+        }
+        Err(AdderError::NoFixFound(
+            suspects
+                .into_iter()
+                .map(|wire| names[wire].to_string())
+                .collect(),
+        ))
+    }
+
+    /// Tries every way to pick swap_count more pairs from pairs[start..] that share no wire with
+    /// the chosen ones. Leaves the working set in chosen and returns true when one is found.
+    fn search(
+        &self,
+        pairs: &[(usize, usize)],
+        start: usize,
+        swap_count: usize,
+        chosen: &mut Vec<(usize, usize)>,
+        random: &mut Random,
+        tests: usize,
+    ) -> bool {
+        if swap_count == 0 {
+            let mut swapped = self.circuit.clone();
+            for (wire_1, wire_2) in chosen.iter() {
+                swapped.swap_wires(*wire_1, *wire_2);
+            }
+            return self.deviations_in(&swapped).is_empty()
+                && self.failing_addition(&swapped, random, tests).is_none();
+        }
+
+        for (index, (wire_1, wire_2)) in pairs.iter().enumerate().skip(start) {
+            let overlaps = chosen
+                .iter()
+                .any(|(a, b)| [a, b].iter().any(|wire| *wire == wire_1 || *wire == wire_2));
+            if overlaps {
+                continue;
+            }
+            chosen.push((*wire_1, *wire_2));
+            if self.search(pairs, index + 1, swap_count - 1, chosen, random, tests) {
+                return true;
+            }
+            chosen.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::CircuitBuilder;
+
+    /// Builds a ripple-carry adder of the given width, naming the internal wires after their
+    /// role and bit, then swaps the named outputs.
+    fn adder(bits: usize, swaps: &[(&str, &str)]) -> Circuit {
+        let mut builder = CircuitBuilder::new();
+        let mut gate = |input_1: String, operation: &str, input_2: String, output: String| {
+            builder.add_gate(input_1, operation.to_string(), input_2, output)
+        };
+        let wire = |name: &str, bit: usize| format!("{name}{bit:02}");
+
+        gate(wire("x", 0), "XOR", wire("y", 0), wire("z", 0));
+        let last_carry = |bit| match bit {
+            _ if bit == bits - 1 => wire("z", bits),
+            0 => wire("hc", 0),
+            _ => wire("co", bit),
+        };
+        gate(wire("x", 0), "AND", wire("y", 0), last_carry(0));
+        for bit in 1..bits {
+            let carry_in = match bit {
+                1 => wire("hc", 0),
+                _ => wire("co", bit - 1),
+            };
+            gate(wire("x", bit), "XOR", wire("y", bit), wire("hs", bit));
+            gate(wire("x", bit), "AND", wire("y", bit), wire("hc", bit));
+            gate(wire("hs", bit), "XOR", carry_in.clone(), wire("z", bit));
+            gate(wire("hs", bit), "AND", carry_in, wire("pr", bit));
+            gate(wire("hc", bit), "OR", wire("pr", bit), last_carry(bit));
+        }
+
+        let mut circuit = builder.assemble().unwrap();
+        for (wire_1, wire_2) in swaps {
+            let (wire_1, wire_2) = (circuit.wire_id(wire_1), circuit.wire_id(wire_2));
+            circuit.swap_wires(wire_1.unwrap(), wire_2.unwrap());
+        }
+        circuit
+    }
+
+    #[test]
+    fn correct_adders_pass() {
+        for bits in [1, 2, 8, 45] {
+            let circuit = adder(bits, &[]);
+            let verifier = AdderVerifier::new(&circuit).unwrap();
+            assert_eq!(verifier.bits(), bits);
+            assert_eq!(verifier.deviations(), vec![]);
+            assert_eq!(
+                verifier.failing_addition(&circuit, &mut Random::new(1), 100),
+                None
+            );
+            assert_eq!(
+                verifier.propose_swaps(0, &mut Random::new(1), 100),
+                Ok(vec![])
+            );
+        }
+    }
+
+    #[test]
+    fn finds_swapped_outputs() {
+        // One swap of each kind seen in the puzzle: a sum with a carry out, a sum with a
+        // propagate, half sum with half carry and two sum outputs.
+        let swaps = [
+            ("z05", "co05"),
+            ("z09", "pr09"),
+            ("hs13", "hc13"),
+            ("z20", "z21"),
+        ];
+        let circuit = adder(24, &swaps);
+        let verifier = AdderVerifier::new(&circuit).unwrap();
+        assert!(verifier
+            .failing_addition(&circuit, &mut Random::new(1), 100)
+            .is_some());
+
+        let deviation = verifier
+            .deviations()
+            .into_iter()
+            .find(|deviation| deviation.output == "co05")
+            .unwrap();
+        assert_eq!(
+            (deviation.role, deviation.bit, deviation.expected),
+            (AdderRole::Sum, Some(5), Expected::Drive("z05".to_string()))
+        );
+
+        let sorted_pairs = |pairs: Vec<(String, String)>| {
+            let mut pairs: Vec<(String, String)> = pairs
+                .into_iter()
+                .map(|(a, b)| (a.clone().min(b.clone()), a.max(b)))
+                .collect();
+            pairs.sort();
+            pairs
+        };
+        let found = verifier.propose_swaps(4, &mut Random::new(1), 100).unwrap();
+        let expected = swaps
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect();
+        assert_eq!(sorted_pairs(found), sorted_pairs(expected));
+
+        let Err(AdderError::NoFixFound(mut suspects)) =
+            verifier.propose_swaps(3, &mut Random::new(1), 100)
+        else {
+            panic!("Expected 3 swaps to not be enough");
+        };
+        suspects.sort();
+        assert_eq!(
+            suspects,
+            ["co05", "hc13", "hs13", "pr09", "z05", "z09", "z20", "z21"]
+        );
+    }
+
+    #[test]
+    fn rejects_other_circuits() {
+        let circuit = Circuit::from_lines(["", "x00 OR y00 -> z00"]).unwrap();
+        assert_eq!(
+            AdderVerifier::new(&circuit).err(),
+            Some(AdderError::BusWidth { x: 1, y: 1, z: 1 })
+        );
+
+        let circuit = Circuit::from_lines(["", "x00 OR y00 -> z00", "x00 AND y00 -> z01"]).unwrap();
+        assert_eq!(
+            AdderVerifier::new(&circuit).err(),
+            Some(AdderError::UnexpectedGate(3))
+        );
+    }
+}
@@ -1,5 +1,11 @@
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
 
+use crate::compiled_circuit::{CompileError, CompiledCircuit};
+
+#[derive(Clone)]
 pub struct Circuit {
     input_x: Vec<usize>,
     input_y: Vec<usize>,
@@ -25,56 +31,13 @@ impl Wire {
             connections: Vec::new(),
         }
     }
-
-    fn shares_connections(&self, other: &Wire) -> Vec<usize> {
-        let mut result = Vec::new();
-        for id in &self.connections {
-            if other.connections.contains(&id) {
-                result.push(*id);
-            }
-        }
-        result
-    }
-
-    fn exclusive_connections(&self, other: &Wire) -> Vec<usize> {
-        let mut result = Vec::new();
-        for id in &self.connections {
-            if !other.connections.contains(id) {
-                result.push(*id);
-            }
-        }
-        for id in &other.connections {
-            if !self.connections.contains(id) {
-                result.push(*id);
-            }
-        }
-        result
-    }
-
-    fn get_connected_gate(
-        &self,
-        circuit: &Circuit,
-        desired_operation: GateOperation,
-    ) -> Option<(Gate, usize)> {
-        for connection_index in &self.connections {
-            let gate = circuit.get_gate(*connection_index);
-            if gate.operation == desired_operation {
-                return Some((*gate, *connection_index));
-            }
-        }
-        None
-    }
-
-    fn is_connected(&self, gate: &Gate) -> bool {
-        self.connections.contains(&gate.id) || gate.output == self.id
-    }
 }
 
 #[derive(Clone, Copy, Debug)]
-struct Gate {
+pub struct Gate {
     id: usize,
-    input_1: usize,
-    input_2: usize,
+    /// Only the first operation.arity() entries are used.
+    inputs: [usize; 2],
     operation: GateOperation,
     output: usize,
     output_powered: bool,
@@ -85,43 +48,129 @@ pub enum GateOperation {
     XOR,
     OR,
     AND,
+    NOT,
+    NAND,
+    NOR,
+    XNOR,
+    /// A constant 0 source, without inputs.
+    ZERO,
+    /// A constant 1 source, without inputs.
+    ONE,
 }
 
 impl GateOperation {
-    fn parse(str: &str) -> Option<GateOperation> {
-        match str {
-            "XOR" => Some(Self::XOR),
-            "OR" => Some(Self::OR),
-            "AND" => Some(Self::AND),
-            _ => None,
+    pub const ALL: [GateOperation; 9] = [
+        GateOperation::XOR,
+        GateOperation::OR,
+        GateOperation::AND,
+        GateOperation::NOT,
+        GateOperation::NAND,
+        GateOperation::NOR,
+        GateOperation::XNOR,
+        GateOperation::ZERO,
+        GateOperation::ONE,
+    ];
+
+    /// Number of inputs the operation reads.
+    pub fn arity(self) -> usize {
+        match self {
+            GateOperation::ZERO | GateOperation::ONE => 0,
+            GateOperation::NOT => 1,
+            _ => 2,
+        }
+    }
+
+    /// Applies the operation to every bit of the inputs at once. Inputs past the arity are
+    /// ignored.
+    pub fn evaluate(self, input_1: u64, input_2: u64) -> u64 {
+        match self {
+            GateOperation::XOR => input_1 ^ input_2,
+            GateOperation::OR => input_1 | input_2,
+            GateOperation::AND => input_1 & input_2,
+            GateOperation::NOT => !input_1,
+            GateOperation::NAND => !(input_1 & input_2),
+            GateOperation::NOR => !(input_1 | input_2),
+            GateOperation::XNOR => !(input_1 ^ input_2),
+            GateOperation::ZERO => 0,
+            GateOperation::ONE => u64::MAX,
+        }
+    }
+
+    /// How the operation is written in the puzzle format. Constants are written as 0 and 1.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            GateOperation::XOR => "XOR",
+            GateOperation::OR => "OR",
+            GateOperation::AND => "AND",
+            GateOperation::NOT => "NOT",
+            GateOperation::NAND => "NAND",
+            GateOperation::NOR => "NOR",
+            GateOperation::XNOR => "XNOR",
+            GateOperation::ZERO => "0",
+            GateOperation::ONE => "1",
         }
     }
+
+    /// Name of the matching Verilog gate primitive, or None for the constants which are
+    /// assigned instead.
+    fn verilog_primitive(&self) -> Option<&'static str> {
+        match self {
+            GateOperation::XOR => Some("xor"),
+            GateOperation::OR => Some("or"),
+            GateOperation::AND => Some("and"),
+            GateOperation::NOT => Some("not"),
+            GateOperation::NAND => Some("nand"),
+            GateOperation::NOR => Some("nor"),
+            GateOperation::XNOR => Some("xnor"),
+            GateOperation::ZERO | GateOperation::ONE => None,
+        }
+    }
+
+    fn dot_color(&self) -> &'static str {
+        match self {
+            GateOperation::XOR => "lightblue",
+            GateOperation::OR => "palegreen",
+            GateOperation::AND => "lightsalmon",
+            GateOperation::NOT => "khaki",
+            GateOperation::NAND => "lightpink",
+            GateOperation::NOR => "aquamarine",
+            GateOperation::XNOR => "lightskyblue",
+            GateOperation::ZERO | GateOperation::ONE => "white",
+        }
+    }
+
+    fn parse(str: &str) -> Option<GateOperation> {
+        Self::ALL
+            .into_iter()
+            .find(|operation| operation.keyword() == str)
+    }
 }
 
 impl Gate {
-    fn update(&mut self, circuit: &Circuit) {
-        self.output_powered = match self.operation {
-            GateOperation::OR => {
-                circuit.components[self.input_1].output()
-                    || circuit.components[self.input_2].output()
-            }
-            GateOperation::XOR => {
-                circuit.components[self.input_1].output()
-                    != circuit.components[self.input_2].output()
-            }
-            GateOperation::AND => {
-                circuit.components[self.input_1].output()
-                    && circuit.components[self.input_2].output()
-            }
-        }
+    pub fn id(&self) -> usize {
+        self.id
     }
 
-    fn other_input(&self, input: usize) -> usize {
-        if self.input_1 == input {
-            self.input_2
-        } else {
-            self.input_1
+    pub fn operation(&self) -> GateOperation {
+        self.operation
+    }
+
+    /// Ids of the input wires, as many as the operation reads.
+    pub fn inputs(&self) -> &[usize] {
+        &self.inputs[..self.operation.arity()]
+    }
+
+    /// Id of the output wire.
+    pub fn output(&self) -> usize {
+        self.output
+    }
+
+    fn update(&mut self, circuit: &Circuit) {
+        let mut values = [0; 2];
+        for (value, input) in values.iter_mut().zip(self.inputs()) {
+            *value = circuit.components[*input].output() as u64;
         }
+        self.output_powered = self.operation.evaluate(values[0], values[1]) & 1 == 1;
     }
 }
 
@@ -140,26 +189,6 @@ impl CircuitComponent {
     }
 }
 
-struct FullAdder {
-    xor_1: Gate,
-    and_1: Gate,
-    xor_2: Vec<Gate>,
-    and_2: Vec<Gate>,
-    or: Vec<Gate>,
-}
-
-impl FullAdder {
-    fn new(xor_1: Gate, and_1: Gate) -> FullAdder {
-        FullAdder {
-            xor_1,
-            and_1,
-            xor_2: Vec::new(),
-            and_2: Vec::new(),
-            or: Vec::new(),
-        }
-    }
-}
-
 // TODO:
 // Store the components as objects in an array/vec. Use indexes as pointers to other components.
 // Any changes to the circuit must be done through specific methods.
@@ -185,10 +214,17 @@ impl Circuit {
                 modifier
             ),
         };
-        let CircuitComponent::Wire(mut wire) = self.get_component(wire_index) else {
+        let CircuitComponent::Wire(_) = self.get_component(wire_index) else {
             panic!("The input wire for id {}{} residing at index {} in components didn't return a wire.", modifier, index, wire_index);
         };
+        self.set_wire(wire_index, powered);
+    }
 
+    /// Powers a input wire and updates everything connected to it.
+    fn set_wire(&mut self, wire_index: usize, powered: bool) {
+        let CircuitComponent::Wire(mut wire) = self.get_component(wire_index) else {
+            panic!("CircuitComponent at index: {} was not a wire!", wire_index);
+        };
         wire.powered = powered;
         self.set_component(wire_index, CircuitComponent::Wire(wire));
         self.update_connected_components(wire_index);
@@ -216,6 +252,18 @@ impl Circuit {
         self.components[index] = component;
     }
 
+    /// Brings every gate output in line with its inputs. Gates start out unpowered, which is
+    /// only right for the ones that output 0 when every input is 0, unlike NOT or a constant 1.
+    fn settle(&mut self) {
+        for index in 0..self.components.len() {
+            if let CircuitComponent::Gate(gate) = self.get_component(index) {
+                if self.update_gate(index, gate).is_some() {
+                    self.update_connected_components(index);
+                }
+            }
+        }
+    }
+
     fn update_connected_components(&mut self, component_index: usize) {
         let mut update_list: Vec<usize> = Vec::new();
 
@@ -287,16 +335,15 @@ impl Circuit {
         panic!("id: {} does not exist!", id)
     }
 
-    fn swap_wires(&mut self, wire_1_id: usize, wire_2_id: usize) {
+    /// Swaps the gates driving the two wires. Panics if either wire isn't driven by a gate.
+    pub fn swap_wires(&mut self, wire_1_id: usize, wire_2_id: usize) {
         let (mut wire_1, mut wire_2) = (
             self.get_wire(wire_1_id).clone(),
             self.get_wire(wire_2_id).clone(),
         );
         let (gate_1_id, gate_2_id) = (wire_1.source, wire_2.source);
-        let (mut gate_1, mut gate_2) = (
-            self.get_gate(wire_1.source).clone(),
-            self.get_gate(wire_2.source).clone(),
-        );
+        let (mut gate_1, mut gate_2) =
+            (*self.get_gate(wire_1.source), *self.get_gate(wire_2.source));
         gate_1.output = wire_2_id;
         gate_2.output = wire_1_id;
         wire_1.source = gate_2_id;
@@ -305,419 +352,58 @@ impl Circuit {
         self.set_component(wire_2_id, CircuitComponent::Wire(wire_2));
         self.set_component(gate_1_id, CircuitComponent::Gate(gate_1));
         self.set_component(gate_2_id, CircuitComponent::Gate(gate_2));
-
-        println!(
-            "Swapped: {} with {}",
-            self.get_component_name(wire_1_id),
-            self.get_component_name(wire_2_id)
-        );
     }
 
-    pub fn fix(&mut self) {
-        let output_0_wire = self.get_wire(self.output_z[0]);
-        let (xor_1, xor_1_id) = self
-            .get_wire(self.input_x[0])
-            .get_connected_gate(self, GateOperation::XOR)
-            .unwrap();
-        if output_0_wire.source != xor_1_id {
-            println!("z0 was not connected to input1xor and has been swapped.");
-            self.swap_wires(self.output_z[0], xor_1.output);
-        }
-        let mut carry_id = self
-            .get_wire(self.input_x[0])
-            .get_connected_gate(self, GateOperation::AND)
-            .unwrap()
-            .0
-            .output;
-        let mut carry = self.get_wire(carry_id).clone();
-
-        for i in 1..self.input_x.len() {
-            let ((xor_1, xor_1_id), (and_1, and_1_id)) = (
-                self.get_wire(self.input_x[i])
-                    .get_connected_gate(self, GateOperation::XOR)
-                    .unwrap(),
-                self.get_wire(self.input_x[i])
-                    .get_connected_gate(self, GateOperation::AND)
-                    .unwrap(),
-            );
-            let (xor_1_output_wire, and_1_output_wire) = (
-                self.get_wire(xor_1.output).clone(),
-                self.get_wire(and_1.output).clone(),
-            );
-
-            if !xor_1_output_wire.exclusive_connections(&carry).is_empty() {
-                if let Some((xor_2, xor_2_id)) =
-                    xor_1_output_wire.get_connected_gate(self, GateOperation::XOR)
-                {
-                    if xor_2.output == self.output_z[i] {
-                        println!("carry is likely connected to the wrong source.");
-                        self.swap_wires(carry_id, {
-                            if xor_2.input_1 == xor_1.output {
-                                xor_2.input_2
-                            } else {
-                                xor_2.input_1
-                            }
-                        });
-                    }
-                } else if let Some((xor_2, xor_2_id)) =
-                    carry.get_connected_gate(self, GateOperation::XOR)
-                {
-                    if xor_2.output == self.output_z[i] {
-                        println!("xor_1 output is likely wrong.");
-                        self.swap_wires(xor_1.output, {
-                            if xor_2.input_1 == carry_id {
-                                xor_2.input_2
-                            } else {
-                                xor_2.input_1
-                            }
-                        });
-                    }
-                };
-            } else {
-                let (xor_2, xor_2_id) = xor_1_output_wire
-                    .get_connected_gate(self, GateOperation::XOR)
-                    .unwrap();
-                if xor_2.output != self.output_z[i] {
-                    println!("Swapped: xor_2.output with output_z[i]");
-                    self.swap_wires(xor_2.output, self.output_z[i]);
-                }
-            }
-
-            let (xor_1, xor_1_id) = self
-                .get_wire(self.input_x[i])
-                .get_connected_gate(self, GateOperation::XOR)
-                .unwrap();
-
-            let and_1_output_wire = self
-                .get_wire(
-                    self.get_wire(self.input_x[i])
-                        .get_connected_gate(self, GateOperation::AND)
-                        .unwrap()
-                        .0
-                        .output,
-                )
-                .clone();
-            let and_2_output_wire = self.get_wire(
-                self.get_wire(
-                    self.get_wire(self.input_x[i])
-                        .get_connected_gate(self, GateOperation::XOR)
-                        .unwrap()
-                        .0
-                        .output,
-                )
-                .get_connected_gate(self, GateOperation::AND)
-                .unwrap()
-                .0
-                .output,
-            );
-
-            let Some((and_2, and_2_id)) = self
-                .get_wire(xor_1.output)
-                .get_connected_gate(self, GateOperation::AND)
-            else {
-                panic!(
-                    "Failed to get a AND gate connected to wire with id: {}\nWire connects to {} elements.",
-                    xor_1.output, self.get_wire(xor_1.output).connections.len()
-                );
-            };
-            let and_2_output_wire = self.get_wire(and_2.output).clone();
-
-            if !and_1_output_wire
-                .exclusive_connections(&and_2_output_wire)
-                .is_empty()
-            {
-                // and_1 & and_2 outputs are not connected to the same gate!
-                // One or both of them are wrongly connected.
-
-                // for or_gate in or_gates connected to the two and gates.
-                for gate_id in and_1_output_wire.exclusive_connections(&and_2_output_wire) {
-                    let gate = self.get_gate(gate_id);
-                    if gate.operation == GateOperation::OR {
-                    } else {
-                        // the wire connected to this gate is wrongly connected!
-                        // Figure out the correct gate by:
-                        // 1: get connected OR gate from the output_wire not connected to gate.
-                        // 2: check validity of OR gate output. (Does it connect to a xor connected
-                        //    to output_z[i+1])
-                        // 3: If yes then swap gate.source with other OR gates input wire which is
-                        //    NOT connected to the other AND gate.
-
-                        let other_or_gate = {
-                            if and_1_output_wire.is_connected(gate) {
-                                and_2_output_wire.get_connected_gate(self, GateOperation::OR)
-                            } else {
-                                and_1_output_wire.get_connected_gate(self, GateOperation::OR)
-                            }
-                        };
-
-                        let Some((other_or_gate, _)) = other_or_gate else {
-                            // Neither of the two AND gates connect to a OR gate.
-                            panic!(
-                                "Found two AND gates with outputs NOT connected to any OR gate."
-                            );
-                        };
-
-                        let Some((xor, _)) = self
-                            .get_wire(other_or_gate.output)
-                            .get_connected_gate(self, GateOperation::XOR)
-                        else {
-                            // other_or_gate is not connected to a xor gate!
-                            if i == self.input_x.len() - 1 {
-                                if other_or_gate.output == self.output_z[self.output_z.len() - 1] {
-                                    // OR gate is valid!
-                                    if and_1_output_wire.is_connected(gate) {
-                                        self.swap_wires(
-                                            and_1_output_wire.id,
-                                            other_or_gate.other_input(and_2_output_wire.id),
-                                        );
-                                        continue;
-                                    }
-                                }
-                            }
-                            panic!("other_or_gate is not connected to a xor gate!");
-                        };
-
-                        if xor.output == self.output_z[i + 1] {
-                            // OR gate is valid!
-                            if i == self.input_x.len() - 1 {
-                                if other_or_gate.output == self.output_z[self.output_z.len() - 1] {
-                                    // OR gate is valid!
-                                    if and_1_output_wire.is_connected(gate) {
-                                        self.swap_wires(
-                                            and_1_output_wire.id,
-                                            other_or_gate.other_input(and_2_output_wire.id),
-                                        );
-                                        continue;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            } else {
-                // Both and gates share the same target gate/s with their outputs.
-                let Some(or_gate) = and_1_output_wire.get_connected_gate(self, GateOperation::OR)
-                else {
-                    panic!("No or_gate found connected to and_1!");
-                };
-                carry_id = or_gate.0.output;
-                carry = self.get_wire(carry_id).clone();
-                //println!("Carry: {}", carry_id);
-
-                // TODO: The output of or_gate is checked in the next iteration of the loop.
-                // But what about the last iteration where carry should instead connect to the last
-                // output_z?
-            }
-        }
-
-        println!("Fixed!");
+    /// Iterates over the gates in the order they were added.
+    pub fn gates(&self) -> impl Iterator<Item = &Gate> {
+        self.components
+            .iter()
+            .filter_map(|component| match component {
+                CircuitComponent::Gate(gate) => Some(gate),
+                CircuitComponent::Wire(_) => None,
+            })
     }
 
-    pub fn fix_FullAdders(&mut self) {
-        let carry_wire: Option<Wire> = None;
-        let mut faulty_wires: Vec<usize> = Vec::new();
-        for index in 0..self.input_x.len() {
-            let (x_input_wire, y_input_wire, z_output_wire) = (
-                self.get_wire(self.input_x[index]),
-                self.get_wire(self.input_y[index]),
-                self.get_wire(self.output_z[index]),
-            );
-
-            let (first_xor, first_and) = {
-                if !(x_input_wire.connections[0] == y_input_wire.connections[0]
-                    && x_input_wire.connections[1] == y_input_wire.connections[1])
-                {
-                    panic!("Input wire connection for wires index: {} has a mismatch! This should not be possible based on the rules specified.", index);
-                }
-
-                let (gate_1, gate_2) = (
-                    self.get_gate(x_input_wire.connections[0]),
-                    self.get_gate(x_input_wire.connections[1]),
-                );
-
-                if gate_1.operation == GateOperation::XOR && gate_2.operation == GateOperation::AND
-                {
-                    (gate_1, gate_2)
-                } else if gate_1.operation == GateOperation::AND
-                    && gate_2.operation == GateOperation::XOR
-                {
-                    (gate_2, gate_1)
-                } else {
-                    panic!("Input wire connection for wires index: {} does not have the correct gate types connected!", index);
-                }
-            };
-            let mut full_adder = FullAdder::new(*first_xor, *first_and);
-
-            let (second_xor, second_and) = {
-                let other_wire = carry_wire.clone();
-                let first_xor_output = self.get_wire(first_xor.output);
+    /// Panics if the component isn't a gate.
+    pub fn gate(&self, gate_id: usize) -> &Gate {
+        self.get_gate(gate_id)
+    }
 
-                // If the wire doesn't have 2 connections it is guaranteed to be faulty.
-                if first_xor_output.connections.len() != 2 {
-                    faulty_wires.push(first_xor.output);
-                    // TODO: Figure out the second XOR or AND and add the XOR input to faulty
-                    // wires.
-                }
+    /// Number of wires and gates, which is one more than the highest id.
+    pub fn component_count(&self) -> usize {
+        self.components.len()
+    }
 
-                let second_xor = {
-                    let mut result;
-                    for (gate, id) in first_xor_output
-                        .connections
-                        .iter()
-                        .map(|connection| (self.get_gate(*connection), connection))
-                    {
-                        if gate.operation == GateOperation::XOR {
-                            if let Some(other_wire) = &other_wire {
-                                if other_wire.connections.contains(id)
-                                    || gate.output == self.output_z[index]
-                                {
-                                    result = gate;
-                                    break;
-                                }
-                            }
-                        }
-                    }
+    pub fn wire_id(&self, name: &str) -> Option<usize> {
+        self.component_ids.get(name).copied()
+    }
 
-                    5
-                };
+    /// Id of the gate driving the wire, or None for inputs.
+    pub fn wire_source(&self, wire_id: usize) -> Option<usize> {
+        let wire = self.get_wire(wire_id);
+        self.has_source(wire).then_some(wire.source)
+    }
 
-                if let Some(carry_wire) = &carry_wire {
-                    for gate in first_xor_output.shares_connections(carry_wire) {}
-                }
-                let (gate_1, gate_2) = (
-                    self.get_gate(first_xor_output.connections[0]),
-                    self.get_gate(first_xor_output.connections[1]),
-                );
+    /// Ids of the gates reading the wire.
+    pub fn wire_connections(&self, wire_id: usize) -> &[usize] {
+        &self.get_wire(wire_id).connections
+    }
 
-                (5, 5)
-            };
+    /// Wire ids of the x, y or z bus, lowest bit first.
+    pub fn bus(&self, modifier: char) -> &[usize] {
+        match modifier {
+            'x' => &self.input_x,
+            'y' => &self.input_y,
+            'z' => &self.output_z,
+            _ => panic!(
+                "The bus modifier {} is not valid. Please use 'x', 'y' or 'z'",
+                modifier
+            ),
         }
     }
 
-    // Rules:
-    // AND gates sources must always be either:
-    //
-    // A & B s 1
-    // A & B s 4
-    // 1.o & C s 2
-    // 1.o & C s 3
-    // 2.o & 3.o s 5
-    // 2.o s Z[index]
-    // if last added:
-    // 5.o s Z[index+1];
-    //
-    // let carry_wire = z[46];
-    // For i in 45..0
-    // if let OR_gate | carry_wire.source == OR
-    //      if OR_gate.input_1.source != AND
-    //          OR_gate.input_1 is wrongly connected!
-    //      if OR_gate.input_2.source != AND
-    //          OR_gate.input_2 is wrongly connected!
-    //
-
-    // #########################################################################################
-
-    //  The first adder is not full. It should only consist of 1 AND & 1 XOR gate. Since there is
-    //  no need for a carry in.
-    //  Handle it first, then start processing the rest.
-    //
-    //
-    //  if output_z[0].source != input_1.connections.XOR (xor)
-    //      output_z[0] is wrongly connected.
-    //      xor.output is wrongly connected.
-    //      repair circuit by swapping the two.
-    //
-    //  carries = input_1.connections.AND.output
-    //
-    //  for i in 1..input_wires.len()
-    //  {
-    //  let xor_1, and_1 = gates connected to input wires.
-    //
-    //  for carry in carries
-    //      if xor_1.output.connections != carry.shares_connections
-    //          // xor_1.output or carry is wrongly connected.
-    //          get any connected XOR gates to xor_1.output and carry
-    //          for gate in XOR gates
-    //              if gate.output == output_z[i]
-    //                  // The wire NOT connected to this gate is wrongly connected. Meaning the wire
-    //                  connected to this gate that is NOT connected to xor_1.output/carry is also
-    //                  wrongly connected.
-    //                  repair circuit by swapping the sources of the two wires.
-    //                  break loop.
-    //
-    //      if xor_1.output and carry shares a connection with the same XOR gate (xor_2)
-    //          if xor_2 is NOT connected to output_z[i]
-    //              xor_2.output is wrongly connected.
-    //              output_z[i] is wrongly connected.
-    //              repair circuit by swapping the sources of xor_2.output and output_z[i]
-    //
-    //  4 out of five gates in this full_adder is now verified.
-    //  Both input gates since they can't have been wrongly connected.
-    //  The two other xor and and gates have at this point been fixed if they where broken.
-    //      (xor_2, and_2)
-    //
-    //  if and_2.output != and_1.output
-    //      one or both of them are wrongly connected.
-    //      for or_gate in or_gates connected to and_1 & and_2
-    //          if (
-    //              (if i+1 >= input.len()) or_gate.output = output_z[i+2] ||
-    //              or_gate.output.connections == input_x[i+1] ||
-    //              or_gate.output.connections.XOR.output == output_z[i+1]
-    //          )
-    //              The wire NOT connected to this or_gate is wrongly connected.
-    //              The wire connected to this gate that does not have one of the and gates as its
-    //              source is wrongly connected.
-    //              repair the circuit by swapping the two wires sources.
-    //      if loop ends without a repair being performed panic because of the found fault without
-    //      a clear solution.
-    //
-    //  if and_1 & and_2 shares a connection to the same OR gate (or_gate)
-    //      set carry to or_gate.output
-    //
-    //
-    //
-    //
-    //
-    //
-    //      if or_gate.output != carry (
-    //      )
-    //          or_gate.output is wrongly connected.
-    //          carry is wrongly connected.
-    //          repair the circuit by swapping the two wire sources.
-    //
-    //  }
-
-    // #########################################################################################
-
-    // carry = output_z[output_z.len()]
-    // for i i input_wires.len()..0
-    // {
-    //  let xor_1, and_1 = gates connected to input wires.
-    //  if and_1.output is connected to a OR gate (or_gate)
-    //      if or_gate.output == carry
-    //          and_1.output and or_gate.output are likely valid.
-    //      else if (
-    //          other input of or_gate is AND gate (other_and) &&
-    //          (
-    //              other_and.input_1.source is xor_1 ||
-    //              other_and.input_1.connections.XOR.output == output_z[i]
-    //          )
-    //      ) ->
-    //          or_gate.output is wrongly connected.
-    //          carry.source is wrongly connected.
-    //          repair the circuit by swapping the two wire sources. (And the connected gates
-    //          outputs.)
-    //
-    //  if carry.source is a OR gate (or_gate)
-    //      if or_gate.input_2 ==
-    //  else
-    //      // carry is wrongly connected. Figure out what or gate it is supposed to be connected
-    //      to.
-    // }
-    //
-
     pub fn print_info(&self) {
-        let (mut or, mut and, mut xor) = (0, 0, 0);
+        let (mut or, mut and, mut xor, mut other) = (0, 0, 0, 0);
         for (index, component) in self.components.iter().enumerate() {
             match component {
                 CircuitComponent::Wire(wire) => {
@@ -739,14 +425,266 @@ impl Circuit {
                     GateOperation::OR => or += 1,
                     GateOperation::AND => and += 1,
                     GateOperation::XOR => xor += 1,
+                    _ => other += 1,
                 },
             }
         }
 
-        println!("Gate count: \nAND: {}\nXOR: {}\nOR: {}", and, xor, or);
+        println!(
+            "Gate count: \nAND: {}\nXOR: {}\nOR: {}\nOther: {}",
+            and, xor, or, other
+        );
     }
 }
 
+/// Exporting the circuit for other tools.
+impl Circuit {
+    /// Wire names indexed by component id. Gates have no name and get an empty string.
+    pub fn component_names(&self) -> Vec<&str> {
+        let mut names = vec![""; self.components.len()];
+        for (name, id) in &self.component_ids {
+            names[*id] = name;
+        }
+        names
+    }
+
+    /// Returns true if a gate drives the wire. Wires without one are inputs to the circuit.
+    fn has_source(&self, wire: &Wire) -> bool {
+        matches!(self.components[wire.source], CircuitComponent::Gate(_))
+    }
+
+    /// Returns the circuit as a Graphviz graph running left to right, with wires as ellipses
+    /// named after the wire and gates as boxes named after their operation. The x and y inputs
+    /// line up on the left and the z outputs on the right, so a swapped output stands out as a
+    /// edge crossing into the wrong part of the adder chain.
+    pub fn to_dot(&self) -> String {
+        let names = self.component_names();
+        let mut dot = String::from("digraph circuit {\n    rankdir=LR;\n");
+
+        for (id, component) in self.components.iter().enumerate() {
+            match component {
+                CircuitComponent::Wire(_) => {
+                    dot.push_str(&format!("    w{id} [label=\"{}\"];\n", names[id]));
+                }
+                CircuitComponent::Gate(gate) => {
+                    dot.push_str(&format!(
+                        "    g{id} [label=\"{}\", shape=box, style=filled, fillcolor={}];\n",
+                        gate.operation.keyword(),
+                        gate.operation.dot_color()
+                    ));
+                    for input in gate.inputs() {
+                        dot.push_str(&format!("    w{input} -> g{id};\n"));
+                    }
+                    dot.push_str(&format!("    g{id} -> w{};\n", gate.output));
+                }
+            }
+        }
+
+        let rank = |ids: &[usize]| -> String {
+            ids.iter().map(|id| format!("w{id}; ")).collect::<String>()
+        };
+        dot.push_str(&format!(
+            "    {{ rank=source; {}{}}}\n",
+            rank(&self.input_x),
+            rank(&self.input_y)
+        ));
+        dot.push_str(&format!("    {{ rank=sink; {}}}\n", rank(&self.output_z)));
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the circuit as a structural Verilog module built from the gate primitives, with
+    /// constants assigned. Wires without a source become inputs, the z bus becomes the outputs and
+    /// every other wire is declared internally. Gate instances are named after their component
+    /// id.
+    pub fn to_verilog(&self, module_name: &str) -> String {
+        let names = self.component_names();
+        let name = |id: usize| verilog_identifier(names[id]);
+
+        let mut inputs = Vec::new();
+        let mut internal = Vec::new();
+        for (id, component) in self.components.iter().enumerate() {
+            if let CircuitComponent::Wire(wire) = component {
+                if !self.has_source(wire) {
+                    inputs.push(id);
+                } else if !self.output_z.contains(&id) {
+                    internal.push(id);
+                }
+            }
+        }
+        // List the buses in bit order rather than in the order the wires were first seen.
+        inputs.sort_by_key(|id| names[*id]);
+        internal.sort_by_key(|id| names[*id]);
+
+        let mut ports: Vec<String> = inputs
+            .iter()
+            .map(|id| format!("    input wire {}", name(*id)))
+            .collect();
+        ports.extend(
+            self.output_z
+                .iter()
+                .map(|id| format!("    output wire {}", name(*id))),
+        );
+
+        let mut verilog = format!(
+            "module {} (\n{}\n);\n",
+            verilog_identifier(module_name),
+            ports.join(",\n")
+        );
+        for id in internal {
+            verilog.push_str(&format!("    wire {};\n", name(id)));
+        }
+        verilog.push('\n');
+        for (id, component) in self.components.iter().enumerate() {
+            let CircuitComponent::Gate(gate) = component else {
+                continue;
+            };
+            let line = match gate.operation.verilog_primitive() {
+                Some(primitive) => {
+                    let mut terminals = vec![name(gate.output)];
+                    terminals.extend(gate.inputs().iter().map(|input| name(*input)));
+                    format!("{primitive} g{id} ({});", terminals.join(", "))
+                }
+                None => format!(
+                    "assign {} = 1'b{};",
+                    name(gate.output),
+                    gate.operation.keyword()
+                ),
+            };
+            verilog.push_str(&format!("    {line}\n"));
+        }
+        verilog.push_str("endmodule\n");
+        verilog
+    }
+
+    pub fn save_dot<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_dot())
+    }
+
+    pub fn save_verilog<P: AsRef<Path>>(&self, path: P, module_name: &str) -> io::Result<()> {
+        std::fs::write(path, self.to_verilog(module_name))
+    }
+
+    /// Returns the circuit in the puzzle format that CircuitBuilder::add_line reads: the current
+    /// x and y inputs as "x00: 1", a empty line, then a line per gate. Gates keep the order they
+    /// were added in and write the wire they drive now, so swapped outputs are saved as swapped.
+    pub fn to_text(&self) -> String {
+        let names = self.component_names();
+        let mut text = String::new();
+        for id in self.input_x.iter().chain(&self.input_y) {
+            let powered = self.get_wire(*id).powered;
+            text.push_str(&format!("{}: {}\n", names[*id], powered as u8));
+        }
+        text.push('\n');
+
+        for gate in self.gates() {
+            let keyword = gate.operation.keyword();
+            let output = names[gate.output];
+            let line = match gate.inputs() {
+                [] => format!("{keyword} -> {output}"),
+                [input] => format!("{keyword} {} -> {output}", names[*input]),
+                [input_1, input_2] => {
+                    format!(
+                        "{} {keyword} {} -> {output}",
+                        names[*input_1], names[*input_2]
+                    )
+                }
+                _ => unreachable!("Gates have at most two inputs"),
+            };
+            text.push_str(&line);
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    /// Reads the puzzle format written by to_text, setting the inputs listed before the gates.
+    pub fn from_lines<I, S>(lines: I) -> Result<Circuit, CircuitParseError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut lines = lines.into_iter();
+        let mut inputs = Vec::new();
+        for line in lines.by_ref() {
+            let line = line.as_ref().trim();
+            if line.is_empty() {
+                break;
+            }
+            let invalid = || CircuitParseError::InvalidLine(line.to_string());
+            let (name, value) = line.split_once(':').ok_or_else(invalid)?;
+            let powered = match value.trim() {
+                "0" => false,
+                "1" => true,
+                _ => return Err(invalid()),
+            };
+            inputs.push((name.trim().to_string(), powered));
+        }
+
+        let mut builder = CircuitBuilder::new();
+        for line in lines {
+            let line = line.as_ref().trim();
+            if !line.is_empty() {
+                builder.add_line(line)?;
+            }
+        }
+        let mut circuit = builder.assemble()?;
+        for (name, powered) in inputs {
+            let wire_index = circuit
+                .wire_id(&name)
+                .filter(|id| circuit.wire_source(*id).is_none())
+                .ok_or(CircuitParseError::UnknownInput(name))?;
+            circuit.set_wire(wire_index, powered);
+        }
+        Ok(circuit)
+    }
+}
+
+/// Wire names are usually plain identifiers already. Anything else, like a name starting with a
+/// digit, is written as a escaped identifier, which Verilog ends at the next whitespace.
+fn verilog_identifier(name: &str) -> String {
+    let mut chars = name.chars();
+    let is_plain = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_');
+    match is_plain {
+        true => name.to_string(),
+        false => format!("\\{name} "),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CircuitParseError {
+    /// The line isn't shaped like a gate, or has the wrong number of inputs for its operation.
+    InvalidLine(String),
+    UnknownOperation(String),
+    /// A input value for a wire that doesn't exist or is driven by a gate.
+    UnknownInput(String),
+    /// The gates driving these wires feed back into themselves, see CompileError.
+    CombinationalLoop(Vec<String>),
+}
+
+impl Display for CircuitParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitParseError::InvalidLine(line) => write!(f, "Invalid gate: '{line}'"),
+            CircuitParseError::UnknownOperation(operation) => {
+                write!(f, "Unknown gate operation: '{operation}'")
+            }
+            CircuitParseError::UnknownInput(wire) => write!(f, "'{wire}' isn't a input wire"),
+            CircuitParseError::CombinationalLoop(wires) => {
+                write!(f, "Combinational loop through: {}", wires.join(","))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CircuitParseError {}
+
 pub struct CircuitBuilder {
     component_ids: HashMap<String, usize>,
     components: Vec<CircuitComponent>,
@@ -776,6 +714,7 @@ impl CircuitBuilder {
         }
     }
 
+    /// Adds a gate with two inputs. Panics if the operation isn't one of those.
     pub fn add_gate(
         &mut self,
         input_1: String,
@@ -783,44 +722,77 @@ impl CircuitBuilder {
         input_2: String,
         output: String,
     ) {
-        let Some(gate_op) = GateOperation::parse(&gate_operation) else {
+        let Some(gate_op) = GateOperation::parse(&gate_operation).filter(|op| op.arity() == 2)
+        else {
             panic!(
                 "INVALID GATE OPERATION: [{}] is not a valid gate!",
                 gate_operation
             );
         };
+        self.add_component(gate_op, &[input_1, input_2], output);
+    }
 
-        let input_1_index: usize = self.get_wire_index(input_1);
-        let input_2_index: usize = self.get_wire_index(input_2);
+    pub fn add_not(&mut self, input: String, output: String) {
+        self.add_component(GateOperation::NOT, &[input], output);
+    }
+
+    /// Adds a source that always drives the output with the value.
+    pub fn add_constant(&mut self, value: bool, output: String) {
+        let operation = match value {
+            true => GateOperation::ONE,
+            false => GateOperation::ZERO,
+        };
+        self.add_component(operation, &[], output);
+    }
+
+    /// Adds a gate written like the puzzle input, "x00 AND y00 -> z00". Inverting gates are
+    /// written the same way, NOT takes a single input as in "NOT x00 -> a" and constants take
+    /// none, as in "1 -> a".
+    pub fn add_line(&mut self, line: &str) -> Result<(), CircuitParseError> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (keyword, inputs, output) = match tokens.as_slice() {
+            [keyword, "->", output] => (keyword, vec![], output),
+            [keyword, input, "->", output] => (keyword, vec![input], output),
+            [input_1, keyword, input_2, "->", output] => (keyword, vec![input_1, input_2], output),
+            _ => return Err(CircuitParseError::InvalidLine(line.to_string())),
+        };
+
+        let operation = GateOperation::parse(keyword)
+            .ok_or_else(|| CircuitParseError::UnknownOperation(keyword.to_string()))?;
+        if operation.arity() != inputs.len() {
+            return Err(CircuitParseError::InvalidLine(line.to_string()));
+        }
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        self.add_component(operation, &inputs, output.to_string());
+        Ok(())
+    }
+
+    fn add_component(&mut self, operation: GateOperation, inputs: &[String], output: String) {
+        let mut input_indexes = [0; 2];
+        for (index, input) in input_indexes.iter_mut().zip(inputs) {
+            *index = self.get_wire_index(input.clone());
+        }
         let output_index: usize = self.get_wire_index(output);
         let gate_index = self.components.len();
 
         let gate = Gate {
             id: gate_index,
-            input_1: input_1_index,
-            input_2: input_2_index,
-            operation: gate_op,
+            inputs: input_indexes,
+            operation,
             output_powered: false,
             output: output_index,
         };
         self.components.push(CircuitComponent::Gate(gate));
-        let gate_index = self.components.len() - 1;
 
-        if let CircuitComponent::Wire(wire) = &mut self.components[input_1_index] {
-            wire.connections.push(gate_index);
-        } else {
-            panic!(
-                "Expected a wire at index: {} but found a gate! \n{:?}",
-                input_1_index, self.components
-            );
-        }
-        if let CircuitComponent::Wire(wire) = &mut self.components[input_2_index] {
-            wire.connections.push(gate_index);
-        } else {
-            panic!(
-                "expected a wire at index: {} but found a gate! \n{:?}",
-                input_2_index, self.components
-            );
+        for input_index in &input_indexes[..inputs.len()] {
+            if let CircuitComponent::Wire(wire) = &mut self.components[*input_index] {
+                wire.connections.push(gate_index);
+            } else {
+                panic!(
+                    "Expected a wire at index: {} but found a gate! \n{:?}",
+                    input_index, self.components
+                );
+            }
         }
 
         if let CircuitComponent::Wire(wire) = &mut self.components[output_index] {
@@ -850,14 +822,149 @@ impl CircuitBuilder {
         indexes
     }
 
-    pub fn assemble(mut self) -> Circuit {
+    /// Builds the circuit and settles every wire. Settling would never end on a loop of gates
+    /// that keeps flipping, like NOT a -> a, so loops are refused before that.
+    pub fn assemble(mut self) -> Result<Circuit, CircuitParseError> {
         let output_z = self.collect_bus('z');
-        Circuit {
+        let mut circuit = Circuit {
             input_x: self.collect_bus('x'),
             input_y: self.collect_bus('y'),
             component_ids: self.component_ids,
             components: self.components,
             output_z,
+        };
+        if let Err(CompileError::CombinationalLoop(wires)) = CompiledCircuit::new(&circuit) {
+            return Err(CircuitParseError::CombinationalLoop(wires));
         }
+        circuit.settle();
+        Ok(circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A half adder: z00 = x00 ^ y00 and the carry z01 = x00 & y00.
+    fn half_adder() -> Circuit {
+        Circuit::from_lines(["", "x00 XOR y00 -> z00", "x00 AND y00 -> z01"]).unwrap()
+    }
+
+    #[test]
+    fn dot_export() {
+        let dot = half_adder().to_dot();
+        assert!(dot.starts_with("digraph circuit {\n"));
+        // x00 and y00 are wires 0 and 1, the XOR gate 3 drives z00 at 2.
+        assert!(dot.contains("    w0 [label=\"x00\"];\n"));
+        assert!(dot.contains("    g3 [label=\"XOR\", shape=box"));
+        assert!(dot.contains("    w0 -> g3;\n    w1 -> g3;\n    g3 -> w2;\n"));
+        assert!(dot.contains("{ rank=source; w0; w1; }"));
+        assert!(dot.contains("{ rank=sink; w2; w4; }"));
+    }
+
+    #[test]
+    fn verilog_export() {
+        let verilog = half_adder().to_verilog("half_adder");
+        let expected = "module half_adder (
+    input wire x00,
+    input wire y00,
+    output wire z00,
+    output wire z01
+);
+
+    xor g3 (z00, x00, y00);
+    and g5 (z01, x00, y00);
+endmodule
+";
+        assert_eq!(verilog, expected);
+        assert_eq!(verilog_identifier("4ab"), "\\4ab ");
+    }
+
+    fn parse(text: &str) -> Circuit {
+        Circuit::from_lines(text.lines()).unwrap()
+    }
+
+    #[test]
+    fn extended_gates() {
+        let text = "x00: 0
+y00: 0
+
+x00 NAND y00 -> z00
+x00 NOR y00 -> z01
+x00 XNOR y00 -> z02
+NOT x00 -> z03
+1 -> z04
+0 -> z05
+";
+        let mut circuit = parse(text);
+        // Inverting gates and constants are right before any input changes.
+        let output = |circuit: &Circuit| -> Vec<u8> {
+            circuit.output().iter().map(|bit| *bit as u8).collect()
+        };
+        assert_eq!(output(&circuit), vec![1, 1, 1, 1, 1, 0]);
+        circuit.set_input('x', 0, true);
+        assert_eq!(output(&circuit), vec![1, 0, 0, 0, 1, 0]);
+        circuit.set_input('y', 0, true);
+        assert_eq!(output(&circuit), vec![0, 0, 1, 0, 1, 0]);
+
+        let verilog = circuit.to_verilog("gates");
+        assert!(verilog.contains("    not g9 (z03, x00);\n"));
+        assert!(verilog.contains("    assign z04 = 1'b1;\n"));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = "x00: 0
+x01: 0
+y00: 1
+y01: 1
+
+x00 XOR y00 -> z00
+x00 AND y00 -> c
+x01 XOR y01 -> s
+s XOR c -> z01
+NOT s -> z02
+";
+        let mut circuit = parse(text);
+        assert_eq!(circuit.output(), vec![true, true, false]);
+        assert_eq!(circuit.to_text(), text);
+
+        let (z00, c) = (circuit.wire_id("z00"), circuit.wire_id("c"));
+        circuit.swap_wires(z00.unwrap(), c.unwrap());
+        let swapped = circuit.to_text();
+        assert!(swapped.contains("x00 XOR y00 -> c\nx00 AND y00 -> z00\n"));
+        assert_eq!(parse(&swapped).to_text(), swapped);
+        assert_eq!(
+            Circuit::from_lines(["s: 1", "", "x00 AND y00 -> s"]).err(),
+            Some(CircuitParseError::UnknownInput("s".to_string()))
+        );
+
+        let mut builder = CircuitBuilder::new();
+        assert_eq!(
+            builder.add_line("a FOO b -> c"),
+            Err(CircuitParseError::UnknownOperation("FOO".to_string()))
+        );
+        assert_eq!(
+            builder.add_line("a NOT b -> c"),
+            Err(CircuitParseError::InvalidLine("a NOT b -> c".to_string()))
+        );
+        assert_eq!(
+            builder.add_line("a AND b c"),
+            Err(CircuitParseError::InvalidLine("a AND b c".to_string()))
+        );
+    }
+
+    #[test]
+    fn refuses_loops() {
+        assert_eq!(
+            Circuit::from_lines(["", "NOT a -> a"]).err(),
+            Some(CircuitParseError::CombinationalLoop(vec!["a".to_string()]))
+        );
+        assert_eq!(
+            Circuit::from_lines(["x00: 1", "", "x00 AND b -> a", "NOT a -> b"]).err(),
+            Some(CircuitParseError::CombinationalLoop(
+                ["a", "b"].map(String::from).to_vec()
+            ))
+        );
     }
 }
@@ -0,0 +1,240 @@
+use std::fmt::Display;
+
+use crate::circuit::{Circuit, GateOperation};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CompileError {
+    /// The gates driving these wires feed back into themselves, so they have no value. Holds the
+    /// outputs of every gate that couldn't be ordered, which is the loops and anything after them.
+    CombinationalLoop(Vec<String>),
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompileError::CombinationalLoop(wires) => {
+                write!(f, "Combinational loop through: {}", wires.join(","))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+#[derive(Clone, Copy, Debug)]
+struct CompiledGate {
+    operation: GateOperation,
+    /// Only the first operation.arity() entries are used.
+    inputs: [usize; 2],
+    output: usize,
+}
+
+/// A circuit flattened into a list of gates that can be evaluated in a single pass.
+///
+/// The gates are sorted by level once, where a gate's level is one more than the highest level
+/// of the gates driving its inputs. Every wire then holds a u64 with one input vector per bit, so
+/// a pass evaluates 64 different inputs at once with plain bitwise operations.
+#[derive(Clone, Debug)]
+pub struct CompiledCircuit {
+    /// Sorted by level, so every gate comes after the gates driving its inputs.
+    gates: Vec<CompiledGate>,
+    /// Index into gates where each level starts.
+    level_starts: Vec<usize>,
+    /// Number of wire values needed, indexed by component id.
+    value_count: usize,
+    input_x: Vec<usize>,
+    input_y: Vec<usize>,
+    output_z: Vec<usize>,
+}
+
+impl CompiledCircuit {
+    /// Orders the gates with Kahn's algorithm, failing if some of them form a loop.
+    pub fn new(circuit: &Circuit) -> Result<Self, CompileError> {
+        let value_count = circuit.component_count();
+
+        // How many inputs of each gate are still waiting on another gate.
+        let mut waiting = vec![0; value_count];
+        let mut ready = Vec::new();
+        for gate in circuit.gates() {
+            waiting[gate.id()] = gate
+                .inputs()
+                .iter()
+                .filter(|wire| circuit.wire_source(**wire).is_some())
+                .count();
+            if waiting[gate.id()] == 0 {
+                ready.push(gate.id());
+            }
+        }
+
+        let mut level = vec![0; value_count];
+        let mut ordered = Vec::new();
+        while let Some(gate_id) = ready.pop() {
+            ordered.push(gate_id);
+            let output = circuit.gate(gate_id).output();
+            for consumer in circuit.wire_connections(output) {
+                level[*consumer] = level[*consumer].max(level[gate_id] + 1);
+                waiting[*consumer] -= 1;
+                if waiting[*consumer] == 0 {
+                    ready.push(*consumer);
+                }
+            }
+        }
+
+        if ordered.len() != circuit.gates().count() {
+            let names = circuit.component_names();
+            let mut stuck: Vec<String> = circuit
+                .gates()
+                .filter(|gate| waiting[gate.id()] > 0)
+                .map(|gate| names[gate.output()].to_string())
+                .collect();
+            stuck.sort();
+            return Err(CompileError::CombinationalLoop(stuck));
+        }
+
+        ordered.sort_by_key(|gate_id| level[*gate_id]);
+        let mut level_starts = Vec::new();
+        for (index, gate_id) in ordered.iter().enumerate() {
+            if index == 0 || level[*gate_id] != level[ordered[index - 1]] {
+                level_starts.push(index);
+            }
+        }
+        let gates = ordered
+            .into_iter()
+            .map(|gate_id| {
+                let gate = circuit.gate(gate_id);
+                CompiledGate {
+                    operation: gate.operation(),
+                    inputs: {
+                        let mut inputs = [0; 2];
+                        inputs[..gate.inputs().len()].copy_from_slice(gate.inputs());
+                        inputs
+                    },
+                    output: gate.output(),
+                }
+            })
+            .collect();
+
+        Ok(CompiledCircuit {
+            gates,
+            level_starts,
+            value_count,
+            input_x: circuit.bus('x').to_vec(),
+            input_y: circuit.bus('y').to_vec(),
+            output_z: circuit.bus('z').to_vec(),
+        })
+    }
+
+    /// Number of levels, the longest chain of gates from a input to a output.
+    pub fn depth(&self) -> usize {
+        self.level_starts.len()
+    }
+
+    /// Evaluates 64 input vectors at once. x[i] and y[i] hold bit i of every vector, with
+    /// vector n in bit n of each word. Returns the z bus in the same layout. Panics if the
+    /// slices don't match the bus widths.
+    pub fn simulate(&self, x: &[u64], y: &[u64]) -> Vec<u64> {
+        assert_eq!(x.len(), self.input_x.len(), "x doesn't match the bus width");
+        assert_eq!(y.len(), self.input_y.len(), "y doesn't match the bus width");
+
+        let mut values = vec![0; self.value_count];
+        for (bus, words) in [(&self.input_x, x), (&self.input_y, y)] {
+            for (id, word) in bus.iter().zip(words) {
+                values[*id] = *word;
+            }
+        }
+        for gate in &self.gates {
+            let mut inputs = [0; 2];
+            for (value, id) in inputs
+                .iter_mut()
+                .zip(&gate.inputs[..gate.operation.arity()])
+            {
+                *value = values[*id];
+            }
+            values[gate.output] = gate.operation.evaluate(inputs[0], inputs[1]);
+        }
+        self.output_z.iter().map(|id| values[*id]).collect()
+    }
+
+    /// Adds every x and y pair, 64 pairs per pass.
+    pub fn add_all(&self, pairs: &[(u64, u64)]) -> Vec<u64> {
+        let mut sums = Vec::with_capacity(pairs.len());
+        for chunk in pairs.chunks(64) {
+            let mut x = vec![0; self.input_x.len()];
+            let mut y = vec![0; self.input_y.len()];
+            for (lane, (x_value, y_value)) in chunk.iter().enumerate() {
+                for (bit, word) in x.iter_mut().enumerate() {
+                    *word |= (x_value >> bit & 1) << lane;
+                }
+                for (bit, word) in y.iter_mut().enumerate() {
+                    *word |= (y_value >> bit & 1) << lane;
+                }
+            }
+
+            let z = self.simulate(&x, &y);
+            sums.extend((0..chunk.len()).map(|lane| {
+                z.iter()
+                    .enumerate()
+                    .map(|(bit, word)| (word >> lane & 1) << bit)
+                    .sum::<u64>()
+            }));
+        }
+        sums
+    }
+
+    pub fn add(&self, x: u64, y: u64) -> u64 {
+        self.add_all(&[(x, y)])[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2 bit ripple-carry adder, with the gates listed out of order.
+    fn two_bit_adder() -> Circuit {
+        Circuit::from_lines([
+            "",
+            "hc01 OR pr01 -> z02",
+            "hs01 XOR hc00 -> z01",
+            "x00 XOR y00 -> z00",
+            "hs01 AND hc00 -> pr01",
+            "x00 AND y00 -> hc00",
+            "x01 XOR y01 -> hs01",
+            "x01 AND y01 -> hc01",
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn adds_in_parallel() {
+        let compiled = CompiledCircuit::new(&two_bit_adder()).unwrap();
+        assert_eq!(compiled.depth(), 3);
+
+        // Every pair of 2 bit numbers, more than fits in one pass.
+        let pairs: Vec<(u64, u64)> = (0..4)
+            .flat_map(|x| (0..4).map(move |y| (x, y)))
+            .cycle()
+            .take(100)
+            .collect();
+        let sums = compiled.add_all(&pairs);
+        assert_eq!(sums.len(), 100);
+        for ((x, y), sum) in pairs.iter().zip(sums) {
+            assert_eq!(x + y, sum);
+        }
+        assert_eq!(compiled.add(3, 3), 6);
+    }
+
+    #[test]
+    fn detects_loops() {
+        let mut circuit = two_bit_adder();
+        // Driving hc00 with the OR gate makes the carry feed back into itself.
+        let (z02, hc00) = (circuit.wire_id("z02"), circuit.wire_id("hc00"));
+        circuit.swap_wires(z02.unwrap(), hc00.unwrap());
+        assert_eq!(
+            CompiledCircuit::new(&circuit).err(),
+            Some(CompileError::CombinationalLoop(
+                ["hc00", "pr01", "z01"].map(String::from).to_vec()
+            ))
+        );
+    }
+}
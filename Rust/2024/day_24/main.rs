@@ -1,12 +1,12 @@
 use std::io;
 
-mod operations;
-use circuit::CircuitBuilder;
-use operations::Operation;
-use operations::OperationResult;
-
+mod adder_verifier;
+use adder_verifier::{AdderError, AdderVerifier};
 mod circuit;
-use circuit::{Circuit, CircuitComponent};
+use circuit::{Circuit, CircuitParseError};
+mod compiled_circuit;
+mod random;
+use random::Random;
 
 mod reader;
 use reader::get_lines;
@@ -15,6 +15,8 @@ mod testing_debug;
 #[derive(Debug)]
 enum AdventError {
     IoError(io::Error),
+    ParseError(CircuitParseError),
+    AdderError(AdderError),
 }
 
 impl From<io::Error> for AdventError {
@@ -23,170 +25,73 @@ impl From<io::Error> for AdventError {
     }
 }
 
-fn get_circuit(path: &str) -> Result<(Circuit, Vec<(char, usize, bool)>), AdventError> {
-    let mut lines = get_lines(path)?.into_iter();
-    let mut inputs = Vec::new();
-
-    while let Some(line) = lines.next() {
-        let mut cache = ['_'; 6];
-        if line.is_empty() {
-            break;
-        }
-        for (index, char) in line.chars().enumerate() {
-            if char == ' ' && index == 0 {
-                break;
-            }
-            cache[index] = char;
-        }
-        let mut input_id = String::new();
-        input_id.push(cache[1]);
-        input_id.push(cache[2]);
-        println!("{:?}", cache);
-        inputs.push((
-            cache[0],
-            input_id.parse::<usize>().expect(&format!(
-                "Unexpected input id: {}. Only numbers are allowed.",
-                input_id
-            )),
-            cache[5] == '1',
-        ));
+impl From<CircuitParseError> for AdventError {
+    fn from(err: CircuitParseError) -> Self {
+        Self::ParseError(err)
     }
+}
 
-    let mut circuit_builder = CircuitBuilder::new();
-    while let Some(line) = lines.next() {
-        let mut operation_state = Operation::None;
-        let mut strings: [String; 5] = [const { String::new() }; 5];
-        let mut strings_index = 0;
-        for char in line.chars() {
-            let (next_operation, potential_result) = operation_state.next(char);
-            operation_state = next_operation;
-            if let Some(result) = potential_result {
-                if let OperationResult::String(str) = result {
-                    strings[strings_index] = str;
-                    strings_index += 1;
-                }
-            }
-        }
-        let (next_operation, result) = operation_state.collect_operation();
-        operation_state = next_operation;
-        if let OperationResult::String(str) = result {
-            strings[strings_index] = str;
-            strings_index += 1;
-        }
-
-        //println!("Added gate: \n{:?}", strings);
-        circuit_builder.add_gate(
-            strings[0].clone(),
-            strings[1].clone(),
-            strings[2].clone(),
-            strings[4].clone(),
-        );
+impl From<AdderError> for AdventError {
+    fn from(err: AdderError) -> Self {
+        Self::AdderError(err)
     }
+}
 
-    Ok((circuit_builder.assemble(), inputs))
+fn get_circuit(path: &str) -> Result<Circuit, AdventError> {
+    Ok(Circuit::from_lines(get_lines(path)?)?)
 }
 
-fn calculate(path: &str) -> Result<String, AdventError> {
-    let (mut circuit, inputs) = get_circuit(path)?;
+/// Part two only ever needs 4 swaps.
+const MAX_SWAPS: usize = 4;
+/// Random additions each proposed fix must get right.
+const ADDITION_TESTS: usize = 1000;
+const FIXED_PATH: &str = "fixed_data.txt";
 
-    //println!("Circuit: \n{:?}", circuit);
+fn calculate(path: &str) -> Result<String, AdventError> {
+    let circuit = get_circuit(path)?;
 
-    for (modifier, index, powered) in &inputs {
-        circuit.set_input(*modifier, *index, *powered);
+    let verifier = AdderVerifier::new(&circuit)?;
+    for deviation in verifier.deviations() {
+        println!("{deviation}");
     }
-
-    let out = circuit.output();
-
-    compare_binary_numbers(inputs.clone(), out);
-    circuit.fix();
-
-    for (modifier, index, powered) in &inputs {
-        circuit.set_input(*modifier, *index, *powered);
+    let swaps = verifier.propose_swaps(MAX_SWAPS, &mut Random::new(24), ADDITION_TESTS)?;
+    let mut fixed = circuit.clone();
+    for (wire_1, wire_2) in &swaps {
+        println!("Swap: {wire_1} with {wire_2}");
+        let id = |name: &str| {
+            circuit
+                .wire_id(name)
+                .expect("Swaps are between existing wires")
+        };
+        fixed.swap_wires(id(wire_1), id(wire_2));
     }
-
-    circuit.fix();
-    //circuit.print_info();
-    //circuit.fix_full_adders();
-    let out = circuit.output();
-
-    compare_binary_numbers(inputs, out);
-
-    todo!();
+    confirm_fix(&fixed)?;
+
+    let mut wires: Vec<String> = swaps
+        .into_iter()
+        .flat_map(|(wire_1, wire_2)| [wire_1, wire_2])
+        .collect();
+    wires.sort();
+    Ok(wires.join(","))
 }
 
-fn compare_binary_numbers(source: Vec<(char, usize, bool)>, result: Vec<bool>) {
-    let result_string = {
-        let mut result_string = String::new();
-        for bit in result.iter().rev() {
-            //   result_string.push(if *bit { '1' } else { '0' });
+/// Saves the fixed circuit and checks that reading it back gives a working adder.
+fn confirm_fix(fixed: &Circuit) -> Result<(), AdventError> {
+    fixed.save(FIXED_PATH)?;
+    let reloaded = get_circuit(FIXED_PATH)?;
+    let verifier = AdderVerifier::new(&reloaded)?;
+    let failing = verifier.failing_addition(&reloaded, &mut Random::new(42), ADDITION_TESTS);
+    match (verifier.deviations().len(), failing) {
+        (0, None) => {
+            println!("Saved the fixed circuit to {FIXED_PATH}, it adds correctly.");
+            Ok(())
         }
-
-        for i in (0..64).rev() {
-            if i < result.len() {
-                result_string.push(if result[i] { '1' } else { '0' });
-            } else {
-                result_string.push('0');
-            }
-        }
-        result_string
-    };
-
-    let expected_string = {
-        let mut iter = source.iter();
-        let mut result_string = String::new();
-        let mut expected_number = {
-            let mut x = 0;
-            let mut binary_exponent = 1;
-            for _ in 0..source.len() / 2 {
-                if iter.next().unwrap().2 {
-                    x += binary_exponent;
-                }
-                binary_exponent *= 2;
-            }
-
-            let mut y = 0;
-            binary_exponent = 1;
-            for (_, _, bit) in iter {
-                if *bit {
-                    y += binary_exponent;
-                }
-                binary_exponent *= 2;
-            }
-
-            x + y
-        };
-
-        let mut binary_exponent = u64::MAX;
-        while binary_exponent > 0 {
-            if expected_number > binary_exponent {
-                expected_number -= binary_exponent;
-                result_string.push('1');
-            } else {
-                result_string.push('0');
-            }
-            binary_exponent /= 2;
+        (deviations, failing) => Err(AdderError::StillBroken {
+            deviations,
+            failing,
         }
-        result_string
-    };
-
-    let difference_string = {
-        let (mut expected_chars, mut result_chars) =
-            (expected_string.chars(), result_string.chars());
-        let mut result = String::new();
-        for _ in 0..64 {
-            if expected_chars.next().unwrap() != result_chars.next().unwrap() {
-                result.push('X');
-            } else {
-                result.push(' ');
-            }
-        }
-        result
-    };
-
-    println!(
-        "{}\n{}\n{}\n{}",
-        difference_string, expected_string, result_string, difference_string
-    );
+        .into()),
+    }
 }
 
 // vkq z11, mmk z24, qdq pvb, hqh z38
@@ -217,7 +122,19 @@ fn compare_binary_numbers(source: Vec<(char, usize, bool)>, result: Vec<bool>) {
 
 */
 
+/// Saves the circuit as a Graphviz graph and a Verilog netlist, to inspect the wiring of the adder.
+fn export_circuit(path: &str) -> Result<(), AdventError> {
+    let circuit = get_circuit(path)?;
+    circuit.save_dot("circuit.dot")?;
+    circuit.save_verilog("circuit.v", "adder")?;
+    Ok(())
+}
+
 fn main() {
+    match export_circuit("data.txt") {
+        Err(err) => println!("An error occured: {err:?}"),
+        Ok(()) => println!("Saved circuit.dot and circuit.v"),
+    }
     match calculate("data.txt") {
         Err(err) => println!("An error occured: {err:?}"),
         Ok(value) => println!("Result is: {}", value),
@@ -291,4 +208,28 @@ Challenge part 2:
 // There should be exactly 8 wires in the full adders which connect to the wrong wire. Might be
 // best to go the route of building full adders anyway.
 
+Update: To see where the adder is miswired, main saves the circuit as circuit.dot for Graphviz
+and as a Verilog netlist in circuit.v. In the graph the inputs are lined up on the left and the
+outputs on the right, so a output going to the wrong bit is easy to spot.
+
+Update: fix and fix_FullAdders are replaced by the adder_verifier module, which works for any
+width instead of the quirks of my input. Every gate gets a role in the full adder from its inputs
+alone, since swapping outputs never changes those: x XOR y, x AND y, sum, propagate or carry out.
+Each role expects its output to feed specific gates, or to be a specific z wire, and the gates
+that don't are printed. The outputs of those gates are the suspects. Pairs of suspects that fix
+both gates when swapped are combined, fewest swaps first, until the circuit matches the template
+and gets random additions right.
+
+Update: Checking additions went through the circuit one component at a time. compiled_circuit
+now sorts the gates by level once and reports any loop a swap creates. Each wire holds a u64
+with one addition per bit, so a single pass over the gates runs 64 additions. 100 000 random
+additions on my input went from about 400ms to 12ms, most of which is now spent moving the bits
+of the numbers in and out of that layout.
+
+Update: Besides AND, OR and XOR the builder knows NOT, NAND, NOR and XNOR gates, written as
+"NOT a -> b" for the single input, and constant sources written as "1 -> a". A circuit can be
+written back out in the same format as the input, with the current input values and any swapped
+wires. The fixed circuit is saved to fixed_data.txt, then read back and verified again to make sure
+the swaps really fix it, failing with a error if they don't.
+
 */
@@ -1,52 +1,133 @@
-pub struct CircularStack<T: Default + Copy> {
-    values: [T; 4],
+use std::ops::Index;
+
+/// Ring buffer holding the last N values pushed to it, overwriting the oldest value when full.
+///
+/// The buffer always holds N values, starting out filled with a default value. get_stack and
+/// get_queue return the whole buffer including those, while len, the iterators and indexing only
+/// cover values that have actually been pushed.
+#[derive(Clone, Debug)]
+pub struct CircularStack<T: Default + Copy, const N: usize> {
+    values: [T; N],
     index: usize,
+    len: usize,
 }
 
-impl<T: Default + Copy> CircularStack<T> {
-    pub fn new() -> CircularStack<T> {
-        CircularStack {
-            values: [T::default(); 4],
-            index: 0,
-        }
+impl<T: Default + Copy, const N: usize> Default for CircularStack<T, N> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn with_default(value: T) -> CircularStack<T> {
+impl<T: Default + Copy, const N: usize> CircularStack<T, N> {
+    pub fn new() -> CircularStack<T, N> {
+        Self::with_default(T::default())
+    }
+
+    pub fn with_default(value: T) -> CircularStack<T, N> {
+        const { assert!(N > 0, "CircularStack needs a capacity of at least 1") };
         CircularStack {
-            values: [value; 4],
+            values: [value; N],
             index: 0,
+            len: 0,
         }
     }
 
     pub fn push(&mut self, value: T) {
         self.values[self.index] = value;
-        self.index = (self.index + 1) % 4;
+        self.index = (self.index + 1) % N;
+        self.len = (self.len + 1).min(N);
     }
 
+    /// Returns the most recently pushed value.
     pub fn peek(&self) -> T {
-        self.values[(self.index + 3) % 4]
+        self.values[(self.index + N - 1) % N]
     }
 
-    pub fn get_stack(&self) -> [T; 4] {
-        let mut result = [T::default(); 4];
-        for (i, item) in result.iter_mut().enumerate() {
-            *item = self.values[3 - ((self.index + i) % 4)];
-        }
+    /// Number of values pushed, up to the capacity N.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true once N values have been pushed, after which every push drops the oldest one.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The whole buffer, newest value first.
+    pub fn get_stack(&self) -> [T; N] {
+        let mut result = self.get_queue();
+        result.reverse();
         result
     }
 
-    pub fn get_queue(&self) -> [T; 4] {
-        let mut result = [T::default(); 4];
+    /// The whole buffer, oldest value first.
+    pub fn get_queue(&self) -> [T; N] {
+        let mut result = [T::default(); N];
         for (i, item) in result.iter_mut().enumerate() {
-            *item = self.values[(self.index + i) % 4];
+            *item = self.values[(self.index + i) % N];
         }
         result
     }
+
+    /// Iterates over the pushed values, oldest first. Use rev for newest first.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = T> + ExactSizeIterator + '_ {
+        (0..self.len).map(|i| self[i])
+    }
+
+    /// Iterates over the pushed values, newest first.
+    pub fn iter_stack(&self) -> impl Iterator<Item = T> + '_ {
+        self.iter().rev()
+    }
+
+    /// Packs the whole buffer, oldest value first, into one integer using the lowest bits of each
+    /// value. Much cheaper to hash than the array when tracking every rolling window of a sequence.
+    ///
+    /// Values that only differ above the lowest bits get the same key, so bits has to be wide
+    /// enough for the range of values used. Panics if bits is 0 or if N values of that many bits
+    /// don't fit in 64.
+    pub fn window_key(&self, bits: u32) -> u64
+    where
+        T: Into<i64>,
+    {
+        assert!(
+            bits > 0 && bits as usize * N <= 64,
+            "Can't pack {N} values of {bits} bits into a u64"
+        );
+        let mask = u64::MAX >> (64 - bits);
+        self.get_queue()
+            .into_iter()
+            // A single 64 bit value would shift everything out, which << doesn't allow.
+            .fold(0, |key, value| {
+                key.checked_shl(bits).unwrap_or(0) | (value.into() as u64 & mask)
+            })
+    }
+}
+
+impl<T: Default + Copy, const N: usize> Index<usize> for CircularStack<T, N> {
+    type Output = T;
+
+    /// Index 0 is the oldest pushed value. Panics if fewer than index + 1 values are stored.
+    fn index(&self, index: usize) -> &T {
+        assert!(
+            index < self.len,
+            "Index {index} out of range for length {}",
+            self.len
+        );
+        &self.values[(self.index + N - self.len + index) % N]
+    }
 }
 
 #[test]
 fn test_circular_stack_push() {
-    let mut cs: CircularStack<i8> = CircularStack::new();
+    let mut cs: CircularStack<i8, 4> = CircularStack::new();
     let sequence = [1, 2, 3, 4, 5, 6];
     for value in sequence {
         cs.push(value);
@@ -56,28 +137,68 @@ fn test_circular_stack_push() {
 
 #[test]
 fn test_circular_stack_get_stack() {
-    let cs: CircularStack<i8> = CircularStack {
+    let cs: CircularStack<i8, 4> = CircularStack {
         values: [6, 7, 8, 9],
         index: 2,
+        len: 4,
     };
     assert_eq!(cs.get_stack(), [7, 6, 9, 8])
 }
 
 #[test]
 fn test_circular_stack_get_queue() {
-    let cs: CircularStack<i8> = CircularStack {
+    let cs: CircularStack<i8, 4> = CircularStack {
         values: [6, 7, 8, 9],
         index: 2,
+        len: 4,
     };
     assert_eq!(cs.get_queue(), [8, 9, 6, 7])
 }
 
 #[test]
 fn test_circular_stack_peek() {
-    let mut cs: CircularStack<i8> = CircularStack::new();
+    let mut cs: CircularStack<i8, 4> = CircularStack::new();
     let sequence = [1, 2, 3, 4, 5, 6];
     for value in sequence {
         cs.push(value);
         assert_eq!(cs.peek(), value)
     }
 }
+
+#[test]
+fn test_circular_stack_iter_and_index() {
+    let mut cs: CircularStack<u8, 3> = CircularStack::new();
+    cs.push(1);
+    cs.push(2);
+    assert_eq!((cs.len(), cs.is_full()), (2, false));
+    assert_eq!(cs.iter().collect::<Vec<_>>(), vec![1, 2]);
+    assert_eq!(cs.get_queue(), [0, 1, 2]);
+
+    for value in [3, 4, 5] {
+        cs.push(value);
+    }
+    assert!(cs.is_full());
+    assert_eq!(cs.iter().collect::<Vec<_>>(), vec![3, 4, 5]);
+    assert_eq!(cs.iter_stack().collect::<Vec<_>>(), vec![5, 4, 3]);
+    assert_eq!((cs[0], cs[2]), (3, 5));
+}
+
+#[test]
+fn test_circular_stack_window_key() {
+    let mut cs: CircularStack<i8, 4> = CircularStack::new();
+    for value in [-2, 0, 1, -1] {
+        cs.push(value);
+    }
+    assert_eq!(cs.window_key(5), 0b11110_00000_00001_11111);
+
+    let mut other: CircularStack<i8, 4> = CircularStack::new();
+    for value in [-2, 0, 1, 2] {
+        other.push(value);
+    }
+    assert_ne!(cs.window_key(5), other.window_key(5));
+
+    // One value filling all 64 bits.
+    let mut single: CircularStack<i64, 1> = CircularStack::new();
+    single.push(-1);
+    assert_eq!(single.window_key(64), u64::MAX);
+}
@@ -0,0 +1,197 @@
+use crate::bitset::BitSet;
+
+/// Matrix over GF(2) with each row stored as a bit set.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Gf2Matrix {
+    columns: usize,
+    rows: Vec<BitSet>,
+}
+
+impl Gf2Matrix {
+    /// Creates a rows * columns matrix of zeroes.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Gf2Matrix {
+            columns,
+            rows: vec![BitSet::new(columns); rows],
+        }
+    }
+
+    /// Creates a matrix with the vectors as its columns, which is how puzzles like toggling
+    /// lights with buttons are usually described. Panics if the lengths differ.
+    pub fn from_columns(rows: usize, columns: &[BitSet]) -> Self {
+        let mut matrix = Gf2Matrix::new(rows, columns.len());
+        for (column, vector) in columns.iter().enumerate() {
+            assert_eq!(vector.len(), rows, "Column length must match the rows");
+            for row in vector.ones() {
+                matrix.set(row, column, true);
+            }
+        }
+        matrix
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.rows[row].get(column)
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, value: bool) {
+        self.rows[row].set(column, value)
+    }
+
+    /// Brings the matrix into reduced row echelon form, only pivoting on the first pivot_limit
+    /// columns. Returns the pivot column of each non-zero row.
+    fn reduce(&mut self, pivot_limit: usize) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        for column in 0..pivot_limit {
+            let row = pivots.len();
+            let Some(pivot_row) = (row..self.rows.len()).find(|r| self.rows[*r].get(column)) else {
+                continue;
+            };
+            self.rows.swap(row, pivot_row);
+
+            let pivot = self.rows[row].clone();
+            for (other, other_row) in self.rows.iter_mut().enumerate() {
+                if other != row && other_row.get(column) {
+                    *other_row ^= &pivot;
+                }
+            }
+
+            pivots.push(column);
+            if pivots.len() == self.rows.len() {
+                break;
+            }
+        }
+        pivots
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().reduce(self.columns).len()
+    }
+
+    /// Returns a basis for the vectors x where A x = 0.
+    pub fn null_space(&self) -> Vec<BitSet> {
+        match self.solve(&BitSet::new(self.rows())) {
+            Some(space) => space.null_space,
+            None => unreachable!("A x = 0 always has the solution x = 0"),
+        }
+    }
+
+    /// Solves A x = b, returning None if there is no solution. Panics if b doesn't have one bit
+    /// per row.
+    pub fn solve(&self, b: &BitSet) -> Option<Gf2SolutionSpace> {
+        assert_eq!(b.len(), self.rows(), "Right hand side must match the rows");
+
+        // Augment the matrix with b as an extra column.
+        let mut augmented = Gf2Matrix::new(self.rows(), self.columns + 1);
+        for (row, values) in self.rows.iter().enumerate() {
+            for column in values.ones() {
+                augmented.set(row, column, true);
+            }
+            augmented.set(row, self.columns, b.get(row));
+        }
+
+        let pivots = augmented.reduce(self.columns);
+        if augmented.rows[pivots.len()..]
+            .iter()
+            .any(|row| row.get(self.columns))
+        {
+            return None;
+        }
+
+        // With every free variable at 0, each pivot variable equals its row's right hand side.
+        let mut particular = BitSet::new(self.columns);
+        for (row, pivot) in pivots.iter().enumerate() {
+            particular.set(*pivot, augmented.get(row, self.columns));
+        }
+
+        // Setting a single free variable to 1 gives one basis vector of the null space.
+        let null_space = (0..self.columns)
+            .filter(|column| !pivots.contains(column))
+            .map(|free| {
+                let mut vector = BitSet::new(self.columns);
+                vector.set(free, true);
+                for (row, pivot) in pivots.iter().enumerate() {
+                    vector.set(*pivot, augmented.get(row, free));
+                }
+                vector
+            })
+            .collect();
+
+        Some(Gf2SolutionSpace {
+            particular,
+            null_space,
+        })
+    }
+}
+
+/// Every solution of A x = b over GF(2): the particular solution xor any combination of the null
+/// space basis vectors.
+#[derive(Clone, Debug)]
+pub struct Gf2SolutionSpace {
+    pub particular: BitSet,
+    pub null_space: Vec<BitSet>,
+}
+
+impl Gf2SolutionSpace {
+    /// Returns the solution with the fewest set bits.
+    ///
+    /// Walks through all 2^n combinations of the null space basis in Gray code order, so each
+    /// step only needs a single xor. Panics if the null space has 64 or more dimensions.
+    pub fn min_weight(&self) -> BitSet {
+        assert!(self.null_space.len() < 64, "Null space too large to search");
+
+        let mut current = self.particular.clone();
+        let mut best = current.clone();
+        for step in 1..1u64 << self.null_space.len() {
+            current ^= &self.null_space[step.trailing_zeros() as usize];
+            if current.count_ones() < best.count_ones() {
+                best = current.clone();
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_and_null_space() {
+        // Buttons (3) (1,3) (2) (2,3) (0,2) (0,1) toggling 4 lights, aiming for [.##.].
+        let buttons: Vec<BitSet> = [&[3][..], &[1, 3], &[2], &[2, 3], &[0, 2], &[0, 1]]
+            .iter()
+            .map(|ones| BitSet::from_ones(4, ones))
+            .collect();
+        let matrix = Gf2Matrix::from_columns(4, &buttons);
+        assert_eq!(matrix.rank(), 4);
+        assert_eq!(matrix.null_space().len(), 2);
+
+        let target = BitSet::from_ones(4, &[1, 2]);
+        let space = matrix.solve(&target).unwrap();
+        let presses = space.min_weight();
+        assert_eq!(presses.count_ones(), 2);
+
+        let mut lights = BitSet::new(4);
+        for button in presses.ones() {
+            lights ^= &buttons[button];
+        }
+        assert_eq!(lights, target);
+    }
+
+    #[test]
+    fn solve_inconsistent() {
+        // Both buttons toggle lights 0 and 1 together, so they can never differ.
+        let buttons = [BitSet::from_ones(2, &[0, 1]), BitSet::from_ones(2, &[0, 1])];
+        let matrix = Gf2Matrix::from_columns(2, &buttons);
+        assert!(matrix.solve(&BitSet::from_ones(2, &[0])).is_none());
+        assert_eq!(matrix.null_space(), vec![BitSet::from_ones(2, &[0, 1])]);
+    }
+}
@@ -0,0 +1,518 @@
+use std::fmt::Display;
+
+use crate::bitset::{BitMatrix, BitSet};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph has a cycle where a acyclic graph was required. Holds the nodes of the cycle in
+    /// the order they are connected, the last node has a edge back to the first.
+    Cycle(Vec<usize>),
+    /// More must visit nodes were requested than the path counting supports.
+    TooManyMustVisit(usize),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Cycle(nodes) => write!(f, "Graph contains the cycle {:?}", nodes),
+            GraphError::TooManyMustVisit(count) => write!(
+                f,
+                "{} must visit nodes requested but at most {} are supported",
+                count, MAX_MUST_VISIT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Path counting keeps one counter per combination of visited must visit nodes, so the amount of
+/// counters per node grows as 2^n.
+const MAX_MUST_VISIT: usize = 16;
+
+/// Adjacency list graph over dense node ids, such as the ones handed out by the interner.
+///
+/// Nodes are created on demand when an edge refers to them, so the graph always holds the nodes
+/// 0..node_count().
+#[derive(Debug, Default, Clone)]
+pub struct Graph {
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_nodes(node_count: usize) -> Self {
+        Graph {
+            edges: vec![Vec::new(); node_count],
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// Makes sure the node exists, adding any missing nodes below it as well.
+    pub fn add_node(&mut self, node: usize) {
+        if node >= self.edges.len() {
+            self.edges.resize(node + 1, Vec::new());
+        }
+    }
+
+    /// Adds a directed edge from one node to another.
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.add_node(from.max(to));
+        self.edges[from].push(to);
+    }
+
+    /// Adds edges in both directions, for undirected graphs.
+    pub fn connect(&mut self, a: usize, b: usize) {
+        self.add_edge(a, b);
+        self.add_edge(b, a);
+    }
+
+    /// Returns the nodes the node has edges to.
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        match self.edges.get(node) {
+            Some(neighbours) => neighbours,
+            None => &[],
+        }
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.neighbours(from).contains(&to)
+    }
+
+    pub fn nodes(&self) -> std::ops::Range<usize> {
+        0..self.edges.len()
+    }
+
+    /// Orders all nodes so that every edge points from a earlier node to a later one.
+    pub fn topological_order(&self) -> Result<Vec<usize>, GraphError> {
+        self.depth_first_order(self.nodes(), |_| true)
+    }
+
+    /// Orders the provided nodes using only the edges between them. Edges to nodes outside of
+    /// the list are ignored, which lets a subset be ordered even if the full graph has cycles.
+    pub fn topological_order_within(&self, nodes: &[usize]) -> Result<Vec<usize>, GraphError> {
+        let mut included = vec![false; self.node_count()];
+        for node in nodes {
+            self.assert_node_exists(*node);
+            included[*node] = true;
+        }
+        self.depth_first_order(nodes.iter().copied(), |node| included[node])
+    }
+
+    /// Returns a cycle in the graph if one exists.
+    pub fn find_cycle(&self) -> Option<Vec<usize>> {
+        match self.topological_order() {
+            Err(GraphError::Cycle(cycle)) => Some(cycle),
+            _ => None,
+        }
+    }
+
+    /// Counts the paths leading from one node to another.
+    pub fn count_paths(&self, from: usize, to: usize) -> Result<u64, GraphError> {
+        self.count_paths_through(from, to, &[])
+    }
+
+    /// Counts the paths leading from one node to another which pass all the must visit nodes, in
+    /// any order.
+    ///
+    /// Only the part of the graph reachable from the start has to be acyclic. Every node keeps a
+    /// count per combination of must visit nodes passed so far, which are added forwards along
+    /// the edges in topological order.
+    pub fn count_paths_through(
+        &self,
+        from: usize,
+        to: usize,
+        must_visit: &[usize],
+    ) -> Result<u64, GraphError> {
+        if must_visit.len() > MAX_MUST_VISIT {
+            return Err(GraphError::TooManyMustVisit(must_visit.len()));
+        }
+        self.assert_node_exists(from.max(to));
+
+        let order = self.depth_first_order([from].into_iter(), |_| true)?;
+        let mask_of = |node: usize| -> usize {
+            must_visit
+                .iter()
+                .enumerate()
+                .filter(|(_, must)| **must == node)
+                .fold(0, |mask, (index, _)| mask | (1 << index))
+        };
+        let combinations = 1 << must_visit.len();
+
+        let mut counts: Vec<Vec<u64>> = vec![Vec::new(); self.node_count()];
+        counts[from] = vec![0; combinations];
+        counts[from][mask_of(from)] = 1;
+
+        for node in order {
+            if node == to || counts[node].is_empty() {
+                continue; // Paths end at the goal, they can't pass through it.
+            }
+            let current = counts[node].clone();
+            for neighbour in self.neighbours(node) {
+                let neighbour_mask = mask_of(*neighbour);
+                let neighbour_counts = &mut counts[*neighbour];
+                if neighbour_counts.is_empty() {
+                    *neighbour_counts = vec![0; combinations];
+                }
+                for (mask, count) in current.iter().enumerate() {
+                    neighbour_counts[mask | neighbour_mask] += count;
+                }
+            }
+        }
+
+        Ok(counts[to].last().copied().unwrap_or(0))
+    }
+
+    /// Depth first search from the roots through the included nodes, returning them in reverse
+    /// post order. Running into a node that is still being searched means there is a cycle, which
+    /// can be read straight from the search stack.
+    fn depth_first_order<I, F>(&self, roots: I, include: F) -> Result<Vec<usize>, GraphError>
+    where
+        I: Iterator<Item = usize>,
+        F: Fn(usize) -> bool,
+    {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        let mut states = vec![State::Unvisited; self.node_count()];
+        let mut order = Vec::with_capacity(self.node_count());
+
+        for root in roots {
+            if states[root] != State::Unvisited {
+                continue;
+            }
+            // Each stack entry is a node together with the index of the next edge to follow.
+            let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+            states[root] = State::InProgress;
+
+            while let Some((node, edge_index)) = stack.last_mut() {
+                let node = *node;
+                let Some(neighbour) = self.neighbours(node).get(*edge_index).copied() else {
+                    states[node] = State::Done;
+                    order.push(node);
+                    stack.pop();
+                    continue;
+                };
+                *edge_index += 1;
+
+                if !include(neighbour) {
+                    continue;
+                }
+                match states[neighbour] {
+                    State::Done => {}
+                    State::Unvisited => {
+                        states[neighbour] = State::InProgress;
+                        stack.push((neighbour, 0));
+                    }
+                    State::InProgress => {
+                        let start = stack
+                            .iter()
+                            .position(|(stack_node, _)| *stack_node == neighbour)
+                            .expect("nodes in progress are always on the stack");
+                        let cycle = stack[start..].iter().map(|(node, _)| *node).collect();
+                        return Err(GraphError::Cycle(cycle));
+                    }
+                }
+            }
+        }
+
+        order.reverse();
+        Ok(order)
+    }
+
+    /// Lists every group of k nodes where all nodes are connected to each other.
+    ///
+    /// The edges are treated as undirected. Each clique is listed once, sorted by node id.
+    pub fn k_cliques(&self, k: usize) -> Vec<Vec<usize>> {
+        let adjacency = self.undirected_adjacency();
+        let mut cliques = Vec::new();
+        if k == 0 {
+            return cliques;
+        }
+
+        let mut clique = Vec::with_capacity(k);
+        let all = BitSet::full(self.node_count());
+        extend_clique(&mut clique, &all, k, &adjacency, &mut cliques);
+        cliques
+    }
+
+    /// Finds one of the largest groups of nodes where all nodes are connected to each other.
+    ///
+    /// The edges are treated as undirected. Uses Bron–Kerbosch with pivoting, where the pivot is
+    /// the node with the most neighbours among the candidates so that as few branches as possible
+    /// are explored. The result is sorted by node id.
+    pub fn maximum_clique(&self) -> Vec<usize> {
+        let adjacency = self.undirected_adjacency();
+        let mut best = Vec::new();
+        bron_kerbosch(
+            &mut Vec::new(),
+            BitSet::full(self.node_count()),
+            BitSet::new(self.node_count()),
+            &adjacency,
+            &mut best,
+        );
+        best.sort();
+        best
+    }
+
+    /// Returns the edges as a node_count * node_count matrix, where row a has bit b set if there
+    /// is a edge from a to b. Checking a whole set of nodes against a row is then a single and.
+    pub fn adjacency_matrix(&self) -> BitMatrix {
+        let mut matrix = BitMatrix::new(self.node_count(), self.node_count());
+        for node in self.nodes() {
+            for neighbour in self.neighbours(node) {
+                matrix.set(node, *neighbour, true);
+            }
+        }
+        matrix
+    }
+
+    /// Adjacency as one bit set per node, with every edge added in both directions.
+    fn undirected_adjacency(&self) -> Vec<BitSet> {
+        let mut adjacency = vec![BitSet::new(self.node_count()); self.node_count()];
+        for node in self.nodes() {
+            for neighbour in self.neighbours(node) {
+                if *neighbour != node {
+                    adjacency[node].insert(*neighbour);
+                    adjacency[*neighbour].insert(node);
+                }
+            }
+        }
+        adjacency
+    }
+
+    fn assert_node_exists(&self, node: usize) {
+        assert!(
+            node < self.node_count(),
+            "Node {} does not exist in a graph with {} nodes!",
+            node,
+            self.node_count()
+        );
+    }
+}
+
+/// Adds one candidate at a time to the clique, only looking at candidates with a higher id than
+/// the last one added so that every clique is only found once.
+fn extend_clique(
+    clique: &mut Vec<usize>,
+    candidates: &BitSet,
+    k: usize,
+    adjacency: &[BitSet],
+    cliques: &mut Vec<Vec<usize>>,
+) {
+    if clique.len() == k {
+        cliques.push(clique.clone());
+        return;
+    }
+    if clique.len() + candidates.count_ones() < k {
+        return;
+    }
+
+    for node in candidates.ones() {
+        let mut next_candidates = candidates.intersection(&adjacency[node]);
+        next_candidates.remove_up_to(node);
+
+        clique.push(node);
+        extend_clique(clique, &next_candidates, k, adjacency, cliques);
+        clique.pop();
+    }
+}
+
+/// clique: The nodes in the current clique.
+/// candidates: Nodes connected to the whole clique that may still be added.
+/// excluded: Nodes connected to the whole clique which have already been tried.
+fn bron_kerbosch(
+    clique: &mut Vec<usize>,
+    mut candidates: BitSet,
+    mut excluded: BitSet,
+    adjacency: &[BitSet],
+    best: &mut Vec<usize>,
+) {
+    if candidates.is_clear() {
+        if excluded.is_clear() && clique.len() > best.len() {
+            *best = clique.clone();
+        }
+        return;
+    }
+    if clique.len() + candidates.count_ones() <= best.len() {
+        return; // Can't beat the best clique found so far.
+    }
+
+    let pivot = candidates
+        .ones()
+        .chain(excluded.ones())
+        .max_by_key(|node| candidates.intersection(&adjacency[*node]).count_ones())
+        .expect("candidates is not empty");
+
+    for node in candidates.difference(&adjacency[pivot]).ones() {
+        clique.push(node);
+        bron_kerbosch(
+            clique,
+            candidates.intersection(&adjacency[node]),
+            excluded.intersection(&adjacency[node]),
+            adjacency,
+            best,
+        );
+        clique.pop();
+        candidates.remove(node);
+        excluded.insert(node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Graph, GraphError};
+
+    #[test]
+    fn add_edge_creates_missing_nodes() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 3);
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.neighbours(0), &[3]);
+        assert!(graph.neighbours(3).is_empty());
+        assert!(graph.neighbours(7).is_empty());
+    }
+
+    #[test]
+    fn topological_order_respects_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge(3, 1);
+        graph.add_edge(1, 0);
+        graph.add_edge(2, 0);
+        graph.add_edge(3, 2);
+
+        let order = graph.topological_order().unwrap();
+        let position = |node| order.iter().position(|n| *n == node).unwrap();
+        assert!(position(3) < position(1) && position(3) < position(2));
+        assert!(position(1) < position(0) && position(2) < position(0));
+    }
+
+    #[test]
+    fn adjacency_matrix_matches_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 2);
+        graph.add_edge(2, 1);
+
+        let matrix = graph.adjacency_matrix();
+        assert!(matrix.get(0, 2) && matrix.get(2, 1));
+        assert!(!matrix.get(2, 0));
+        assert_eq!(matrix.count_ones(), 2);
+        assert!(matrix.transitive_closure().get(0, 1));
+    }
+
+    #[test]
+    fn topological_order_reports_cycle() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 3);
+        graph.add_edge(3, 1);
+
+        assert_eq!(
+            graph.topological_order(),
+            Err(GraphError::Cycle(vec![1, 2, 3]))
+        );
+        // Ordering a subset without the back edge works fine.
+        assert_eq!(
+            graph.topological_order_within(&[2, 0, 1]),
+            Ok(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn count_paths_with_must_visit() {
+        // 0 -> 1 -> 3 -> 4
+        // 0 -> 2 -> 3
+        // 1 -> 2
+        let mut graph = Graph::new();
+        for (from, to) in [(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (3, 4)] {
+            graph.add_edge(from, to);
+        }
+
+        assert_eq!(graph.count_paths(0, 4), Ok(3));
+        assert_eq!(graph.count_paths_through(0, 4, &[2]), Ok(2));
+        assert_eq!(graph.count_paths_through(0, 4, &[1, 2]), Ok(1));
+        assert_eq!(graph.count_paths(4, 0), Ok(0));
+    }
+
+    /// Two triangles sharing node 2, with a square 2-3-4-5 where 2, 3 and 4 form a larger clique.
+    fn clique_test_graph() -> Graph {
+        let mut graph = Graph::new();
+        for (a, b) in [
+            (0, 1),
+            (1, 2),
+            (0, 2),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+            (2, 5),
+            (3, 5),
+            (4, 5),
+        ] {
+            graph.connect(a, b);
+        }
+        graph
+    }
+
+    #[test]
+    fn k_cliques_lists_triangles_once() {
+        let triangles = clique_test_graph().k_cliques(3);
+        assert_eq!(
+            triangles,
+            vec![
+                vec![0, 1, 2],
+                vec![2, 3, 4],
+                vec![2, 3, 5],
+                vec![2, 4, 5],
+                vec![3, 4, 5]
+            ]
+        );
+    }
+
+    #[test]
+    fn maximum_clique_finds_largest_group() {
+        assert_eq!(clique_test_graph().maximum_clique(), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn maximum_clique_beyond_one_word() {
+        // A 70 node ring with a clique of 5 placed across the 64 node word boundary.
+        let mut graph = Graph::new();
+        for node in 0..70 {
+            graph.connect(node, (node + 1) % 70);
+        }
+        let clique = [61, 62, 63, 64, 65];
+        for a in clique {
+            for b in clique {
+                if a < b {
+                    graph.connect(a, b);
+                }
+            }
+        }
+        assert_eq!(graph.maximum_clique(), clique.to_vec());
+        assert_eq!(graph.k_cliques(5), vec![clique.to_vec()]);
+    }
+
+    #[test]
+    fn connect_adds_both_directions() {
+        let mut graph = Graph::new();
+        graph.connect(1, 2);
+        assert!(graph.has_edge(1, 2));
+        assert!(graph.has_edge(2, 1));
+        assert_eq!(graph.edge_count(), 2);
+    }
+}
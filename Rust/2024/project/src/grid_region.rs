@@ -0,0 +1,342 @@
+use std::collections::VecDeque;
+
+/// Which surrounding tiles count as neighbours of a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The 4 tiles sharing an edge with the tile.
+    Orthogonal,
+    /// The 8 tiles sharing an edge or a corner with the tile.
+    Surrounding,
+}
+
+const ORTHOGONAL: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+const SURROUNDING: [(isize, isize); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+impl Neighbourhood {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Neighbourhood::Orthogonal => &ORTHOGONAL,
+            Neighbourhood::Surrounding => &SURROUNDING,
+        }
+    }
+}
+
+/// A connected group of tiles.
+///
+/// Positions are (x, y), matching the grid[x][y] layout used by the puzzles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub label: usize,
+    pub area: u64,
+    /// Amount of tile edges facing a tile outside of the region.
+    pub perimeter: u64,
+    /// Amount of straight sides. Counted through the corners since a polygon has as many sides as
+    /// corners.
+    pub sides: u64,
+    pub min: (usize, usize),
+    pub max: (usize, usize),
+}
+
+/// Holds the region label of every tile together with the measurements of each region.
+pub struct RegionMap {
+    labels: Vec<Vec<usize>>,
+    regions: Vec<Region>,
+}
+
+impl RegionMap {
+    pub fn label(&self, x: usize, y: usize) -> usize {
+        self.labels[x][y]
+    }
+
+    pub fn region_at(&self, x: usize, y: usize) -> &Region {
+        &self.regions[self.labels[x][y]]
+    }
+
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+}
+
+/// Splits the top left width * height tiles of the grid into orthogonally connected regions.
+///
+/// same_region decides if two neighbouring tiles belong together. Grids are indexed as
+/// grid[x][y], which lets both fixed size arrays and vectors be used.
+pub fn label_regions<T, R, F>(grid: &[R], width: usize, height: usize, same_region: F) -> RegionMap
+where
+    R: AsRef<[T]>,
+    F: Fn(&T, &T) -> bool,
+{
+    let mut labels: Vec<Vec<Option<usize>>> = vec![vec![None; height]; width];
+    let mut regions = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if labels[x][y].is_some() {
+                continue;
+            }
+
+            let label = regions.len();
+            let tiles = flood_fill(
+                grid,
+                width,
+                height,
+                (x, y),
+                &same_region,
+                |x, y| match labels[x][y] {
+                    Some(_) => false,
+                    None => {
+                        labels[x][y] = Some(label);
+                        true
+                    }
+                },
+            );
+
+            let in_region = |x: isize, y: isize| {
+                x >= 0
+                    && y >= 0
+                    && (x as usize) < width
+                    && (y as usize) < height
+                    && labels[x as usize][y as usize] == Some(label)
+            };
+            regions.push(measure_region(label, &tiles, in_region));
+        }
+    }
+
+    let labels = labels
+        .into_iter()
+        .map(|column| column.into_iter().map(|label| label.unwrap_or(0)).collect())
+        .collect();
+
+    RegionMap { labels, regions }
+}
+
+/// Collects every tile orthogonally connected to start for which same_region holds.
+///
+/// visit is called once for each tile reached and should return false if the tile has already
+/// been handled, which stops the fill from continuing through it.
+fn flood_fill<T, R, F, V>(
+    grid: &[R],
+    width: usize,
+    height: usize,
+    start: (usize, usize),
+    same_region: &F,
+    mut visit: V,
+) -> Vec<(usize, usize)>
+where
+    R: AsRef<[T]>,
+    F: Fn(&T, &T) -> bool,
+    V: FnMut(usize, usize) -> bool,
+{
+    let mut tiles = Vec::new();
+    let mut stack = vec![start];
+    visit(start.0, start.1);
+
+    while let Some((x, y)) = stack.pop() {
+        tiles.push((x, y));
+        let current = &grid[x].as_ref()[y];
+        for (nx, ny) in neighbours(x, y, width, height, Neighbourhood::Orthogonal) {
+            if same_region(current, &grid[nx].as_ref()[ny]) && visit(nx, ny) {
+                stack.push((nx, ny));
+            }
+        }
+    }
+
+    tiles
+}
+
+fn measure_region<F>(label: usize, tiles: &[(usize, usize)], in_region: F) -> Region
+where
+    F: Fn(isize, isize) -> bool,
+{
+    let mut region = Region {
+        label,
+        area: tiles.len() as u64,
+        perimeter: 0,
+        sides: 0,
+        min: (usize::MAX, usize::MAX),
+        max: (0, 0),
+    };
+
+    for (x, y) in tiles {
+        region.min = (region.min.0.min(*x), region.min.1.min(*y));
+        region.max = (region.max.0.max(*x), region.max.1.max(*y));
+
+        let (x, y) = (*x as isize, *y as isize);
+        for (index, (dx, dy)) in ORTHOGONAL.iter().enumerate() {
+            if !in_region(x + dx, y + dy) {
+                region.perimeter += 1;
+            }
+
+            // Each corner of the tile is checked by looking at this direction together with the
+            // next one clockwise.
+            // Outwards corner: Neither side belongs to the region.
+            // Inwards corner: Both sides belong to the region but the diagonal does not.
+            let (cx, cy) = ORTHOGONAL[(index + 1) % 4];
+            let side_a = in_region(x + dx, y + dy);
+            let side_b = in_region(x + cx, y + cy);
+            let diagonal = in_region(x + dx + cx, y + dy + cy);
+            if (!side_a && !side_b) || (side_a && side_b && !diagonal) {
+                region.sides += 1;
+            }
+        }
+    }
+
+    region
+}
+
+/// Returns the positions around (x, y) that are inside of the width * height area.
+pub fn neighbours(
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    neighbourhood: Neighbourhood,
+) -> impl Iterator<Item = (usize, usize)> {
+    neighbourhood.offsets().iter().filter_map(move |(dx, dy)| {
+        let (nx, ny) = (x.checked_add_signed(*dx)?, y.checked_add_signed(*dy)?);
+        (nx < width && ny < height).then_some((nx, ny))
+    })
+}
+
+/// Counts how many neighbours of (x, y) pass the is_present check.
+pub fn count_neighbours<T, R, F>(
+    grid: &[R],
+    width: usize,
+    height: usize,
+    (x, y): (usize, usize),
+    neighbourhood: Neighbourhood,
+    is_present: F,
+) -> usize
+where
+    R: AsRef<[T]>,
+    F: Fn(&T) -> bool,
+{
+    neighbours(x, y, width, height, neighbourhood)
+        .filter(|(nx, ny)| is_present(&grid[*nx].as_ref()[*ny]))
+        .count()
+}
+
+/// Repeatedly removes present tiles until no more tiles are removable, returning the removed
+/// positions in the order they were removed.
+///
+/// can_remove receives a present tile together with its amount of present neighbours. Removed
+/// tiles are replaced by the removed value. Only the neighbours of removed tiles are checked
+/// again, so each tile is only revisited when something around it has changed.
+pub fn peel<T, R, FP, FR>(
+    grid: &mut [R],
+    width: usize,
+    height: usize,
+    neighbourhood: Neighbourhood,
+    is_present: FP,
+    can_remove: FR,
+    removed: T,
+) -> Vec<(usize, usize)>
+where
+    T: Clone,
+    R: AsRef<[T]> + AsMut<[T]>,
+    FP: Fn(&T) -> bool,
+    FR: Fn(&T, usize) -> bool,
+{
+    let mut queued = vec![vec![true; height]; width];
+    let mut queue: VecDeque<(usize, usize)> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .collect();
+    let mut removed_tiles = Vec::new();
+
+    while let Some((x, y)) = queue.pop_front() {
+        queued[x][y] = false;
+        let tile = &grid[x].as_ref()[y];
+        if !is_present(tile) {
+            continue;
+        }
+
+        let present = count_neighbours(grid, width, height, (x, y), neighbourhood, &is_present);
+        if !can_remove(tile, present) {
+            continue;
+        }
+
+        grid[x].as_mut()[y] = removed.clone();
+        removed_tiles.push((x, y));
+        for (nx, ny) in neighbours(x, y, width, height, neighbourhood) {
+            if !queued[nx][ny] {
+                queued[nx][ny] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    removed_tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Converts rows of text into a grid[x][y] of chars.
+    fn grid_from_rows(rows: &[&str]) -> Vec<Vec<char>> {
+        let width = rows[0].len();
+        (0..width)
+            .map(|x| rows.iter().map(|row| row.as_bytes()[x] as char).collect())
+            .collect()
+    }
+
+    #[test]
+    fn label_regions_small_example() {
+        let grid = grid_from_rows(&["AAAA", "BBCD", "BBCC", "EEEC"]);
+        let region_map = label_regions(&grid, 4, 4, |a, b| a == b);
+
+        let price: u64 = region_map
+            .regions()
+            .iter()
+            .map(|region| region.area * region.perimeter)
+            .sum();
+        let bulk_price: u64 = region_map
+            .regions()
+            .iter()
+            .map(|region| region.area * region.sides)
+            .sum();
+
+        assert_eq!(region_map.regions().len(), 5);
+        assert_eq!(price, 140);
+        assert_eq!(bulk_price, 80);
+    }
+
+    #[test]
+    fn label_regions_inner_holes() {
+        let grid = grid_from_rows(&["EEEEE", "EXXXX", "EEEEE", "EXXXX", "EEEEE"]);
+        let region_map = label_regions(&grid, 5, 5, |a, b| a == b);
+
+        let region = region_map.region_at(0, 0);
+        assert_eq!(region.area, 17);
+        assert_eq!(region.sides, 12);
+        assert_eq!((region.min, region.max), ((0, 0), (4, 4)));
+        assert_ne!(region_map.label(1, 1), region_map.label(1, 3));
+    }
+
+    #[test]
+    fn peel_removes_until_stable() {
+        let mut grid = grid_from_rows(&["@@@", "@@@", "@@@"]);
+        let removed = peel(
+            &mut grid,
+            3,
+            3,
+            Neighbourhood::Surrounding,
+            |tile| *tile == '@',
+            |_, neighbours| neighbours < 4,
+            '.',
+        );
+
+        // The corners go first, after which the rest can be removed as well.
+        assert_eq!(removed.len(), 9);
+        assert_eq!(&removed[..4], &[(0, 0), (2, 0), (0, 2), (2, 2)]);
+    }
+}
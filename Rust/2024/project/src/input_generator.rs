@@ -0,0 +1,466 @@
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use crate::random::Random;
+
+/// What kind of maze to generate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeStyle {
+    /// Exactly one path between any two tiles.
+    Perfect,
+    /// A perfect maze with every dead end opened into a neighbour, so there are loops everywhere.
+    Braided,
+    /// A perfect maze with a few open rooms cleared out of it.
+    Rooms,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// Mazes and tracks need a odd size of at least 5, so every corridor has a wall either side.
+    InvalidSize(usize),
+    /// There aren't enough tiles to drop that many bytes on, the start and exit are kept free.
+    TooManyBytes {
+        count: usize,
+        free: usize,
+    },
+    UnknownKind(String),
+    InvalidArgument(String),
+}
+
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::InvalidSize(size) => {
+                write!(f, "Size {size} has to be odd and at least 5")
+            }
+            GeneratorError::TooManyBytes { count, free } => {
+                write!(f, "Can't drop {count} bytes on {free} free tiles")
+            }
+            GeneratorError::UnknownKind(kind) => write!(
+                f,
+                "Unknown kind '{kind}', expected perfect, braided, rooms, track or bytes"
+            ),
+            GeneratorError::InvalidArgument(argument) => {
+                write!(f, "Expected a number but found '{argument}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+/// Square grid of open and blocked tiles, stored row by row.
+struct Grid {
+    size: usize,
+    open: Vec<bool>,
+}
+
+impl Grid {
+    fn new(size: usize) -> Self {
+        Grid {
+            size,
+            open: vec![false; size * size],
+        }
+    }
+
+    fn is_open(&self, (x, y): (usize, usize)) -> bool {
+        self.open[y * self.size + x]
+    }
+
+    fn set(&mut self, (x, y): (usize, usize), open: bool) {
+        self.open[y * self.size + x] = open;
+    }
+
+    /// The tiles above, below and beside the position that are inside the grid.
+    fn neighbours(&self, (x, y): (usize, usize), distance: usize) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::with_capacity(4);
+        if x + distance < self.size {
+            neighbours.push((x + distance, y));
+        }
+        if y + distance < self.size {
+            neighbours.push((x, y + distance));
+        }
+        if x >= distance {
+            neighbours.push((x - distance, y));
+        }
+        if y >= distance {
+            neighbours.push((x, y - distance));
+        }
+        neighbours
+    }
+
+    fn open_neighbours(&self, position: (usize, usize)) -> usize {
+        self.neighbours(position, 1)
+            .into_iter()
+            .filter(|neighbour| self.is_open(*neighbour))
+            .count()
+    }
+
+    /// The maze cells, the tiles on odd positions. Walls between two cells are the only tiles
+    /// that get opened besides the cells themselves.
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (1..self.size - 1)
+            .step_by(2)
+            .flat_map(move |y| (1..self.size - 1).step_by(2).map(move |x| (x, y)))
+    }
+
+    /// Cells two tiles away, skipping the border wall.
+    fn neighbour_cells(&self, position: (usize, usize)) -> Vec<(usize, usize)> {
+        self.neighbours(position, 2)
+            .into_iter()
+            .filter(|(x, y)| *x > 0 && *y > 0 && *x < self.size - 1 && *y < self.size - 1)
+            .collect()
+    }
+
+    fn open_between(&mut self, (x_1, y_1): (usize, usize), (x_2, y_2): (usize, usize)) {
+        self.set(((x_1 + x_2) / 2, (y_1 + y_2) / 2), true);
+    }
+
+    fn to_lines(&self, start: (usize, usize), goal: (usize, usize)) -> Vec<String> {
+        (0..self.size)
+            .map(|y| {
+                (0..self.size)
+                    .map(|x| match (x, y) {
+                        position if position == start => 'S',
+                        position if position == goal => 'E',
+                        position if self.is_open(position) => '.',
+                        _ => '#',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Start and exit in the corners, like the day 16 mazes.
+fn start_and_goal(size: usize) -> ((usize, usize), (usize, usize)) {
+    ((1, size - 2), (size - 2, 1))
+}
+
+fn check_size(size: usize) -> Result<(), GeneratorError> {
+    if size < 5 || size.is_multiple_of(2) {
+        return Err(GeneratorError::InvalidSize(size));
+    }
+    Ok(())
+}
+
+/// Carves a perfect maze with a randomised depth first search from the start.
+fn perfect_maze(size: usize, random: &mut Random) -> Grid {
+    let mut grid = Grid::new(size);
+    let (start, _) = start_and_goal(size);
+    grid.set(start, true);
+    let mut stack = vec![start];
+    while let Some(&cell) = stack.last() {
+        let unvisited: Vec<(usize, usize)> = grid
+            .neighbour_cells(cell)
+            .into_iter()
+            .filter(|neighbour| !grid.is_open(*neighbour))
+            .collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[random.below(unvisited.len() as u64) as usize];
+        grid.open_between(cell, next);
+        grid.set(next, true);
+        stack.push(next);
+    }
+    grid
+}
+
+/// Opens a extra wall around every dead end, preferring walls that lead into another dead end
+/// so both are fixed at once.
+fn braid(grid: &mut Grid, random: &mut Random) {
+    let mut cells: Vec<(usize, usize)> = grid.cells().collect();
+    random.shuffle(&mut cells);
+    for cell in cells {
+        if grid.open_neighbours(cell) != 1 {
+            continue;
+        }
+        let mut closed: Vec<(usize, usize)> = grid
+            .neighbour_cells(cell)
+            .into_iter()
+            .filter(|(x, y)| !grid.is_open(((cell.0 + x) / 2, (cell.1 + y) / 2)))
+            .collect();
+        random.shuffle(&mut closed);
+        closed.sort_by_key(|neighbour| grid.open_neighbours(*neighbour) != 1);
+        if let Some(neighbour) = closed.first() {
+            grid.open_between(cell, *neighbour);
+        }
+    }
+}
+
+/// Clears a few rooms of 2 to 3 cells across. Clearing only opens tiles, so everything stays
+/// connected.
+fn add_rooms(grid: &mut Grid, random: &mut Random) {
+    let cells_across = (grid.size - 1) / 2;
+    let rooms = (grid.size / 10).max(1);
+    for _ in 0..rooms {
+        let width = (2 + random.below(2) as usize).min(cells_across);
+        let height = (2 + random.below(2) as usize).min(cells_across);
+        let left = 1 + 2 * random.below((cells_across - width + 1) as u64) as usize;
+        let top = 1 + 2 * random.below((cells_across - height + 1) as u64) as usize;
+        for y in top..top + 2 * height - 1 {
+            for x in left..left + 2 * width - 1 {
+                grid.set((x, y), true);
+            }
+        }
+    }
+}
+
+/// Generates a maze in the day 16 format, walls as '#', open tiles as '.', with S in the bottom
+/// left and E in the top right corner.
+pub fn maze(
+    size: usize,
+    style: MazeStyle,
+    random: &mut Random,
+) -> Result<Vec<String>, GeneratorError> {
+    check_size(size)?;
+    let mut grid = perfect_maze(size, random);
+    match style {
+        MazeStyle::Perfect => {}
+        MazeStyle::Braided => braid(&mut grid, random),
+        MazeStyle::Rooms => add_rooms(&mut grid, random),
+    }
+    let (start, goal) = start_and_goal(size);
+    Ok(grid.to_lines(start, goal))
+}
+
+/// Generates a race track in the day 20 format: a single winding corridor from S to E with no
+/// branches, walls everywhere else.
+///
+/// The track is the path between the corners of a perfect maze, so it fills a good part of the
+/// grid and has plenty of thin walls to cheat through.
+pub fn race_track(size: usize, random: &mut Random) -> Result<Vec<String>, GeneratorError> {
+    check_size(size)?;
+    let maze = perfect_maze(size, random);
+    let (start, goal) = start_and_goal(size);
+
+    // A perfect maze has only one path, so a breadth first search finds it.
+    let mut previous = vec![None; size * size];
+    let mut queue = VecDeque::from([start]);
+    while let Some(position) = queue.pop_front() {
+        if position == goal {
+            break;
+        }
+        for neighbour in maze.neighbours(position, 1) {
+            let index = neighbour.1 * size + neighbour.0;
+            if maze.is_open(neighbour) && previous[index].is_none() && neighbour != start {
+                previous[index] = Some(position);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    let mut track = Grid::new(size);
+    let mut position = goal;
+    track.set(position, true);
+    while let Some(from) = previous[position.1 * size + position.0] {
+        track.set(from, true);
+        position = from;
+    }
+    Ok(track.to_lines(start, goal))
+}
+
+/// Generates falling bytes in the day 18 format, one "x,y" line per byte, for a grid of the
+/// given size. Every byte lands on a different tile and the start and exit corners stay free.
+///
+/// Dropping every byte only blocks the way for sure when count is large enough, as it always is
+/// when every free tile gets one. With fewer bytes the path may stay open throughout.
+pub fn byte_fall(
+    size: usize,
+    count: usize,
+    random: &mut Random,
+) -> Result<Vec<String>, GeneratorError> {
+    let free = (size * size).saturating_sub(2);
+    if count > free {
+        return Err(GeneratorError::TooManyBytes { count, free });
+    }
+    let mut tiles: Vec<(usize, usize)> = (0..size)
+        .flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|tile| *tile != (0, 0) && *tile != (size - 1, size - 1))
+        .collect();
+    random.shuffle(&mut tiles);
+    Ok(tiles
+        .into_iter()
+        .take(count)
+        .map(|(x, y)| format!("{x},{y}"))
+        .collect())
+}
+
+/// Runs the generate subcommand: kind, then optionally the seed, size and for bytes the amount.
+/// The seed defaults to 0, the size to 41 for mazes and tracks and 71 for bytes, and the amount
+/// to every free tile.
+pub fn run_command(args: &[String]) -> Result<Vec<String>, GeneratorError> {
+    let number = |index: usize, default: usize| match args.get(index) {
+        None => Ok(default),
+        Some(argument) => argument
+            .parse::<usize>()
+            .map_err(|_| GeneratorError::InvalidArgument(argument.clone())),
+    };
+    let kind = args.first().map(String::as_str).unwrap_or("");
+    let mut random = Random::new(number(1, 0)? as u64);
+    match kind {
+        "perfect" => maze(number(2, 41)?, MazeStyle::Perfect, &mut random),
+        "braided" => maze(number(2, 41)?, MazeStyle::Braided, &mut random),
+        "rooms" => maze(number(2, 41)?, MazeStyle::Rooms, &mut random),
+        "track" => race_track(number(2, 41)?, &mut random),
+        "bytes" => {
+            let size = number(2, 71)?;
+            let count = number(3, (size * size).saturating_sub(2))?;
+            byte_fall(size, count, &mut random)
+        }
+        _ => Err(GeneratorError::UnknownKind(kind.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open tiles of the lines, with the amount of open neighbours each has.
+    fn open_tiles(lines: &[String]) -> Vec<((usize, usize), usize)> {
+        let open = |x: usize, y: usize| {
+            lines
+                .get(y)
+                .and_then(|line| line.as_bytes().get(x))
+                .is_some_and(|char| *char != b'#')
+        };
+        let mut tiles = Vec::new();
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..line.len() {
+                if open(x, y) {
+                    let neighbours = [
+                        (x + 1, y),
+                        (x, y + 1),
+                        (x.wrapping_sub(1), y),
+                        (x, y.wrapping_sub(1)),
+                    ]
+                    .iter()
+                    .filter(|(x, y)| open(*x, *y))
+                    .count();
+                    tiles.push(((x, y), neighbours));
+                }
+            }
+        }
+        tiles
+    }
+
+    fn is_connected(lines: &[String]) -> bool {
+        let tiles = open_tiles(lines);
+        let mut grid = Grid::new(lines.len());
+        for (tile, _) in &tiles {
+            grid.set(*tile, true);
+        }
+        let mut reached = vec![false; lines.len() * lines.len()];
+        let mut queue = VecDeque::from([tiles[0].0]);
+        let mut count = 0;
+        while let Some(position) = queue.pop_front() {
+            let index = position.1 * lines.len() + position.0;
+            if reached[index] {
+                continue;
+            }
+            reached[index] = true;
+            count += 1;
+            for neighbour in grid.neighbours(position, 1) {
+                if grid.is_open(neighbour) {
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        count == tiles.len()
+    }
+
+    #[test]
+    fn mazes_have_their_shape() {
+        let mut random = Random::new(50);
+        let perfect = maze(21, MazeStyle::Perfect, &mut random).unwrap();
+        assert_eq!(perfect.len(), 21);
+        assert!(perfect.iter().all(|line| line.len() == 21));
+        assert_eq!(&perfect[19][..2], "#S");
+        assert_eq!(&perfect[1][19..], "E#");
+        assert!(is_connected(&perfect));
+        // A tree has one less connection than it has tiles.
+        let tiles = open_tiles(&perfect);
+        let connections: usize = tiles
+            .iter()
+            .map(|(_, neighbours)| neighbours)
+            .sum::<usize>()
+            / 2;
+        assert_eq!(connections + 1, tiles.len());
+
+        let braided = maze(21, MazeStyle::Braided, &mut random).unwrap();
+        assert!(is_connected(&braided));
+        assert!(open_tiles(&braided)
+            .iter()
+            .all(|(_, neighbours)| *neighbours >= 2));
+
+        let rooms = maze(21, MazeStyle::Rooms, &mut random).unwrap();
+        assert!(is_connected(&rooms));
+        // A perfect maze never has a 3 by 3 block of open tiles, the rooms do.
+        let open_block = |x: usize, y: usize| {
+            (y..y + 3).all(|y| {
+                rooms[y].as_bytes()[x..x + 3]
+                    .iter()
+                    .all(|char| *char != b'#')
+            })
+        };
+        assert!((0..19).any(|y| (0..19).any(|x| open_block(x, y))));
+
+        assert_eq!(
+            maze(20, MazeStyle::Perfect, &mut random),
+            Err(GeneratorError::InvalidSize(20))
+        );
+    }
+
+    #[test]
+    fn tracks_have_no_branches() {
+        let track = race_track(15, &mut Random::new(20)).unwrap();
+        assert!(is_connected(&track));
+        let tiles = open_tiles(&track);
+        for ((x, y), neighbours) in tiles {
+            let end = matches!(track[y].as_bytes()[x], b'S' | b'E');
+            assert_eq!(neighbours, if end { 1 } else { 2 });
+        }
+    }
+
+    #[test]
+    fn bytes_land_on_free_tiles() {
+        let bytes = byte_fall(7, 47, &mut Random::new(18)).unwrap();
+        assert_eq!(bytes.len(), 47);
+        let mut sorted = bytes.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 47);
+        assert!(!bytes.contains(&"0,0".to_string()) && !bytes.contains(&"6,6".to_string()));
+        assert_eq!(
+            byte_fall(7, 48, &mut Random::new(18)),
+            Err(GeneratorError::TooManyBytes {
+                count: 48,
+                free: 47
+            })
+        );
+    }
+
+    #[test]
+    fn command_is_deterministic() {
+        let args = |text: &str| text.split(' ').map(String::from).collect::<Vec<_>>();
+        assert_eq!(
+            run_command(&args("braided 7 15")),
+            run_command(&args("braided 7 15"))
+        );
+        assert_ne!(
+            run_command(&args("braided 7 15")),
+            run_command(&args("braided 8 15"))
+        );
+        assert_eq!(run_command(&args("bytes 1 7 5")).unwrap().len(), 5);
+        assert_eq!(
+            run_command(&args("spiral")),
+            Err(GeneratorError::UnknownKind("spiral".to_string()))
+        );
+        assert_eq!(
+            run_command(&args("track x")),
+            Err(GeneratorError::InvalidArgument("x".to_string()))
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+/// Maps names to dense ids starting at 0, in the order they are first seen.
+///
+/// Lets puzzles index plain vectors by node id instead of packing names into integers by hand,
+/// while still being able to turn the ids back into names for the output.
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    ids: HashMap<String, usize>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id of the name, assigning the next free id if it hasn't been seen before.
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    /// Returns the id of an already interned name.
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    /// Returns the name behind an id.
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Iterates over all (id, name) pairs in id order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.names.iter().map(String::as_str).enumerate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interner;
+
+    #[test]
+    fn intern_assigns_dense_ids() {
+        let mut interner = Interner::new();
+        assert_eq!(interner.intern("svr"), 0);
+        assert_eq!(interner.intern("out"), 1);
+        assert_eq!(interner.intern("svr"), 0);
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn reverse_lookup() {
+        let mut interner = Interner::new();
+        let id = interner.intern("kh");
+        assert_eq!(interner.name(id), Some("kh"));
+        assert_eq!(interner.get("kh"), Some(id));
+        assert_eq!(interner.get("tc"), None);
+        assert_eq!(interner.name(5), None);
+    }
+}
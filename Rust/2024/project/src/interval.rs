@@ -0,0 +1,268 @@
+use std::iter::Sum;
+use std::ops::{Range, Sub};
+
+/// Set of values stored as sorted, non-overlapping half-open ranges.
+///
+/// Overlapping or touching ranges are merged as they are inserted, so the set always holds the
+/// fewest ranges needed to cover its values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T = u64> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { ranges: Vec::new() }
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every value in the range, merging it with any ranges it overlaps or touches.
+    pub fn insert(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        // Ranges entirely before or after the new one are kept as is, the ones in between are
+        // merged into it.
+        let first = self.ranges.partition_point(|r| r.end < range.start);
+        let last = self.ranges.partition_point(|r| r.start <= range.end);
+        let mut merged = range;
+        if first < last {
+            merged.start = merged.start.min(self.ranges[first].start);
+            merged.end = merged.end.max(self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, [merged]);
+    }
+
+    /// Removes every value in the range, splitting ranges that only partially overlap it.
+    pub fn remove(&mut self, range: Range<T>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let first = self.ranges.partition_point(|r| r.end <= range.start);
+        let last = self.ranges.partition_point(|r| r.start < range.end);
+        if first == last {
+            return;
+        }
+
+        let mut remaining = Vec::with_capacity(2);
+        if self.ranges[first].start < range.start {
+            remaining.push(self.ranges[first].start..range.start);
+        }
+        if range.end < self.ranges[last - 1].end {
+            remaining.push(range.end..self.ranges[last - 1].end);
+        }
+        self.ranges.splice(first..last, remaining);
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let index = self.ranges.partition_point(|r| r.end <= value);
+        self.ranges
+            .get(index)
+            .is_some_and(|range| range.contains(&value))
+    }
+
+    /// Returns the ranges of the set, sorted by their start.
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Range<T>> {
+        self.ranges.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Returns the lowest value in the set.
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    /// Returns a set containing the values found in either set.
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+        for range in other.iter() {
+            union.insert(range.clone());
+        }
+        union
+    }
+
+    /// Returns a set containing the values found in both sets.
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut ranges = Vec::new();
+        let (mut a, mut b) = (0, 0);
+        while a < self.ranges.len() && b < other.ranges.len() {
+            let (range_a, range_b) = (&self.ranges[a], &other.ranges[b]);
+            let start = range_a.start.max(range_b.start);
+            let end = range_a.end.min(range_b.end);
+            if start < end {
+                ranges.push(start..end);
+            }
+
+            // Whichever range ends first can't overlap anything further along in the other set.
+            match range_a.end <= range_b.end {
+                true => a += 1,
+                false => b += 1,
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    /// Returns a set containing the values of this set that are not in the other set.
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut difference = self.clone();
+        for range in other.iter() {
+            difference.remove(range.clone());
+        }
+        difference
+    }
+
+    /// Returns the total amount of values in the set.
+    pub fn size(&self) -> T
+    where
+        T: Sub<Output = T> + Sum,
+    {
+        self.ranges
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+/// Moves values within source ranges by an offset, leaving values outside them unchanged.
+///
+/// The entries should not overlap each other. Whole interval sets can be pushed through the map,
+/// which splits their ranges wherever they cross the edge of an entry.
+#[derive(Debug, Clone, Default)]
+pub struct RangeMap {
+    /// (source range, destination start), sorted by the source start.
+    entries: Vec<(Range<u64>, u64)>,
+}
+
+impl RangeMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps the length values starting at source_start onto the values starting at
+    /// destination_start.
+    pub fn insert(&mut self, source_start: u64, destination_start: u64, length: u64) {
+        let index = self
+            .entries
+            .partition_point(|(r, _)| r.start < source_start);
+        self.entries.insert(
+            index,
+            (source_start..source_start + length, destination_start),
+        );
+    }
+
+    pub fn map(&self, value: u64) -> u64 {
+        let index = self.entries.partition_point(|(r, _)| r.end <= value);
+        match self.entries.get(index) {
+            Some((source, destination)) if source.contains(&value) => {
+                value - source.start + destination
+            }
+            _ => value,
+        }
+    }
+
+    /// Maps every value in the set.
+    pub fn map_set(&self, set: &IntervalSet<u64>) -> IntervalSet<u64> {
+        let mut mapped = IntervalSet::new();
+        for range in set.iter() {
+            let mut cursor = range.start;
+            let first = self.entries.partition_point(|(r, _)| r.end <= cursor);
+            for (source, destination) in &self.entries[first..] {
+                if source.start >= range.end {
+                    break;
+                }
+
+                // The part in front of the entry isn't covered by anything and keeps its values.
+                if cursor < source.start {
+                    mapped.insert(cursor..source.start);
+                    cursor = source.start;
+                }
+                let end = range.end.min(source.end);
+                mapped
+                    .insert(cursor - source.start + destination..end - source.start + destination);
+                cursor = end;
+            }
+
+            if cursor < range.end {
+                mapped.insert(cursor..range.end);
+            }
+        }
+        mapped
+    }
+}
+
+/// Pushes the set through each map in turn, returning the values that come out the other end.
+pub fn map_set_through<'a, I>(maps: I, set: &IntervalSet<u64>) -> IntervalSet<u64>
+where
+    I: IntoIterator<Item = &'a RangeMap>,
+{
+    maps.into_iter()
+        .fold(set.clone(), |set, map| map.map_set(&set))
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)] // Single ranges are intended in the tests.
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_merges_overlapping_and_touching() {
+        let set: IntervalSet = [10..15, 3..6, 16..21, 12..19, 6..8].into_iter().collect();
+        assert_eq!(set.ranges(), &[3..8, 10..21]);
+        assert_eq!(set.size(), 16);
+        assert!(set.contains(7));
+        assert!(!set.contains(8));
+    }
+
+    #[test]
+    fn set_operations() {
+        let a: IntervalSet = [0..10, 20..30].into_iter().collect();
+        let b: IntervalSet = [5..25].into_iter().collect();
+
+        assert_eq!(a.union(&b).ranges(), &[0..30]);
+        assert_eq!(a.intersection(&b).ranges(), &[5..10, 20..25]);
+        assert_eq!(a.difference(&b).ranges(), &[0..5, 25..30]);
+        assert_eq!(b.difference(&a).ranges(), &[10..20]);
+    }
+
+    #[test]
+    fn map_set_splits_ranges() {
+        let mut map = RangeMap::new();
+        map.insert(98, 50, 2);
+        map.insert(50, 52, 48);
+
+        assert_eq!(map.map(79), 81);
+        assert_eq!(map.map(99), 51);
+        assert_eq!(map.map(10), 10);
+
+        let set: IntervalSet = [45..100].into_iter().collect();
+        // 45..50 is unmapped, 50..98 moves to 52..100 and 98..100 moves to 50..52.
+        assert_eq!(map.map_set(&set).ranges(), &[45..100]);
+
+        let set: IntervalSet = [96..102].into_iter().collect();
+        assert_eq!(map.map_set(&set).ranges(), &[50..52, 98..102]);
+    }
+}
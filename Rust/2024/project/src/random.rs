@@ -0,0 +1,76 @@
+/// Small seeded random number generator, for tests and generated inputs that have to come out
+/// the same on every run.
+///
+/// Uses SplitMix64, which passes the usual statistical tests and needs nothing but a counter.
+/// Not suitable for anything security related.
+#[derive(Clone, Debug)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /// Returns a value in 0..bound. Panics if bound is 0.
+    ///
+    /// Maps the full 64 bits onto the range with a multiplication, the bias that leaves is far
+    /// too small to matter for the bounds used here.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Bound must be above 0");
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Returns true with a probability of numerator / denominator.
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    /// Shuffles the values in place with a Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, values: &mut [T]) {
+        for index in (1..values.len()).rev() {
+            let other = self.below(index as u64 + 1) as usize;
+            values.swap(index, other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_values() {
+        let (mut a, mut b) = (Random::new(24), Random::new(24));
+        let values: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+        assert_eq!(values, (0..5).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(Random::new(25).next_u64(), values[0]);
+        // The first value of SplitMix64 seeded with 0.
+        assert_eq!(Random::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+    }
+
+    #[test]
+    fn below_and_shuffle() {
+        let mut random = Random::new(7);
+        let mut seen = [false; 6];
+        for _ in 0..200 {
+            seen[random.below(6) as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+
+        let mut values: Vec<u32> = (0..20).collect();
+        random.shuffle(&mut values);
+        assert_ne!(values, (0..20).collect::<Vec<_>>());
+        values.sort();
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+    }
+}
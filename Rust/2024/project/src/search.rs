@@ -0,0 +1,440 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+#[derive(Debug)]
+pub enum SearchError {
+    GoalUnreachable,
+}
+
+/// A single path through the search space together with its total cost.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub cost: u64,
+    pub nodes: Vec<N>,
+}
+
+/// Predecessor graph holding every optimal path from the start node to the goal nodes.
+///
+/// Each node reached by the search stores all neighbours it can be reached from at its optimal
+/// cost. Walking the predecessors backwards from the goals visits exactly the nodes which lie on
+/// at least one optimal path.
+pub struct PathDag<N> {
+    start: N,
+    cost: u64,
+    goals: Vec<N>,
+    distances: HashMap<N, u64>,
+    predecessors: HashMap<N, Vec<N>>,
+}
+
+impl<N: Copy + Eq + Hash> PathDag<N> {
+    pub fn start(&self) -> N {
+        self.start
+    }
+
+    /// The cost of the optimal paths.
+    pub fn cost(&self) -> u64 {
+        self.cost
+    }
+
+    /// All goal nodes that were reached at the optimal cost.
+    pub fn goals(&self) -> &[N] {
+        &self.goals
+    }
+
+    /// The cost of reaching the node from the start, if the search reached it.
+    pub fn distance(&self, node: &N) -> Option<u64> {
+        self.distances.get(node).copied()
+    }
+
+    pub fn predecessors(&self, node: &N) -> &[N] {
+        match self.predecessors.get(node) {
+            Some(predecessors) => predecessors,
+            None => &[],
+        }
+    }
+
+    /// Returns every node that is part of at least one optimal path.
+    pub fn nodes(&self) -> HashSet<N> {
+        let mut visited: HashSet<N> = self.goals.iter().copied().collect();
+        let mut queue: VecDeque<N> = self.goals.iter().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            for predecessor in self.predecessors(&node) {
+                if visited.insert(*predecessor) {
+                    queue.push_back(*predecessor);
+                }
+            }
+        }
+
+        visited
+    }
+
+    /// Returns the union of all optimal paths after mapping each node with the key function.
+    ///
+    /// Useful when several nodes share the same tile, such as a position with multiple facings.
+    pub fn tiles<T, F>(&self, key: F) -> HashSet<T>
+    where
+        T: Eq + Hash,
+        F: Fn(&N) -> T,
+    {
+        self.nodes().iter().map(key).collect()
+    }
+
+    /// Counts how many distinct optimal paths exist from the start to any of the goals.
+    pub fn count_paths(&self) -> u64 {
+        let nodes = self.topological_order();
+        let mut counts: HashMap<N, u64> = HashMap::with_capacity(nodes.len());
+
+        for node in nodes {
+            let count = match node == self.start {
+                true => 1,
+                false => self
+                    .predecessors(&node)
+                    .iter()
+                    .map(|predecessor| counts.get(predecessor).copied().unwrap_or(0))
+                    .sum(),
+            };
+            counts.insert(node, count);
+        }
+
+        self.goals.iter().map(|goal| counts[goal]).sum()
+    }
+
+    /// Lists every optimal path. The amount of paths can grow exponentially, so prefer
+    /// count_paths or nodes when the paths themselves are not needed.
+    pub fn paths(&self) -> Vec<Path<N>> {
+        let mut paths = Vec::new();
+        for goal in &self.goals {
+            let mut trace = vec![*goal];
+            self.collect_paths(&mut trace, &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths(&self, trace: &mut Vec<N>, paths: &mut Vec<Path<N>>) {
+        let current = *trace.last().expect("trace always holds at least the goal");
+        if current == self.start {
+            paths.push(Path {
+                cost: self.cost,
+                nodes: trace.iter().rev().copied().collect(),
+            });
+            return;
+        }
+
+        for predecessor in self.predecessors(&current) {
+            trace.push(*predecessor);
+            self.collect_paths(trace, paths);
+            trace.pop();
+        }
+    }
+
+    /// Nodes on the optimal paths ordered so that every node comes after its predecessors.
+    /// Since edge costs are positive, sorting by distance is enough.
+    fn topological_order(&self) -> Vec<N> {
+        let mut nodes: Vec<N> = self.nodes().into_iter().collect();
+        nodes.sort_by_key(|node| self.distances[node]);
+        nodes
+    }
+}
+
+/// Finds all optimal paths from start to any node accepted by is_goal.
+///
+/// neighbours returns each reachable node together with the cost of moving there. Costs must be
+/// positive. heuristic must never overestimate the remaining cost and must be consistent, return
+/// 0 to run a plain Dijkstra search.
+pub fn shortest_path_dag<N, FN, FH, FG>(
+    start: N,
+    neighbours: FN,
+    heuristic: FH,
+    is_goal: FG,
+) -> Result<PathDag<N>, SearchError>
+where
+    N: Copy + Eq + Hash + Ord,
+    FN: Fn(&N) -> Vec<(N, u64)>,
+    FH: Fn(&N) -> u64,
+    FG: Fn(&N) -> bool,
+{
+    let mut distances: HashMap<N, u64> = HashMap::from([(start, 0)]);
+    let mut predecessors: HashMap<N, Vec<N>> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), 0, start))]);
+    let mut best_cost: Option<u64> = None;
+    let mut goals = Vec::new();
+
+    while let Some(Reverse((estimate, cost, node))) = queue.pop() {
+        if best_cost.is_some_and(|best| estimate > best) {
+            break;
+        }
+        if cost > distances[&node] {
+            continue; // A cheaper way to this node has already been processed.
+        }
+
+        if is_goal(&node) {
+            best_cost = Some(cost);
+            goals.push(node);
+            continue;
+        }
+
+        for (neighbour, step_cost) in neighbours(&node) {
+            let new_cost = cost + step_cost;
+            match distances.get(&neighbour) {
+                Some(existing) if new_cost > *existing => {}
+                Some(existing) if new_cost == *existing => {
+                    predecessors.entry(neighbour).or_default().push(node);
+                }
+                _ => {
+                    distances.insert(neighbour, new_cost);
+                    predecessors.insert(neighbour, vec![node]);
+                    queue.push(Reverse((
+                        new_cost + heuristic(&neighbour),
+                        new_cost,
+                        neighbour,
+                    )));
+                }
+            }
+        }
+    }
+
+    let cost = best_cost.ok_or(SearchError::GoalUnreachable)?;
+    Ok(PathDag {
+        start,
+        cost,
+        goals,
+        distances,
+        predecessors,
+    })
+}
+
+/// Calculates the cost of reaching every node reachable from source.
+pub fn distance_field<N, FN>(source: N, neighbours: FN) -> HashMap<N, u64>
+where
+    N: Copy + Eq + Hash + Ord,
+    FN: Fn(&N) -> Vec<(N, u64)>,
+{
+    let mut distances: HashMap<N, u64> = HashMap::from([(source, 0)]);
+    let mut queue = BinaryHeap::from([Reverse((0, source))]);
+
+    while let Some(Reverse((cost, node))) = queue.pop() {
+        if cost > distances[&node] {
+            continue;
+        }
+        for (neighbour, step_cost) in neighbours(&node) {
+            let new_cost = cost + step_cost;
+            if distances
+                .get(&neighbour)
+                .is_none_or(|existing| new_cost < *existing)
+            {
+                distances.insert(neighbour, new_cost);
+                queue.push(Reverse((new_cost, neighbour)));
+            }
+        }
+    }
+
+    distances
+}
+
+/// Finds up to k loopless paths from start to a goal in order of increasing cost using Yen's
+/// algorithm. Paths with equal cost are all counted towards k.
+pub fn k_shortest_paths<N, FN, FG>(start: N, neighbours: FN, is_goal: FG, k: usize) -> Vec<Path<N>>
+where
+    N: Copy + Eq + Hash + Ord,
+    FN: Fn(&N) -> Vec<(N, u64)>,
+    FG: Fn(&N) -> bool,
+{
+    let mut found: Vec<Path<N>> = Vec::with_capacity(k);
+    if k == 0 {
+        return found;
+    }
+    let Some(first) = constrained_shortest_path(start, &neighbours, &is_goal, &HashSet::new())
+    else {
+        return found;
+    };
+
+    let mut candidates: BinaryHeap<Reverse<(u64, Vec<N>)>> = BinaryHeap::new();
+    let mut seen: HashSet<Vec<N>> = HashSet::from([first.nodes.clone()]);
+    found.push(first);
+
+    while found.len() < k {
+        let previous = found
+            .last()
+            .expect("found is never empty here")
+            .nodes
+            .clone();
+
+        for spur_index in 0..previous.len() - 1 {
+            let root = &previous[..=spur_index];
+            let spur_node = previous[spur_index];
+
+            // Block the next step of every known path sharing this root, as well as the root
+            // itself so that the spur path can't loop back into it.
+            let mut blocked_edges: HashSet<(N, N)> = HashSet::new();
+            for path in &found {
+                if path.nodes.len() > spur_index + 1 && &path.nodes[..=spur_index] == root {
+                    blocked_edges.insert((path.nodes[spur_index], path.nodes[spur_index + 1]));
+                }
+            }
+            let blocked_nodes: HashSet<N> = root[..spur_index].iter().copied().collect();
+
+            let spur_neighbours = |node: &N| -> Vec<(N, u64)> {
+                neighbours(node)
+                    .into_iter()
+                    .filter(|(next, _)| {
+                        !blocked_nodes.contains(next) && !blocked_edges.contains(&(*node, *next))
+                    })
+                    .collect()
+            };
+
+            let Some(spur_path) =
+                constrained_shortest_path(spur_node, &spur_neighbours, &is_goal, &blocked_nodes)
+            else {
+                continue;
+            };
+
+            let mut nodes = root[..spur_index].to_vec();
+            nodes.extend(spur_path.nodes);
+            if seen.insert(nodes.clone()) {
+                let cost = path_cost(&nodes[..=spur_index], &neighbours) + spur_path.cost;
+                candidates.push(Reverse((cost, nodes)));
+            }
+        }
+
+        let Some(Reverse((cost, nodes))) = candidates.pop() else {
+            break;
+        };
+        found.push(Path { cost, nodes });
+    }
+
+    found
+}
+
+/// Plain Dijkstra search returning a single optimal path that avoids the blocked nodes.
+fn constrained_shortest_path<N, FN, FG>(
+    start: N,
+    neighbours: &FN,
+    is_goal: &FG,
+    blocked: &HashSet<N>,
+) -> Option<Path<N>>
+where
+    N: Copy + Eq + Hash + Ord,
+    FN: Fn(&N) -> Vec<(N, u64)>,
+    FG: Fn(&N) -> bool,
+{
+    let mut distances: HashMap<N, u64> = HashMap::from([(start, 0)]);
+    let mut previous: HashMap<N, N> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((0, start))]);
+
+    while let Some(Reverse((cost, node))) = queue.pop() {
+        if cost > distances[&node] {
+            continue;
+        }
+
+        if is_goal(&node) {
+            let mut nodes = vec![node];
+            while let Some(parent) = previous.get(nodes.last().unwrap()) {
+                nodes.push(*parent);
+            }
+            nodes.reverse();
+            return Some(Path { cost, nodes });
+        }
+
+        for (neighbour, step_cost) in neighbours(&node) {
+            if blocked.contains(&neighbour) {
+                continue;
+            }
+            let new_cost = cost + step_cost;
+            if distances
+                .get(&neighbour)
+                .is_none_or(|existing| new_cost < *existing)
+            {
+                distances.insert(neighbour, new_cost);
+                previous.insert(neighbour, node);
+                queue.push(Reverse((new_cost, neighbour)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Sums the cheapest step costs along the nodes.
+fn path_cost<N, FN>(nodes: &[N], neighbours: &FN) -> u64
+where
+    N: Copy + Eq,
+    FN: Fn(&N) -> Vec<(N, u64)>,
+{
+    nodes
+        .windows(2)
+        .map(|step| {
+            neighbours(&step[0])
+                .into_iter()
+                .filter(|(next, _)| *next == step[1])
+                .map(|(_, cost)| cost)
+                .min()
+                .expect("consecutive path nodes are always connected")
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A small diamond shaped graph:
+    //     1
+    //   /   \
+    // 0       3 - 4
+    //   \   /
+    //     2
+    // With an additional, more expensive, direct edge from 0 to 3.
+    fn diamond(node: &u8) -> Vec<(u8, u64)> {
+        match node {
+            0 => vec![(1, 1), (2, 1), (3, 5)],
+            1 => vec![(3, 1)],
+            2 => vec![(3, 1)],
+            3 => vec![(4, 1)],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn shortest_path_dag_counts_all_optimal_paths() {
+        let Ok(dag) = shortest_path_dag(0, diamond, |_| 0, |node| *node == 4) else {
+            panic!("Goal should be reachable!");
+        };
+        assert_eq!(dag.cost(), 3);
+        assert_eq!(dag.count_paths(), 2);
+        assert_eq!(dag.nodes(), HashSet::from([0, 1, 2, 3, 4]));
+        assert_eq!(dag.paths().len(), 2);
+    }
+
+    #[test]
+    fn shortest_path_dag_tiles_merges_keys() {
+        let Ok(dag) = shortest_path_dag(0, diamond, |_| 0, |node| *node == 4) else {
+            panic!("Goal should be reachable!");
+        };
+        assert_eq!(dag.tiles(|node| node / 2), HashSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn shortest_path_dag_unreachable_goal() {
+        let result = shortest_path_dag(0, diamond, |_| 0, |node| *node == 9);
+        assert!(matches!(result, Err(SearchError::GoalUnreachable)));
+    }
+
+    #[test]
+    fn distance_field_from_source() {
+        let distances = distance_field(0, diamond);
+        assert_eq!(distances[&3], 2);
+        assert_eq!(distances[&4], 3);
+        assert_eq!(distances.len(), 5);
+    }
+
+    #[test]
+    fn k_shortest_paths_in_cost_order() {
+        let paths = k_shortest_paths(0, diamond, |node| *node == 4, 5);
+        let costs: Vec<u64> = paths.iter().map(|path| path.cost).collect();
+        assert_eq!(costs, vec![3, 3, 6]);
+        assert_eq!(paths[2].nodes, vec![0, 3, 4]);
+        assert!(k_shortest_paths(0, diamond, |node| *node == 4, 0).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+/// Prefix tree over words made of any small symbol type, like bytes, chars or enum values.
+///
+/// Nodes are kept in a single vector and point to their children by index. Each node only has a
+/// handful of children for small alphabets, so they are searched linearly.
+#[derive(Debug, Clone)]
+pub struct Trie<K> {
+    nodes: Vec<TrieNode<K>>,
+    word_count: usize,
+}
+
+#[derive(Debug, Clone)]
+struct TrieNode<K> {
+    children: Vec<(K, usize)>,
+    is_word: bool,
+}
+
+impl<K> TrieNode<K> {
+    fn new() -> Self {
+        TrieNode {
+            children: Vec::new(),
+            is_word: false,
+        }
+    }
+}
+
+impl<K: Copy + Eq> Default for Trie<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Copy + Eq> Trie<K> {
+    const ROOT: usize = 0;
+
+    pub fn new() -> Self {
+        Trie {
+            nodes: vec![TrieNode::new()],
+            word_count: 0,
+        }
+    }
+
+    fn child(&self, node: usize, symbol: K) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|(child_symbol, _)| *child_symbol == symbol)
+            .map(|(_, child)| *child)
+    }
+
+    /// Adds a word, returning false if it was already in the trie.
+    pub fn insert<I: IntoIterator<Item = K>>(&mut self, word: I) -> bool {
+        let mut node = Self::ROOT;
+        for symbol in word {
+            node = match self.child(node, symbol) {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(TrieNode::new());
+                    self.nodes[node].children.push((symbol, child));
+                    child
+                }
+            };
+        }
+
+        let is_new = !self.nodes[node].is_word;
+        self.nodes[node].is_word = true;
+        self.word_count += is_new as usize;
+        is_new
+    }
+
+    pub fn contains(&self, word: &[K]) -> bool {
+        let mut node = Self::ROOT;
+        for symbol in word {
+            match self.child(node, *symbol) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        self.nodes[node].is_word
+    }
+
+    /// Number of distinct words in the trie.
+    pub fn len(&self) -> usize {
+        self.word_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.word_count == 0
+    }
+
+    /// Iterates over the lengths of all words that the target starts with, shortest first.
+    pub fn prefix_lengths<'a>(&'a self, target: &'a [K]) -> impl Iterator<Item = usize> + 'a {
+        let mut node = Some(Self::ROOT);
+        (0..=target.len()).filter_map(move |length| {
+            let current = node?;
+            node = target
+                .get(length)
+                .and_then(|symbol| self.child(current, *symbol));
+            // The empty word never counts, it would let a segmentation loop forever.
+            (length > 0 && self.nodes[current].is_word).then_some(length)
+        })
+    }
+
+    /// Counts the ways the target can be split into a sequence of words from the trie.
+    ///
+    /// Works backwards from the end of the target, so the count for every suffix is only
+    /// calculated once. Returns 1 for an empty target.
+    pub fn count_segmentations(&self, target: &[K]) -> u64 {
+        let mut counts = vec![0u64; target.len() + 1];
+        counts[target.len()] = 1;
+        for start in (0..target.len()).rev() {
+            counts[start] = self
+                .prefix_lengths(&target[start..])
+                .map(|length| counts[start + length])
+                .sum();
+        }
+        counts[0]
+    }
+
+    /// Returns true if the target can be split into words from the trie at all.
+    pub fn can_segment(&self, target: &[K]) -> bool {
+        self.segmentable_suffixes(target)[0]
+    }
+
+    /// Lists every way the target can be split into words from the trie.
+    ///
+    /// The amount of segmentations can grow exponentially with the length of the target, so check
+    /// count_segmentations first if the input isn't known to be small.
+    pub fn segmentations<'a>(&self, target: &'a [K]) -> Vec<Vec<&'a [K]>> {
+        let segmentable = self.segmentable_suffixes(target);
+        let mut result = Vec::new();
+        if segmentable[0] {
+            self.collect_segmentations(target, 0, &segmentable, &mut Vec::new(), &mut result);
+        }
+        result
+    }
+
+    /// For each start index, whether the target from there on can be split into words. Used to
+    /// avoid walking down branches that can never finish.
+    fn segmentable_suffixes(&self, target: &[K]) -> Vec<bool> {
+        let mut segmentable = vec![false; target.len() + 1];
+        segmentable[target.len()] = true;
+        for start in (0..target.len()).rev() {
+            segmentable[start] = self
+                .prefix_lengths(&target[start..])
+                .any(|length| segmentable[start + length]);
+        }
+        segmentable
+    }
+
+    fn collect_segmentations<'a>(
+        &self,
+        target: &'a [K],
+        start: usize,
+        segmentable: &[bool],
+        current: &mut Vec<&'a [K]>,
+        result: &mut Vec<Vec<&'a [K]>>,
+    ) {
+        if start == target.len() {
+            result.push(current.clone());
+            return;
+        }
+        for length in self.prefix_lengths(&target[start..]) {
+            if !segmentable[start + length] {
+                continue;
+            }
+            current.push(&target[start..start + length]);
+            self.collect_segmentations(target, start + length, segmentable, current, result);
+            current.pop();
+        }
+    }
+}
+
+impl<K: Copy + Eq, W: IntoIterator<Item = K>> FromIterator<W> for Trie<K> {
+    fn from_iter<T: IntoIterator<Item = W>>(words: T) -> Self {
+        let mut trie = Trie::new();
+        for word in words {
+            trie.insert(word);
+        }
+        trie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trie;
+
+    #[test]
+    fn insert_and_contains() {
+        let mut trie = Trie::new();
+        assert!(trie.insert("br".bytes()));
+        assert!(trie.insert("bwu".bytes()));
+        assert!(!trie.insert("br".bytes()));
+        assert_eq!(trie.len(), 2);
+
+        assert!(trie.contains(b"br"));
+        assert!(!trie.contains(b"b"));
+        assert!(!trie.contains(b"bw"));
+        assert!(!trie.contains(b"brr"));
+        assert_eq!(trie.prefix_lengths(b"bwurr").collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn count_and_list_segmentations() {
+        let trie: Trie<u8> = ["r", "wr", "b", "g", "bwu", "rb", "gb", "br"]
+            .iter()
+            .map(|word| word.bytes())
+            .collect();
+
+        assert_eq!(trie.count_segmentations(b"gbbr"), 4);
+        assert_eq!(trie.count_segmentations(b"ubwu"), 0);
+        assert_eq!(trie.count_segmentations(b"rrbgbr"), 6);
+        assert!(trie.can_segment(b"bwurrg"));
+        assert!(!trie.can_segment(b"bbrgwb"));
+
+        let mut ways = trie.segmentations(b"gbbr");
+        ways.sort();
+        let expected: Vec<Vec<&[u8]>> = vec![
+            vec![b"g", b"b", b"b", b"r"],
+            vec![b"g", b"b", b"br"],
+            vec![b"gb", b"b", b"r"],
+            vec![b"gb", b"br"],
+        ];
+        assert_eq!(ways, expected);
+    }
+
+    #[test]
+    fn other_alphabets() {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        enum Step {
+            Up,
+            Down,
+        }
+        use Step::{Down, Up};
+
+        let trie: Trie<Step> = [vec![Up], vec![Up, Down], vec![Down, Down]]
+            .into_iter()
+            .collect();
+        assert_eq!(trie.count_segmentations(&[Up, Down, Down, Up]), 1);
+        assert_eq!(trie.count_segmentations(&[Up, Up, Down]), 1);
+        assert_eq!(trie.count_segmentations(&[]), 1);
+        assert_eq!(trie.segmentations(&[Down]), Vec::<Vec<&[Step]>>::new());
+    }
+}
@@ -0,0 +1,223 @@
+use std::cmp::Ordering;
+
+/// Disjoint set of the elements 0..len, used to keep track of which elements have been joined
+/// together into the same component.
+///
+/// Uses path compression together with union by size, making each operation close to constant
+/// time.
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+    component_count: usize,
+}
+
+impl UnionFind {
+    /// Creates len elements, each in a component of its own.
+    pub fn new(len: usize) -> Self {
+        UnionFind {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+            component_count: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// Returns the representative element of the component the element is part of.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point every element on the way directly at the root to speed up later searches.
+        let mut current = element;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Joins the components of the two elements. Returns false if they already were part of the
+    /// same component.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+
+        // The smaller component is placed under the larger one to keep the trees shallow.
+        let (large, small) = match self.sizes[root_a] >= self.sizes[root_b] {
+            true => (root_a, root_b),
+            false => (root_b, root_a),
+        };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+        self.component_count -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Returns the size of the component the element is part of.
+    pub fn component_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.sizes[root]
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.component_count
+    }
+
+    /// Returns the size of every component, in no particular order.
+    pub fn component_sizes(&self) -> Vec<usize> {
+        (0..self.len())
+            .filter(|element| self.parents[*element] == *element)
+            .map(|root| self.sizes[root])
+            .collect()
+    }
+
+    /// Lists the elements of every component. Components are ordered by their lowest element.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut component_index: Vec<Option<usize>> = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::with_capacity(self.component_count);
+
+        for element in 0..self.len() {
+            let root = self.find(element);
+            let index = *component_index[root].get_or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+            components[index].push(element);
+        }
+
+        components
+    }
+}
+
+/// Edge weights that can be sorted with a total order. Floats use total_cmp, so a NaN weight
+/// still has a place in the order instead of breaking the sort.
+pub trait Weight: Copy {
+    fn total_order(&self, other: &Self) -> Ordering;
+}
+
+macro_rules! impl_weight_for_integers {
+    ($($type:ty),*) => {
+        $(
+            impl Weight for $type {
+                fn total_order(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+
+impl_weight_for_integers!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl Weight for f32 {
+    fn total_order(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
+impl Weight for f64 {
+    fn total_order(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
+/// Picks the cheapest edges that connect the nodes 0..node_count using Kruskal's algorithm.
+///
+/// The edges are (node_a, node_b, weight) and the chosen ones are returned in the order they were
+/// added, so the last edge is the one that completed the forest. Nodes without any connecting
+/// edges end up as their own trees.
+pub fn minimum_spanning_forest<W, I>(node_count: usize, edges: I) -> Vec<(usize, usize, W)>
+where
+    W: Weight,
+    I: IntoIterator<Item = (usize, usize, W)>,
+{
+    let mut edges: Vec<(usize, usize, W)> = edges.into_iter().collect();
+    edges.sort_by(|a, b| a.2.total_order(&b.2));
+
+    let mut components = UnionFind::new(node_count);
+    let mut forest = Vec::with_capacity(node_count.saturating_sub(1));
+    for (a, b, weight) in edges {
+        if components.union(a, b) {
+            forest.push((a, b, weight));
+            if components.component_count() == 1 {
+                break;
+            }
+        }
+    }
+
+    forest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{minimum_spanning_forest, UnionFind};
+
+    #[test]
+    fn union_tracks_component_sizes() {
+        let mut union_find = UnionFind::new(6);
+        assert!(union_find.union(0, 1));
+        assert!(union_find.union(2, 1));
+        assert!(!union_find.union(0, 2));
+        assert!(union_find.union(4, 5));
+
+        assert_eq!(union_find.component_count(), 3);
+        assert_eq!(union_find.component_size(2), 3);
+        assert!(union_find.connected(0, 2));
+        assert!(!union_find.connected(0, 3));
+
+        let mut sizes = union_find.component_sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 2, 3]);
+        assert_eq!(
+            union_find.components(),
+            vec![vec![0, 1, 2], vec![3], vec![4, 5]]
+        );
+    }
+
+    #[test]
+    fn minimum_spanning_forest_picks_cheapest_edges() {
+        let edges = [
+            (0, 1, 4.0),
+            (1, 2, 1.0),
+            (0, 2, 2.5),
+            (2, 3, 7.0),
+            (1, 3, 3.0),
+        ];
+        let forest = minimum_spanning_forest(4, edges);
+        assert_eq!(forest, vec![(1, 2, 1.0), (0, 2, 2.5), (1, 3, 3.0)]);
+    }
+
+    #[test]
+    fn minimum_spanning_forest_disconnected() {
+        let forest = minimum_spanning_forest(5, [(0, 1, 2), (3, 4, 1)]);
+        assert_eq!(forest, vec![(3, 4, 1), (0, 1, 2)]);
+    }
+
+    #[test]
+    fn minimum_spanning_forest_nan_weight() {
+        // NaN sorts after every other weight, so the edge is only used if nothing else connects.
+        let edges = [(0, 1, f64::NAN), (1, 2, 1.0), (0, 2, 2.0)];
+        assert_eq!(
+            minimum_spanning_forest(3, edges),
+            vec![(1, 2, 1.0), (0, 2, 2.0)]
+        );
+    }
+}
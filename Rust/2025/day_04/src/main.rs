@@ -1,3 +1,5 @@
+#[allow(dead_code)] // Shared module, only the peeling is used here.
+mod grid_region;
 mod reader;
 
 #[cfg(test)]
@@ -152,8 +154,13 @@ Then we would simply call collect_paper over and over again untill the return va
 It is not the most "efficient" solution, but it would be very easy to modify the existing code to work.
 A more efficient solution would require a larger redesign of the system. Not impossible but it would
 still demand quite a lot more work.
+
+Update:
+The removal is now done by grid_region::peel, which keeps a queue of tiles to check and only adds
+the neighbours of removed tiles back to it. So we no longer scan the whole grid over and over.
 */
 mod part_two {
+    use crate::grid_region::{self, Neighbourhood};
     use crate::reader;
     use std::error::Error;
 
@@ -199,33 +206,20 @@ mod part_two {
             })
         }
 
+        /// Removes every accessible paper tile, including the ones that become accessible once
+        /// others are removed. Returns the amount of paper collected.
         fn collect_paper(&mut self) -> u64 {
-            let mut paper_collected = 0;
-            for y in 1..self.size_override + 1 {
-                for x in 1..self.size_override + 1 {
-                    if let Tile::Empty = self.grid[x][y] {
-                        continue;
-                    }
-
-                    if self.is_accessible(x, y) {
-                        paper_collected += 1;
-                        self.grid[x][y] = Tile::Empty;
-                    }
-                }
-            }
-            paper_collected
-        }
-
-        fn is_accessible(&mut self, c_x: usize, c_y: usize) -> bool {
-            let mut surrounding_paper = 0;
-            for y in c_y - 1..=c_y + 1 {
-                for x in c_x - 1..=c_x + 1 {
-                    if let Tile::Paper = self.grid[x][y] {
-                        surrounding_paper += 1;
-                    }
-                }
-            }
-            surrounding_paper <= 4
+            let size = self.size_override + 2; // Include the layer of empty tiles.
+            let removed = grid_region::peel(
+                &mut self.grid,
+                size,
+                size,
+                Neighbourhood::Surrounding,
+                |tile| matches!(tile, Tile::Paper),
+                |_, surrounding_paper| surrounding_paper < 4,
+                Tile::Empty,
+            );
+            removed.len() as u64
         }
 
         /// Debug code to print the map in the same format as the data file.
@@ -245,16 +239,7 @@ mod part_two {
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
         let mut map = Map::load_from_file(data_path)?;
-        let mut paper_collected = 0;
-        loop {
-            let paper = map.collect_paper();
-            if paper == 0 {
-                break;
-            }
-            paper_collected += paper;
-        }
-
-        Ok(paper_collected)
+        Ok(map.collect_paper())
     }
 }
 
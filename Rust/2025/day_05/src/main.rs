@@ -1,3 +1,5 @@
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod interval;
 mod reader;
 
 #[cfg(test)]
@@ -111,73 +113,32 @@ This should be enough to merge any overlapping ranges.
 
 Then count the total by looping through all ranges adding the following to the total
 range.upper - range.lower
+
+Update: The merging has been moved into the shared interval set, which merges overlapping ranges
+as they are inserted. It keeps the ranges sorted, so only the neighbouring ranges have to be
+checked instead of restarting the search after every merge.
 */
 mod part_two {
+    use crate::interval::IntervalSet;
     use crate::reader;
-    use std::error::Error;
-
-    #[derive(Clone, Copy, Debug)]
-    struct IDRange {
-        upper: u64,
-        lower: u64,
-    }
-
-    impl IDRange {
-        pub fn parse(data_string: &str) -> Result<IDRange, Box<dyn Error>> {
-            let mut parts = data_string.split('-');
-            Ok(IDRange {
-                lower: parts.next().ok_or("Invalid Range Format")?.parse()?,
-                upper: parts.next().ok_or("Invalid Range Format")?.parse()?,
-            })
-        }
-
-        pub fn attempt_merge(&self, other: &IDRange) -> Option<IDRange> {
-            if !self.overlaps_with(other) {
-                return None;
-            }
-
-            Some(IDRange {
-                upper: self.upper.max(other.upper),
-                lower: self.lower.min(other.lower),
-            })
-        }
-
-        fn overlaps_with(&self, other: &IDRange) -> bool {
-            self.lower <= other.upper && other.lower <= self.upper
-        }
-
-        pub fn size(&self) -> u64 {
-            self.upper - self.lower + 1 // Add 1 since the range is inclusive.
-        }
-    }
-
-    fn merge_ranges(ranges: &mut Vec<IDRange>) {
-        let mut origin_i = 0;
-        while origin_i < ranges.len() {
-            let mut search_i = origin_i + 1;
-            while search_i < ranges.len() {
-                if let Some(merged_range) = ranges[origin_i].attempt_merge(&ranges[search_i]) {
-                    ranges.swap_remove(search_i);
-                    ranges[origin_i] = merged_range;
-                    search_i = origin_i + 1;
-                } else {
-                    search_i += 1;
-                }
-            }
-            origin_i += 1;
-        }
+    use std::{error::Error, ops::Range};
+
+    /// Parses an inclusive "lower-upper" range into a half-open range.
+    fn parse_range(data_string: &str) -> Result<Range<u64>, Box<dyn Error>> {
+        let mut parts = data_string.split('-');
+        let lower: u64 = parts.next().ok_or("Invalid Range Format")?.parse()?;
+        let upper: u64 = parts.next().ok_or("Invalid Range Format")?.parse()?;
+        Ok(lower..upper + 1) // Add 1 since the range is inclusive.
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
-        let mut ranges: Vec<IDRange> = Vec::new();
+        let mut fresh_ids: IntervalSet<u64> = IntervalSet::new();
 
         for line in reader::get_lines(data_path)?.take_while(|line| !line.is_empty()) {
-            ranges.push(IDRange::parse(&line)?);
+            fresh_ids.insert(parse_range(&line)?);
         }
 
-        merge_ranges(&mut ranges);
-
-        Ok(ranges.iter().map(|range| range.size()).sum())
+        Ok(fresh_ids.size())
     }
 }
 
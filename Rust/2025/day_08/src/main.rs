@@ -1,7 +1,10 @@
-use core::f32;
-use std::{cmp::Ordering, collections::BinaryHeap, error::Error, usize};
+use std::error::Error;
 
 mod reader;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod union_find;
+
+use union_find::{minimum_spanning_forest, UnionFind};
 
 #[allow(dead_code)]
 const PART_ONE_EXPECTED_TEST_VALUE: u64 = 40;
@@ -49,14 +52,18 @@ others.
 Time to restart.
 
 97119 is too low
+
+Update: The network bookkeeping has been replaced with the shared union find structure. It does
+the merging of the smaller network into the larger one for us, and keeps track of the network
+sizes. The distances are now also compared squared as integers, the old ordering truncated them
+which could put pairs in the wrong order.
 */
 
 #[derive(Clone, Copy, Debug)]
 struct JunctionBox {
-    x: f32,
-    y: f32,
-    z: f32,
-    network_id: usize,
+    x: i64,
+    y: i64,
+    z: i64,
 }
 
 impl JunctionBox {
@@ -66,140 +73,48 @@ impl JunctionBox {
             x: parts.next().ok_or("Missing x coordinate.")?.parse()?,
             y: parts.next().ok_or("Missing y coordinate.")?.parse()?,
             z: parts.next().ok_or("Missing z coordinate.")?.parse()?,
-            network_id: usize::max_value(),
         })
     }
 
-    fn distance(&self, other: &JunctionBox) -> f32 {
-        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2))
-            .sqrt()
-    }
-
-    fn in_network(&self) -> bool {
-        self.network_id != usize::max_value()
-    }
-}
-
-fn merge_networks(
-    networks_to_merge: (usize, usize),
-    junction_boxes: &mut Vec<JunctionBox>,
-    networks: &mut Vec<Vec<usize>>,
-) -> usize {
-    let (largest_network, smallest_network) = {
-        if networks[networks_to_merge.0].len() >= networks[networks_to_merge.1].len() {
-            (networks_to_merge.0, networks_to_merge.1)
-        } else {
-            (networks_to_merge.1, networks_to_merge.0)
-        }
-    };
-
-    if networks_to_merge.0 == networks_to_merge.1 {
-        return networks[largest_network].len();
-    }
-
-    for i in 0..networks[smallest_network].len() {
-        let box_id = networks[smallest_network][i];
-        junction_boxes[box_id].network_id = largest_network;
-        networks[largest_network].push(box_id);
-    }
-
-    networks[smallest_network].clear(); // We don't delete the network since then we need to
-                                        // account for later networks getting a new id.
-    networks[largest_network].len()
-}
-
-#[derive(Clone, Copy)]
-struct BoxPair {
-    box_1_id: usize,
-    box_2_id: usize,
-    distance: f32,
-}
-
-impl Ord for BoxPair {
-    fn cmp(&self, other: &Self) -> Ordering {
-        (self.distance as i64 * 100).cmp(&(other.distance as i64 * 100))
+    /// Squared distance to the other box. Only used for comparing distances, so the square root
+    /// is skipped which keeps the ordering exact.
+    fn distance(&self, other: &JunctionBox) -> i64 {
+        (self.x - other.x).pow(2) + (self.y - other.y).pow(2) + (self.z - other.z).pow(2)
     }
 }
 
-impl PartialOrd for BoxPair {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+fn load_junction_boxes(data_path: &str) -> Result<Vec<JunctionBox>, Box<dyn Error>> {
+    let mut junction_boxes: Vec<JunctionBox> = Vec::new();
+    for line in reader::get_lines(data_path)? {
+        junction_boxes.push(JunctionBox::parse(&line)?);
     }
+    Ok(junction_boxes)
 }
 
-impl PartialEq for BoxPair {
-    fn eq(&self, other: &Self) -> bool {
-        self.distance == other.distance
+/// Returns every pair of junction boxes as (box_1_id, box_2_id, distance).
+fn box_pairs(junction_boxes: &[JunctionBox]) -> Vec<(usize, usize, i64)> {
+    let mut pairs = Vec::with_capacity(junction_boxes.len() * junction_boxes.len() / 2);
+    for (box_id, junction_box) in junction_boxes.iter().enumerate() {
+        for (other_box_id, other_box) in junction_boxes.iter().enumerate().skip(box_id + 1) {
+            pairs.push((box_id, other_box_id, junction_box.distance(other_box)));
+        }
     }
+    pairs
 }
 
-impl Eq for BoxPair {}
-
 fn calculate_part_one(data_path: &str, connections_to_make: usize) -> Result<u64, Box<dyn Error>> {
-    let lines = reader::get_lines(data_path)?;
-    let mut junction_boxes: Vec<JunctionBox> = Vec::new();
-    for line in lines {
-        junction_boxes.push(JunctionBox::parse(&line)?);
-    }
-
-    let mut networks: Vec<Vec<usize>> = Vec::new();
+    let junction_boxes = load_junction_boxes(data_path)?;
+    let mut junction_box_pairs = box_pairs(&junction_boxes);
+    junction_box_pairs.sort_by_key(|(_, _, distance)| *distance);
 
-    let mut junction_box_pairs: BinaryHeap<BoxPair> = BinaryHeap::new();
-
-    for box_id in 0..junction_boxes.len() {
-        let junction_box = junction_boxes[box_id];
-        for other_box_id in box_id + 1..junction_boxes.len() {
-            let other_box = junction_boxes[other_box_id];
-            junction_box_pairs.push(BoxPair {
-                box_1_id: box_id,
-                box_2_id: other_box_id,
-                distance: junction_box.distance(&other_box),
-            });
-        }
+    let mut networks = UnionFind::new(junction_boxes.len());
+    for (box_1_id, box_2_id, _) in junction_box_pairs.iter().take(connections_to_make) {
+        networks.union(*box_1_id, *box_2_id);
     }
 
-    for box_pair in junction_box_pairs
-        .into_sorted_vec()
-        .iter()
-        .take(connections_to_make)
-    {
-        let (box_1_id, box_2_id, distance) =
-            (box_pair.box_1_id, box_pair.box_2_id, box_pair.distance);
-        let mut junction_box = junction_boxes[box_1_id];
-        let mut other_junction_box = junction_boxes[box_2_id];
-        if junction_box.in_network() && other_junction_box.in_network() {
-            merge_networks(
-                (junction_box.network_id, other_junction_box.network_id),
-                &mut junction_boxes,
-                &mut networks,
-            );
-        } else if junction_box.in_network() && !other_junction_box.in_network() {
-            networks[junction_box.network_id].push(box_2_id);
-            other_junction_box.network_id = junction_box.network_id;
-            junction_boxes[box_2_id] = other_junction_box;
-        } else if !junction_box.in_network() && other_junction_box.in_network() {
-            networks[other_junction_box.network_id].push(box_1_id);
-            junction_box.network_id = other_junction_box.network_id;
-            junction_boxes[box_1_id] = junction_box;
-        } else {
-            networks.push(vec![box_1_id, box_2_id]);
-
-            junction_box.network_id = networks.len() - 1;
-            junction_boxes[box_1_id] = junction_box;
-            other_junction_box.network_id = networks.len() - 1;
-            junction_boxes[box_2_id] = other_junction_box;
-        }
-    }
-
-    let mut network_sizes = networks.iter().map(|n| n.len()).collect::<Vec<usize>>();
+    let mut network_sizes = networks.component_sizes();
     network_sizes.sort();
-    let mut result = 1;
-    for network_size in network_sizes.iter().rev().take(3) {
-        if *network_size == 0 {
-            break;
-        }
-        result *= network_size;
-    }
+    let result: usize = network_sizes.iter().rev().take(3).product();
     Ok(result as u64)
 }
 
@@ -216,78 +131,22 @@ Copy the part 1 solution and make the following changes:
 4: Return the product from multiplying the x values of the latest pair of boxes.
 
 9003684864 is too low
+
+Update: Connecting the boxes in order of distance until everything is one network is exactly
+Kruskal's algorithm for a minimum spanning tree. The last edge it adds is the pair we are after.
 */
 
 fn calculate_part_two(data_path: &str) -> Result<u64, Box<dyn Error>> {
-    let lines = reader::get_lines(data_path)?;
-    let mut junction_boxes: Vec<JunctionBox> = Vec::new();
-    for line in lines {
-        junction_boxes.push(JunctionBox::parse(&line)?);
-    }
+    let junction_boxes = load_junction_boxes(data_path)?;
+    let connections = minimum_spanning_forest(junction_boxes.len(), box_pairs(&junction_boxes));
 
-    let mut networks: Vec<Vec<usize>> = Vec::new();
-
-    let mut junction_box_pairs: BinaryHeap<BoxPair> = BinaryHeap::new();
-
-    for box_id in 0..junction_boxes.len() {
-        let junction_box = junction_boxes[box_id];
-        for other_box_id in box_id + 1..junction_boxes.len() {
-            let other_box = junction_boxes[other_box_id];
-            junction_box_pairs.push(BoxPair {
-                box_1_id: box_id,
-                box_2_id: other_box_id,
-                distance: junction_box.distance(&other_box),
-            });
-        }
+    let (box_1_id, box_2_id, _) = *connections.last().ok_or("No junction boxes to connect.")?;
+    if connections.len() + 1 != junction_boxes.len() {
+        return Err("The junction boxes could not all be connected.".into());
     }
 
-    let junction_box_pairs = junction_box_pairs.into_sorted_vec();
-    let mut box_pairs_iter = junction_box_pairs.iter();
-    let mut box_pair: &BoxPair = &BoxPair {
-        box_1_id: 0,
-        box_2_id: 0,
-        distance: 0.0,
-    };
-
-    for _ in 0..junction_box_pairs.len() {
-        box_pair = box_pairs_iter.next().expect("This will never fail since this will never run more times than there are items in the iterator");
-        let (box_1_id, box_2_id, _) = (box_pair.box_1_id, box_pair.box_2_id, box_pair.distance);
-        let mut junction_box = junction_boxes[box_1_id];
-        let mut other_junction_box = junction_boxes[box_2_id];
-
-        if junction_box.in_network() && other_junction_box.in_network() {
-            if merge_networks(
-                (junction_box.network_id, other_junction_box.network_id),
-                &mut junction_boxes,
-                &mut networks,
-            ) == junction_boxes.len()
-            {}
-        } else if junction_box.in_network() && !other_junction_box.in_network() {
-            networks[junction_box.network_id].push(box_2_id);
-            other_junction_box.network_id = junction_box.network_id;
-            junction_boxes[box_2_id] = other_junction_box;
-        } else if !junction_box.in_network() && other_junction_box.in_network() {
-            networks[other_junction_box.network_id].push(box_1_id);
-            junction_box.network_id = other_junction_box.network_id;
-            junction_boxes[box_1_id] = junction_box;
-        } else {
-            networks.push(vec![box_1_id, box_2_id]);
-
-            junction_box.network_id = networks.len() - 1;
-            junction_boxes[box_1_id] = junction_box;
-            other_junction_box.network_id = networks.len() - 1;
-            junction_boxes[box_2_id] = other_junction_box;
-        }
-        if networks[junction_boxes[box_1_id].network_id].len() == junction_boxes.len()
-            || networks[junction_boxes[box_2_id].network_id].len() == junction_boxes.len()
-        {
-            break;
-        }
-    }
-
-    let result =
-        junction_boxes[box_pair.box_1_id].x as u64 * junction_boxes[box_pair.box_2_id].x as u64;
-    Ok(result)
+    let result = junction_boxes[box_1_id].x * junction_boxes[box_2_id].x;
+    Ok(result as u64)
 }
 
 //
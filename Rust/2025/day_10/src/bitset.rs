@@ -0,0 +1,339 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Fixed size set of the numbers 0..len stored as bits in u64 words.
+///
+/// The set operations work on a whole word at a time, so intersecting two sets of a few hundred
+/// values only takes a handful of instructions. Bits past len are always kept at zero.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BitSet {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    /// Creates a set able to hold 0..len with nothing in it.
+    pub fn new(len: usize) -> Self {
+        BitSet {
+            len,
+            words: vec![0; len.div_ceil(WORD_BITS)],
+        }
+    }
+
+    /// Creates a set holding every value in 0..len.
+    pub fn full(len: usize) -> Self {
+        let mut set = BitSet {
+            len,
+            words: vec![u64::MAX; len.div_ceil(WORD_BITS)],
+        };
+        set.clear_unused_bits();
+        set
+    }
+
+    /// Creates a set able to hold 0..len with the listed bits set.
+    pub fn from_ones(len: usize, ones: &[usize]) -> Self {
+        let mut set = BitSet::new(len);
+        for bit in ones {
+            set.insert(*bit);
+        }
+        set
+    }
+
+    fn clear_unused_bits(&mut self) {
+        if let Some(last) = self.words.last_mut() {
+            let used = self.len % WORD_BITS;
+            if used != 0 {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+
+    fn assert_in_range(&self, bit: usize) {
+        assert!(
+            bit < self.len,
+            "Bit {bit} out of range for length {}",
+            self.len
+        );
+    }
+
+    /// Number of bits, set or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Panics if the bit is out of range.
+    pub fn get(&self, bit: usize) -> bool {
+        self.assert_in_range(bit);
+        self.words[bit / WORD_BITS] >> (bit % WORD_BITS) & 1 == 1
+    }
+
+    /// Panics if the bit is out of range.
+    pub fn set(&mut self, bit: usize, value: bool) {
+        self.assert_in_range(bit);
+        let mask = 1 << (bit % WORD_BITS);
+        match value {
+            true => self.words[bit / WORD_BITS] |= mask,
+            false => self.words[bit / WORD_BITS] &= !mask,
+        }
+    }
+
+    /// Panics if the bit is out of range.
+    pub fn toggle(&mut self, bit: usize) {
+        self.assert_in_range(bit);
+        self.words[bit / WORD_BITS] ^= 1 << (bit % WORD_BITS);
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        self.get(value)
+    }
+
+    pub fn insert(&mut self, value: usize) {
+        self.set(value, true)
+    }
+
+    pub fn remove(&mut self, value: usize) {
+        self.set(value, false)
+    }
+
+    /// Removes every value less than or equal to the provided one.
+    pub fn remove_up_to(&mut self, value: usize) {
+        self.assert_in_range(value);
+        for word in &mut self.words[..value / WORD_BITS] {
+            *word = 0;
+        }
+        let bit = value % WORD_BITS;
+        self.words[value / WORD_BITS] &= match bit {
+            63 => 0,
+            _ => !((1 << (bit + 1)) - 1),
+        };
+    }
+
+    /// Number of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns true if no bits are set.
+    pub fn is_clear(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    /// Iterates over the positions of the set bits in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(index * WORD_BITS + bit)
+            })
+        })
+    }
+
+    /// Returns true if the sets have any value in common.
+    pub fn intersects(&self, other: &BitSet) -> bool {
+        self.assert_same_len(other);
+        self.words.iter().zip(&other.words).any(|(a, b)| a & b != 0)
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.assert_same_len(other);
+        self.words
+            .iter()
+            .zip(&other.words)
+            .all(|(a, b)| a & !b == 0)
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        self & other
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        self | other
+    }
+
+    /// Values in this set that aren't in the other.
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        self.assert_same_len(other);
+        BitSet {
+            len: self.len,
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & !b)
+                .collect(),
+        }
+    }
+
+    fn assert_same_len(&self, other: &BitSet) {
+        assert_eq!(self.len, other.len, "Bit sets must have the same length");
+    }
+}
+
+macro_rules! impl_bit_operator {
+    ($op_trait:ident, $op:ident, $assign_trait:ident, $assign:ident) => {
+        impl $assign_trait<&BitSet> for BitSet {
+            /// Panics if the lengths differ.
+            fn $assign(&mut self, rhs: &BitSet) {
+                self.assert_same_len(rhs);
+                for (word, other) in self.words.iter_mut().zip(&rhs.words) {
+                    word.$assign(other);
+                }
+            }
+        }
+
+        impl $op_trait<&BitSet> for &BitSet {
+            type Output = BitSet;
+
+            /// Panics if the lengths differ.
+            fn $op(self, rhs: &BitSet) -> BitSet {
+                let mut result = self.clone();
+                result.$assign(rhs);
+                result
+            }
+        }
+    };
+}
+
+impl_bit_operator!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_bit_operator!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_bit_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+/// Matrix of booleans with each row stored as a bit set, for relations like "a must come before
+/// b" or "a is connected to b".
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BitMatrix {
+    columns: usize,
+    rows: Vec<BitSet>,
+}
+
+impl BitMatrix {
+    /// Creates a rows * columns matrix with every bit cleared.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        BitMatrix {
+            columns,
+            rows: vec![BitSet::new(columns); rows],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.rows[row].get(column)
+    }
+
+    pub fn set(&mut self, row: usize, column: usize, value: bool) {
+        self.rows[row].set(column, value)
+    }
+
+    pub fn row(&self, row: usize) -> &BitSet {
+        &self.rows[row]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut BitSet {
+        &mut self.rows[row]
+    }
+
+    /// Iterates over the rows in order.
+    pub fn iter_rows(&self) -> impl Iterator<Item = &BitSet> {
+        self.rows.iter()
+    }
+
+    /// Number of set bits in the whole matrix.
+    pub fn count_ones(&self) -> usize {
+        self.rows.iter().map(BitSet::count_ones).sum()
+    }
+
+    /// Returns the matrix where row a has bit b set if b can be reached from a by following one
+    /// or more set bits, treating the matrix as the edges of a graph.
+    ///
+    /// Uses Warshall's algorithm: once every path through the nodes 0..k is known, any row that
+    /// reaches k also reaches everything k reaches, which is a single or of two rows. Panics if
+    /// the matrix isn't square.
+    pub fn transitive_closure(&self) -> BitMatrix {
+        assert_eq!(self.rows(), self.columns, "Matrix must be square");
+
+        let mut closure = self.clone();
+        for k in 0..closure.rows() {
+            let reachable_from_k = closure.rows[k].clone();
+            for row in closure.rows.iter_mut() {
+                if row.get(k) {
+                    *row |= &reachable_from_k;
+                }
+            }
+        }
+        closure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_set_spanning_words() {
+        let mut set = BitSet::from_ones(130, &[0, 64, 129]);
+        assert_eq!(set.count_ones(), 3);
+        set.toggle(64);
+        set.insert(1);
+        assert_eq!(set.ones().collect::<Vec<_>>(), vec![0, 1, 129]);
+
+        let other = BitSet::from_ones(130, &[1, 2]);
+        assert_eq!((&set ^ &other).ones().collect::<Vec<_>>(), vec![0, 2, 129]);
+        assert_eq!((&set & &other).ones().collect::<Vec<_>>(), vec![1]);
+        assert_eq!((&set | &other).count_ones(), 4);
+        assert_eq!(set.difference(&other).ones().collect::<Vec<_>>(), [0, 129]);
+        assert!(set.intersects(&other));
+        assert!(!set.is_subset(&other));
+        assert!(BitSet::from_ones(130, &[1]).is_subset(&other));
+    }
+
+    #[test]
+    fn full_and_remove_up_to() {
+        let mut set = BitSet::full(70);
+        assert_eq!(set.count_ones(), 70);
+        set.remove_up_to(63);
+        assert_eq!(set.ones().collect::<Vec<_>>(), (64..70).collect::<Vec<_>>());
+        set.remove_up_to(69);
+        assert!(set.is_clear());
+        assert!(!set.is_empty());
+    }
+
+    #[test]
+    fn transitive_closure_follows_chains() {
+        // 0 -> 1 -> 2 -> 3, and 4 -> 4.
+        let mut matrix = BitMatrix::new(5, 5);
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (4, 4)] {
+            matrix.set(from, to, true);
+        }
+
+        let closure = matrix.transitive_closure();
+        let reachable: Vec<Vec<usize>> = closure
+            .iter_rows()
+            .map(|row| row.ones().collect())
+            .collect();
+        assert_eq!(
+            reachable,
+            vec![vec![1, 2, 3], vec![2, 3], vec![3], vec![], vec![4]]
+        );
+        assert_eq!(closure.count_ones(), 7);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::linear_algebra::{Field, Matrix, Rational};
+
+/// Finds the non-negative integer x with the lowest sum for which A x = b holds.
+///
+/// Uses branch and bound: the problem is first solved with fractions allowed, using the simplex
+/// method. If some variable comes out as a fraction the problem is split in two, one where it is
+/// at most the value rounded down and one where it is at least the value rounded up. Branches
+/// whose fractional minimum can't beat the best whole solution found so far are skipped.
+///
+/// Branching alone never ends when the fractional problem has no upper limit but no whole
+/// solution exists, like 2x - 2y = 1. So it is first checked that A x = b has a whole solution at
+/// all, ignoring the signs, and the search then caps the sum of x. Every variable is limited by
+/// the cap, which makes each search finite. The cap starts at the fractional minimum and doubles
+/// until a solution is found or it reaches a limit that any solvable problem has a solution within,
+/// see sum_limit.
+///
+/// Returns None if there is no such x, or if the lowest sum doesn't fit in a i64. Panics if b
+/// doesn't have one value per row of A.
+pub fn minimise_sum(a: &Matrix<Rational>, b: &[Rational]) -> Option<Vec<i64>> {
+    assert_eq!(a.rows(), b.len(), "Right hand side must match the rows");
+    let (rows, rhs) = integer_rows(a, b);
+    if !has_integer_solution(rows.clone(), &rhs) {
+        return None;
+    }
+
+    let mut bounds = Bounds::new(a.columns());
+    let (minimum, _) = minimise_relaxed(a, b, &bounds)?;
+    let limit = sum_limit(&rows, &rhs);
+    let mut cap = minimum.ceil().max(1);
+    loop {
+        bounds.sum_cap = Some(cap.min(limit));
+        if let Some(x) = branch_and_bound(a, b, bounds.clone()) {
+            return Some(x);
+        }
+        if cap >= limit {
+            return None;
+        }
+        cap = cap.saturating_mul(2);
+    }
+}
+
+/// Searches for the x with the lowest sum within the bounds.
+fn branch_and_bound(a: &Matrix<Rational>, b: &[Rational], bounds: Bounds) -> Option<Vec<i64>> {
+    let mut best: Option<(i128, Vec<i64>)> = None;
+    let mut branches = vec![bounds];
+    while let Some(bounds) = branches.pop() {
+        let Some((minimum, x)) = minimise_relaxed(a, b, &bounds) else {
+            continue;
+        };
+        // The sum of whole numbers is whole, so the fractional minimum can be rounded up.
+        if best
+            .as_ref()
+            .is_some_and(|(best_sum, _)| minimum.ceil() >= *best_sum)
+        {
+            continue;
+        }
+
+        match x.iter().position(|value| !value.is_integer()) {
+            Some(variable) => {
+                let mut lower_branch = bounds.clone();
+                lower_branch.upper[variable] = Some(x[variable].floor());
+                let mut upper_branch = bounds;
+                upper_branch.lower[variable] = x[variable].ceil();
+                branches.push(upper_branch);
+                branches.push(lower_branch);
+            }
+            None => {
+                let x: Option<Vec<i64>> = x
+                    .iter()
+                    .map(|value| i64::try_from(value.numerator()).ok())
+                    .collect();
+                if let Some(x) = x {
+                    best = Some((minimum.numerator(), x));
+                }
+            }
+        }
+    }
+
+    best.map(|(_, x)| x)
+}
+
+/// Scales every row of A x = b to whole numbers, returning the rows of A and the values of b.
+fn integer_rows(a: &Matrix<Rational>, b: &[Rational]) -> (Vec<Vec<i128>>, Vec<i128>) {
+    let mut rows: Vec<Vec<i128>> = Vec::with_capacity(a.rows());
+    let mut rhs: Vec<i128> = Vec::with_capacity(a.rows());
+    for (row, value) in b.iter().enumerate() {
+        let scale = a
+            .row(row)
+            .iter()
+            .chain([value])
+            .fold(1, |scale, value| lcm(scale, value.denominator()));
+        let scaled = |value: &Rational| value.numerator() * (scale / value.denominator());
+        rows.push(a.row(row).iter().map(scaled).collect());
+        rhs.push(scaled(value));
+    }
+    (rows, rhs)
+}
+
+/// Checks if the whole number rows have a solution in whole numbers, negative ones included.
+///
+/// Column operations that keep whole solutions whole (swapping, and adding a whole multiple of
+/// one column to another) bring the rows to a lower triangle, the way Euclid's algorithm finds a
+/// gcd. Solving the triangle from the top then only has to check that every division comes out
+/// even.
+fn has_integer_solution(mut rows: Vec<Vec<i128>>, rhs: &[i128]) -> bool {
+    let columns = rows.first().map_or(0, Vec::len);
+    let mut pivot_column = 0;
+    let mut solution: Vec<i128> = Vec::with_capacity(columns);
+    for (row, value) in rhs.iter().enumerate() {
+        for column in pivot_column + 1..columns {
+            while rows[row][column] != 0 {
+                let quotient = rows[row][pivot_column] / rows[row][column];
+                for entries in rows.iter_mut() {
+                    entries[pivot_column] -= quotient * entries[column];
+                    entries.swap(pivot_column, column);
+                }
+            }
+        }
+
+        // Everything left of the pivot is already solved.
+        let remaining = value
+            - rows[row][..pivot_column]
+                .iter()
+                .zip(&solution)
+                .map(|(entry, solved)| entry * solved)
+                .sum::<i128>();
+        if pivot_column < columns && rows[row][pivot_column] != 0 {
+            if remaining % rows[row][pivot_column] != 0 {
+                return false;
+            }
+            solution.push(remaining / rows[row][pivot_column]);
+            pivot_column += 1;
+        } else if remaining != 0 {
+            return false;
+        }
+    }
+    true
+}
+
+/// A sum that the lowest non-negative whole solution never goes above, if there is one.
+///
+/// When A x = b, x >= 0 has a whole solution, it has one where no variable is above (n + 1) times
+/// the largest determinant of a square part of [A b], n being the variable count (Schrijver,
+/// Theory of Linear and Integer Programming, theorem 17.1). Hadamard's bound, the product of the
+/// row lengths, stands in for that determinant. Rounding every length up keeps it an upper bound.
+fn sum_limit(rows: &[Vec<i128>], rhs: &[i128]) -> i128 {
+    let determinant: f64 = rows
+        .iter()
+        .zip(rhs)
+        .map(|(row, value)| {
+            let squares: f64 = row.iter().chain([value]).map(|v| (*v as f64).powi(2)).sum();
+            squares.sqrt().ceil().max(1.0)
+        })
+        .product();
+    let variables = rows.first().map_or(0, Vec::len) as f64;
+    // Past what a i64 holds the answer couldn't be returned anyway.
+    (variables * (variables + 1.0) * determinant).min(i64::MAX as f64) as i128
+}
+
+fn lcm(a: i128, b: i128) -> i128 {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+/// Extra limits placed on the variables by the branching.
+#[derive(Clone, Debug)]
+struct Bounds {
+    lower: Vec<i128>,
+    upper: Vec<Option<i128>>,
+    /// Highest allowed sum of all variables.
+    sum_cap: Option<i128>,
+}
+
+impl Bounds {
+    fn new(variable_count: usize) -> Self {
+        Bounds {
+            lower: vec![0; variable_count],
+            upper: vec![None; variable_count],
+            sum_cap: None,
+        }
+    }
+}
+
+/// Minimises the sum of x with fractions allowed, returning the minimum together with x.
+fn minimise_relaxed(
+    a: &Matrix<Rational>,
+    b: &[Rational],
+    bounds: &Bounds,
+) -> Option<(Rational, Vec<Rational>)> {
+    let variable_count = a.columns();
+
+    // Every bound becomes an extra equation with a slack variable of its own:
+    // x - slack = lower, x + slack = upper.
+    let mut rows: Vec<(Vec<Rational>, Rational)> = (0..a.rows())
+        .map(|row| (a.row(row).to_vec(), b[row]))
+        .collect();
+    let mut slack_rows: Vec<(usize, Rational, Rational)> = Vec::new();
+    for variable in 0..variable_count {
+        if bounds.lower[variable] > 0 {
+            slack_rows.push((variable, -Rational::ONE, bounds.lower[variable].into()));
+        }
+        if let Some(upper) = bounds.upper[variable] {
+            slack_rows.push((variable, Rational::ONE, upper.into()));
+        }
+    }
+
+    let mut column_count = variable_count + slack_rows.len();
+    if let Some(cap) = bounds.sum_cap {
+        // sum(x) + slack = cap.
+        column_count += 1;
+        let mut row = vec![Rational::ZERO; column_count];
+        row[..variable_count].fill(Rational::ONE);
+        row[column_count - 1] = Rational::ONE;
+        rows.push((row, cap.into()));
+    }
+    for (row, _) in rows.iter_mut() {
+        row.resize(column_count, Rational::ZERO);
+    }
+    for (slack, (variable, sign, value)) in slack_rows.into_iter().enumerate() {
+        let mut row = vec![Rational::ZERO; column_count];
+        row[variable] = Rational::ONE;
+        row[variable_count + slack] = sign;
+        rows.push((row, value));
+    }
+
+    let mut costs = vec![Rational::ZERO; column_count];
+    costs[..variable_count].fill(Rational::ONE);
+
+    let x = Simplex::minimise(rows, &costs)?;
+    let minimum = x[..variable_count]
+        .iter()
+        .fold(Rational::ZERO, |sum, value| sum + *value);
+    Some((minimum, x[..variable_count].to_vec()))
+}
+
+/// Simplex tableau for minimising costs * x subject to rows * x = rhs and x >= 0.
+struct Simplex {
+    rows: Vec<Vec<Rational>>,
+    rhs: Vec<Rational>,
+    basis: Vec<usize>,
+}
+
+impl Simplex {
+    /// Returns the x that minimises costs * x, or None if there is no valid x. Only meant for
+    /// costs that are never negative, like the sums minimised here. Since x >= 0 the cost then
+    /// can't go below 0, so the minimum always exists when there is a valid x.
+    fn minimise(rows: Vec<(Vec<Rational>, Rational)>, costs: &[Rational]) -> Option<Vec<Rational>> {
+        let column_count = costs.len();
+        let row_count = rows.len();
+
+        // Phase one: An artificial variable is added to each row, which gives an easy starting
+        // point where only they are non-zero. Pushing them all to zero finds a valid x.
+        let mut simplex = Simplex {
+            rows: Vec::with_capacity(row_count),
+            rhs: Vec::with_capacity(row_count),
+            basis: (column_count..column_count + row_count).collect(),
+        };
+        for (index, (mut row, mut rhs)) in rows.into_iter().enumerate() {
+            if rhs < Rational::ZERO {
+                row.iter_mut().for_each(|value| *value = -*value);
+                rhs = -rhs;
+            }
+            row.resize(column_count + row_count, Rational::ZERO);
+            row[column_count + index] = Rational::ONE;
+            simplex.rows.push(row);
+            simplex.rhs.push(rhs);
+        }
+
+        let mut artificial_costs = vec![Rational::ZERO; column_count + row_count];
+        artificial_costs[column_count..].fill(Rational::ONE);
+        simplex.optimise(&artificial_costs);
+        if simplex
+            .rhs
+            .iter()
+            .zip(&simplex.basis)
+            .any(|(value, column)| *column >= column_count && !value.is_zero())
+        {
+            return None;
+        }
+
+        // Artificial variables still in the basis are zero. Swap them for a real variable, or
+        // drop the row if it only repeats the other rows.
+        let mut row = 0;
+        while row < simplex.rows.len() {
+            if simplex.basis[row] < column_count {
+                row += 1;
+                continue;
+            }
+            match (0..column_count).find(|column| !simplex.rows[row][*column].is_zero()) {
+                Some(column) => {
+                    simplex.pivot(row, column);
+                    row += 1;
+                }
+                None => {
+                    simplex.rows.remove(row);
+                    simplex.rhs.remove(row);
+                    simplex.basis.remove(row);
+                }
+            }
+        }
+        for row in simplex.rows.iter_mut() {
+            row.truncate(column_count);
+        }
+
+        // Phase two: Minimise the actual costs starting from the valid x.
+        simplex.optimise(costs);
+
+        let mut x = vec![Rational::ZERO; column_count];
+        for (value, column) in simplex.rhs.iter().zip(&simplex.basis) {
+            x[*column] = *value;
+        }
+        Some(x)
+    }
+
+    /// Pivots until no column can lower the cost any further.
+    ///
+    /// Uses Bland's rule, always picking the lowest column and row that works, which prevents
+    /// the method from cycling forever on degenerate problems.
+    fn optimise(&mut self, costs: &[Rational]) {
+        loop {
+            let reduced_cost = |column: usize| {
+                self.rows
+                    .iter()
+                    .zip(&self.basis)
+                    .fold(costs[column], |cost, (row, basis_column)| {
+                        cost - costs[*basis_column] * row[column]
+                    })
+            };
+            let Some(column) = (0..costs.len())
+                .filter(|column| !self.basis.contains(column))
+                .find(|column| reduced_cost(*column) < Rational::ZERO)
+            else {
+                return;
+            };
+
+            let Some(row) = (0..self.rows.len())
+                .filter(|row| self.rows[*row][column] > Rational::ZERO)
+                .min_by(|a, b| {
+                    let ratio_a = self.rhs[*a] / self.rows[*a][column];
+                    let ratio_b = self.rhs[*b] / self.rows[*b][column];
+                    ratio_a
+                        .cmp(&ratio_b)
+                        .then(self.basis[*a].cmp(&self.basis[*b]))
+                })
+            else {
+                // Unbounded, which can't happen with costs that are never negative.
+                return;
+            };
+
+            self.pivot(row, column);
+        }
+    }
+
+    fn pivot(&mut self, row: usize, column: usize) {
+        let pivot = self.rows[row][column];
+        self.rows[row]
+            .iter_mut()
+            .for_each(|value| *value = *value / pivot);
+        self.rhs[row] = self.rhs[row] / pivot;
+
+        for other in (0..self.rows.len()).filter(|other| *other != row) {
+            let factor = self.rows[other][column];
+            if factor.is_zero() {
+                continue;
+            }
+            for c in 0..self.rows[other].len() {
+                let value = self.rows[row][c];
+                self.rows[other][c] = self.rows[other][c] - factor * value;
+            }
+            self.rhs[other] = self.rhs[other] - factor * self.rhs[row];
+        }
+        self.basis[row] = column;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational_rows(rows: &[&[i64]]) -> Matrix<Rational> {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|row| row.iter().map(|value| Rational::from(*value)).collect())
+                .collect(),
+        )
+    }
+
+    fn rational_values(values: &[i64]) -> Vec<Rational> {
+        values.iter().map(|value| Rational::from(*value)).collect()
+    }
+
+    #[test]
+    fn minimise_sum_joltage_example() {
+        // Blocks (3) (1,3) (2) (2,3) (0,2) (0,1) reaching the counters {3,5,4,7}.
+        let a = rational_rows(&[
+            &[0, 0, 0, 0, 1, 1],
+            &[0, 1, 0, 0, 0, 1],
+            &[0, 0, 1, 1, 1, 0],
+            &[1, 1, 0, 1, 0, 0],
+        ]);
+        let x = minimise_sum(&a, &rational_values(&[3, 5, 4, 7])).unwrap();
+        assert_eq!(x.iter().sum::<i64>(), 10);
+
+        // The found presses have to actually reach the counters.
+        for (row, target) in [3, 5, 4, 7].iter().enumerate() {
+            let reached: i64 = (0..6)
+                .map(|column| a[(row, column)].numerator() as i64 * x[column])
+                .sum();
+            assert_eq!(reached, *target);
+        }
+    }
+
+    #[test]
+    fn minimise_sum_needs_branching() {
+        // 2x + 2y = 3 has fractional solutions only.
+        let a = rational_rows(&[&[2, 2]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[3])), None);
+
+        // 2x + 3y = 7: the relaxation picks y = 7/3, but the best whole answer is x = 2, y = 1.
+        let a = rational_rows(&[&[2, 3]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[7])), Some(vec![2, 1]));
+    }
+
+    #[test]
+    fn minimise_sum_unbounded_without_whole_solution() {
+        // 2x - 2y = 1 has fractional solutions without an upper limit, but no whole ones.
+        let a = rational_rows(&[&[2, -2]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[1])), None);
+
+        // The rows only rule out whole solutions together: x + y = 1 and x - y = 0.
+        let a = rational_rows(&[&[1, 1, 0], &[1, -1, 0]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[1, 0])), None);
+
+        // x - y = 1 has no upper limit either, but does have whole solutions.
+        let a = rational_rows(&[&[1, -1]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[1])), Some(vec![1, 0]));
+    }
+
+    #[test]
+    fn minimise_sum_whole_solutions_all_negative() {
+        // x + y = 1 and x - 3z + 3w = 2 have whole solutions, like x = -1, y = 2, w = 1, but
+        // none without a negative, while the fractional problem has no upper limit.
+        let a = rational_rows(&[&[1, 1, 0, 0], &[1, 0, -3, 3]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[1, 2])), None);
+    }
+
+    #[test]
+    fn minimise_sum_infeasible_and_redundant() {
+        let a = rational_rows(&[&[1, 1], &[1, 1]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[2, 3])), None);
+        assert_eq!(
+            minimise_sum(&a, &rational_values(&[2, 2])).map(|x| x.iter().sum::<i64>()),
+            Some(2)
+        );
+
+        let a = rational_rows(&[&[1, -1]]);
+        assert_eq!(minimise_sum(&a, &rational_values(&[-2])), Some(vec![0, 2]));
+    }
+}
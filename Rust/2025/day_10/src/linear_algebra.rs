@@ -0,0 +1,593 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+use std::ops::{Add, Div, Index, IndexMut, Mul, Neg, RangeInclusive, Sub};
+
+/// Number types that support exact addition, subtraction, multiplication and division.
+pub trait Field:
+    Copy
+    + PartialEq
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+}
+
+/// Exact fraction, always stored in lowest terms with a positive denominator.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+impl Rational {
+    /// Panics if the denominator is zero.
+    pub fn new(numerator: i128, denominator: i128) -> Self {
+        assert!(denominator != 0, "Rational with a zero denominator");
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Rational {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    /// Returns the value as an integer, or None if it is a fraction.
+    pub fn to_integer(self) -> Option<i128> {
+        self.is_integer().then_some(self.numerator)
+    }
+
+    /// Rounds towards negative infinity.
+    pub fn floor(&self) -> i128 {
+        self.numerator.div_euclid(self.denominator)
+    }
+
+    /// Rounds towards positive infinity.
+    pub fn ceil(&self) -> i128 {
+        -(-self.numerator).div_euclid(self.denominator)
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(value: i128) -> Self {
+        Rational {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Rational::from(value as i128)
+    }
+}
+
+impl Debug for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.denominator {
+            1 => write!(f, "{}", self.numerator),
+            _ => write!(f, "{}/{}", self.numerator, self.denominator),
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Denominators are positive so cross multiplying keeps the order.
+        (self.numerator * other.denominator).cmp(&(other.numerator * self.denominator))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+    fn add(self, rhs: Rational) -> Rational {
+        Rational::new(
+            self.numerator * rhs.denominator + rhs.numerator * self.denominator,
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+    fn sub(self, rhs: Rational) -> Rational {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+    fn mul(self, rhs: Rational) -> Rational {
+        Rational::new(
+            self.numerator * rhs.numerator,
+            self.denominator * rhs.denominator,
+        )
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+    /// Panics when dividing by zero.
+    fn div(self, rhs: Rational) -> Rational {
+        Rational::new(
+            self.numerator * rhs.denominator,
+            self.denominator * rhs.numerator,
+        )
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        Rational {
+            numerator: -self.numerator,
+            denominator: self.denominator,
+        }
+    }
+}
+
+impl Field for Rational {
+    const ZERO: Self = Rational {
+        numerator: 0,
+        denominator: 1,
+    };
+    const ONE: Self = Rational {
+        numerator: 1,
+        denominator: 1,
+    };
+}
+
+/// Element of GF(2), the field with only 0 and 1 where addition is xor.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Gf2(pub bool);
+
+// Addition and subtraction are both xor and multiplication is and, as GF(2) works modulo 2.
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Add for Gf2 {
+    type Output = Gf2;
+    fn add(self, rhs: Gf2) -> Gf2 {
+        Gf2(self.0 ^ rhs.0)
+    }
+}
+
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Sub for Gf2 {
+    type Output = Gf2;
+    fn sub(self, rhs: Gf2) -> Gf2 {
+        Gf2(self.0 ^ rhs.0)
+    }
+}
+
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl Mul for Gf2 {
+    type Output = Gf2;
+    fn mul(self, rhs: Gf2) -> Gf2 {
+        Gf2(self.0 & rhs.0)
+    }
+}
+
+impl Div for Gf2 {
+    type Output = Gf2;
+    /// Panics when dividing by zero.
+    fn div(self, rhs: Gf2) -> Gf2 {
+        assert!(rhs.0, "Division by zero in GF(2)");
+        self
+    }
+}
+
+impl Field for Gf2 {
+    const ZERO: Self = Gf2(false);
+    const ONE: Self = Gf2(true);
+}
+
+/// Dense matrix stored row by row.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Matrix<F> {
+    rows: usize,
+    columns: usize,
+    values: Vec<F>,
+}
+
+impl<F: Field> Matrix<F> {
+    /// Creates a rows * columns matrix filled with zeroes.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Matrix {
+            rows,
+            columns,
+            values: vec![F::ZERO; rows * columns],
+        }
+    }
+
+    /// Panics if the rows are not all the same length.
+    pub fn from_rows(rows: Vec<Vec<F>>) -> Self {
+        let columns = rows.first().map_or(0, Vec::len);
+        assert!(
+            rows.iter().all(|row| row.len() == columns),
+            "Matrix rows must all be the same length"
+        );
+        Matrix {
+            rows: rows.len(),
+            columns,
+            values: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn row(&self, row: usize) -> &[F] {
+        &self.values[row * self.columns..(row + 1) * self.columns]
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for column in 0..self.columns {
+            self.values
+                .swap(a * self.columns + column, b * self.columns + column);
+        }
+    }
+
+    /// Brings the matrix into reduced row echelon form, returning the pivot column of each
+    /// non-zero row.
+    ///
+    /// Only the first pivot_limit columns are used as pivots, which lets an augmented matrix be
+    /// reduced without pivoting on the right hand side.
+    fn reduce(&mut self, pivot_limit: usize) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        for column in 0..pivot_limit {
+            let row = pivots.len();
+            let Some(pivot_row) = (row..self.rows).find(|r| !self[(*r, column)].is_zero()) else {
+                continue;
+            };
+            self.swap_rows(row, pivot_row);
+
+            let pivot = self[(row, column)];
+            for c in column..self.columns {
+                self[(row, c)] = self[(row, c)] / pivot;
+            }
+
+            for other in (0..self.rows).filter(|other| *other != row) {
+                let factor = self[(other, column)];
+                if factor.is_zero() {
+                    continue;
+                }
+                for c in column..self.columns {
+                    self[(other, c)] = self[(other, c)] - factor * self[(row, c)];
+                }
+            }
+
+            pivots.push(column);
+            if pivots.len() == self.rows {
+                break;
+            }
+        }
+        pivots
+    }
+
+    /// Brings the matrix into reduced row echelon form, returning the pivot columns.
+    pub fn row_reduce(&mut self) -> Vec<usize> {
+        self.reduce(self.columns)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().row_reduce().len()
+    }
+}
+
+impl<F> Index<(usize, usize)> for Matrix<F> {
+    type Output = F;
+    fn index(&self, (row, column): (usize, usize)) -> &F {
+        &self.values[row * self.columns + column]
+    }
+}
+
+impl<F> IndexMut<(usize, usize)> for Matrix<F> {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut F {
+        &mut self.values[row * self.columns + column]
+    }
+}
+
+/// Every solution of a linear system A x = b.
+///
+/// The free variables can be given any value, each pivot variable then follows from them:
+/// x[pivot] = constant - sum(coefficient * x[free]).
+#[derive(Clone, Debug)]
+pub struct SolutionSpace<F> {
+    variable_count: usize,
+    pivot_variables: Vec<usize>,
+    free_variables: Vec<usize>,
+    constants: Vec<F>,
+    /// For each pivot variable, the coefficient of each free variable.
+    coefficients: Vec<Vec<F>>,
+}
+
+/// Solves A x = b, returning None if the system has no solution.
+///
+/// Panics if b doesn't have one value per row of A.
+pub fn solve<F: Field>(a: &Matrix<F>, b: &[F]) -> Option<SolutionSpace<F>> {
+    assert_eq!(a.rows(), b.len(), "Right hand side must match the rows");
+
+    let variable_count = a.columns();
+    let mut augmented = Matrix::new(a.rows(), variable_count + 1);
+    for row in 0..a.rows() {
+        for column in 0..variable_count {
+            augmented[(row, column)] = a[(row, column)];
+        }
+        augmented[(row, variable_count)] = b[row];
+    }
+
+    let pivot_variables = augmented.reduce(variable_count);
+
+    // A zero row with a non-zero right hand side means 0 = something.
+    if (pivot_variables.len()..augmented.rows())
+        .any(|row| !augmented[(row, variable_count)].is_zero())
+    {
+        return None;
+    }
+
+    let free_variables: Vec<usize> = (0..variable_count)
+        .filter(|variable| !pivot_variables.contains(variable))
+        .collect();
+    let constants = (0..pivot_variables.len())
+        .map(|row| augmented[(row, variable_count)])
+        .collect();
+    let coefficients = (0..pivot_variables.len())
+        .map(|row| {
+            free_variables
+                .iter()
+                .map(|free| augmented[(row, *free)])
+                .collect()
+        })
+        .collect();
+
+    Some(SolutionSpace {
+        variable_count,
+        pivot_variables,
+        free_variables,
+        constants,
+        coefficients,
+    })
+}
+
+impl<F: Field> SolutionSpace<F> {
+    pub fn rank(&self) -> usize {
+        self.pivot_variables.len()
+    }
+
+    /// Variables that can be picked freely, in increasing order.
+    pub fn free_variables(&self) -> &[usize] {
+        &self.free_variables
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.free_variables.is_empty()
+    }
+
+    /// Returns the full solution given a value for each free variable.
+    pub fn solution(&self, free_values: &[F]) -> Vec<F> {
+        assert_eq!(free_values.len(), self.free_variables.len());
+
+        let mut solution = vec![F::ZERO; self.variable_count];
+        for (free, value) in self.free_variables.iter().zip(free_values) {
+            solution[*free] = *value;
+        }
+        for (row, pivot) in self.pivot_variables.iter().enumerate() {
+            solution[*pivot] = self.coefficients[row]
+                .iter()
+                .zip(free_values)
+                .fold(self.constants[row], |value, (coefficient, free_value)| {
+                    value - *coefficient * *free_value
+                });
+        }
+        solution
+    }
+}
+
+impl SolutionSpace<Rational> {
+    /// Calls visit with every integer solution where each variable is within its bounds.
+    ///
+    /// The free variables are tried one by one within their bounds, so the amount of work grows
+    /// with the product of their range sizes.
+    pub fn for_each_integer_solution<V>(&self, bounds: &[RangeInclusive<i64>], mut visit: V)
+    where
+        V: FnMut(&[i64]),
+    {
+        assert_eq!(bounds.len(), self.variable_count);
+
+        let free_bounds: Vec<RangeInclusive<i64>> = self
+            .free_variables
+            .iter()
+            .map(|free| bounds[*free].clone())
+            .collect();
+        if free_bounds.iter().any(|range| range.is_empty()) {
+            return;
+        }
+
+        // Each pivot row is scaled by the common denominator of its values, so the pivot values
+        // can be found with integer math: pivot = (constant - sum(coefficient * free)) / scale.
+        let scaled_rows: Vec<(i128, i128, Vec<i128>)> = (0..self.rank())
+            .map(|row| {
+                let scale = self.coefficients[row]
+                    .iter()
+                    .fold(self.constants[row].denominator, |scale, value| {
+                        scale / gcd(scale, value.denominator) * value.denominator
+                    });
+                let scale_value = |value: &Rational| value.numerator * (scale / value.denominator);
+                let coefficients = self.coefficients[row].iter().map(scale_value).collect();
+                (scale, scale_value(&self.constants[row]), coefficients)
+            })
+            .collect();
+
+        let mut free_values: Vec<i64> = free_bounds.iter().map(|range| *range.start()).collect();
+        let mut solution = vec![0; self.variable_count];
+        loop {
+            for (free, value) in self.free_variables.iter().zip(&free_values) {
+                solution[*free] = *value;
+            }
+
+            let fits = self.pivot_variables.iter().zip(&scaled_rows).all(
+                |(pivot, (scale, constant, coefficients))| {
+                    let value = coefficients.iter().zip(&free_values).fold(
+                        *constant,
+                        |value, (coefficient, free_value)| {
+                            value - coefficient * *free_value as i128
+                        },
+                    );
+                    if value % scale != 0 {
+                        return false;
+                    }
+                    match i64::try_from(value / scale) {
+                        Ok(value) if bounds[*pivot].contains(&value) => {
+                            solution[*pivot] = value;
+                            true
+                        }
+                        _ => false,
+                    }
+                },
+            );
+            if fits {
+                visit(&solution);
+            }
+
+            // Count through the free values like an odometer.
+            let mut position = 0;
+            while position < free_values.len() {
+                if free_values[position] < *free_bounds[position].end() {
+                    free_values[position] += 1;
+                    break;
+                }
+                free_values[position] = *free_bounds[position].start();
+                position += 1;
+            }
+            if position == free_values.len() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational_matrix(rows: &[&[i64]]) -> Matrix<Rational> {
+        Matrix::from_rows(
+            rows.iter()
+                .map(|row| row.iter().map(|value| Rational::from(*value)).collect())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn rational_arithmetic() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(-2, -6);
+        assert_eq!(half + third, Rational::new(5, 6));
+        assert_eq!(half - third, Rational::new(1, 6));
+        assert_eq!(half / third, Rational::new(3, 2));
+        assert_eq!(Rational::new(4, -8), -half);
+        assert_eq!(Rational::new(-7, 2).floor(), -4);
+        assert_eq!(Rational::new(-7, 2).ceil(), -3);
+        assert!(third < half);
+        assert_eq!(format!("{}", Rational::new(6, 3)), "2");
+    }
+
+    #[test]
+    fn solve_unique_system() {
+        // Claw machine example: 94a + 22b = 8400, 34a + 67b = 5400.
+        let a = rational_matrix(&[&[94, 22], &[34, 67]]);
+        let b = [Rational::from(8400i64), Rational::from(5400i64)];
+        let space = solve(&a, &b).unwrap();
+        assert!(space.is_unique());
+        assert_eq!(
+            space.solution(&[]),
+            vec![Rational::from(80i64), Rational::from(40i64)]
+        );
+        assert_eq!(a.rank(), 2);
+    }
+
+    #[test]
+    fn solve_inconsistent_and_free_variables() {
+        let a = rational_matrix(&[&[1, 1], &[2, 2]]);
+        assert!(solve(&a, &[Rational::from(1i64), Rational::from(3i64)]).is_none());
+
+        let space = solve(&a, &[Rational::from(3i64), Rational::from(6i64)]).unwrap();
+        assert_eq!(space.rank(), 1);
+        assert_eq!(space.free_variables(), &[1]);
+
+        let mut solutions = Vec::new();
+        space.for_each_integer_solution(&[0..=2, 0..=5], |solution| {
+            solutions.push(solution.to_vec())
+        });
+        assert_eq!(solutions, vec![vec![2, 1], vec![1, 2], vec![0, 3]]);
+    }
+
+    #[test]
+    fn integer_solutions_skip_fractions() {
+        // 2x + y = 3, so x = (3 - y) / 2 is only whole for odd y.
+        let a = rational_matrix(&[&[2, 1]]);
+        let space = solve(&a, &[Rational::from(3i64)]).unwrap();
+        let mut solutions = Vec::new();
+        space.for_each_integer_solution(&[0..=10, 0..=10], |solution| {
+            solutions.push(solution.to_vec())
+        });
+        assert_eq!(solutions, vec![vec![1, 1], vec![0, 3]]);
+    }
+
+    #[test]
+    fn solve_over_gf2() {
+        // Lights toggled by buttons (0, 1), (1, 2) and (0, 2), aiming for lights 0 and 1 on.
+        let (o, i) = (Gf2(false), Gf2(true));
+        let a = Matrix::from_rows(vec![vec![i, o, i], vec![i, i, o], vec![o, i, i]]);
+        assert!(solve(&a, &[i, o, o]).is_none());
+
+        let space = solve(&a, &[i, i, o]).unwrap();
+        assert_eq!(space.rank(), 2);
+        assert_eq!(space.free_variables(), &[2]);
+        assert_eq!(space.solution(&[o]), vec![i, o, o]);
+        assert_eq!(space.solution(&[i]), vec![o, i, i]);
+    }
+}
@@ -1,3 +1,10 @@
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod bitset;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod gf2;
+mod integer_programming;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod linear_algebra;
 mod reader;
 
 #[cfg(test)]
@@ -66,50 +73,44 @@ amount. In debug mode execution went from 3.200 seconds to 0.097 seconds.
 Release mode went from 0.310 seconds to 0.033 seconds.
 
 We can then
+
+Update: Pressing a button twice is the same as not pressing it at all, and the order doesn't
+matter. So each button is either pressed once or not at all, and the lights are the xor of the
+pressed buttons. That is a linear system over GF(2), where each light is an equation and each
+button a variable. The shared gf2 module packs the lights into bits and solves it by elimination.
+Any buttons left free after the elimination make up the null space, and trying every combination
+of them finds the solution with the fewest pressed buttons. There are only ever a few free
+buttons, so this is a lot less work than searching through the light states.
 */
 mod part_one {
+    use crate::bitset::BitSet;
+    use crate::gf2::Gf2Matrix;
     use crate::reader;
-    use std::{
-        collections::{HashSet, VecDeque},
-        error::Error,
-    };
-
-    #[derive(Default, PartialEq, Eq, Debug, Hash, Clone, Copy)]
-    struct Lights {
-        lights: [bool; 10],
-    }
-
-    impl Lights {
-        fn from_light_pattern(data: &str) -> Result<Lights, Box<dyn Error>> {
-            let trimmed_data = &data[1..data.len() - 1];
-            let mut new_lights = Lights::default();
-            for (i, char) in trimmed_data.chars().enumerate() {
-                new_lights.lights[i] = match char {
-                    '#' => true,
-                    '.' => false,
-                    _ => return Err("Unexpected character in light pattern!".into()),
-                };
-            }
-            Ok(new_lights)
-        }
-
-        fn from_button(data: &&str) -> Result<Lights, Box<dyn Error>> {
-            let trimmed_data = data[1..data.len() - 1].split(',');
-            let mut new_lights = Lights::default();
-            for i in trimmed_data {
-                new_lights.lights[i.parse::<usize>()?] = true;
-            }
-
-            Ok(new_lights)
+    use std::error::Error;
+
+    fn parse_light_pattern(data: &str) -> Result<BitSet, Box<dyn Error>> {
+        let trimmed_data = &data[1..data.len() - 1];
+        let mut lights = BitSet::new(trimmed_data.len());
+        for (i, char) in trimmed_data.chars().enumerate() {
+            match char {
+                '#' => lights.set(i, true),
+                '.' => {}
+                _ => return Err("Unexpected character in light pattern!".into()),
+            };
         }
+        Ok(lights)
+    }
 
-        fn combine(&self, other: &Lights) -> Lights {
-            let mut new_lights = Lights::default();
-            for i in 0..10 {
-                new_lights.lights[i] = self.lights[i] ^ other.lights[i];
+    fn parse_button(data: &str, light_count: usize) -> Result<BitSet, Box<dyn Error>> {
+        let mut lights = BitSet::new(light_count);
+        for i in data[1..data.len() - 1].split(',') {
+            let light = i.parse::<usize>()?;
+            if light >= light_count {
+                return Err("Button toggles a light that doesn't exist!".into());
             }
-            new_lights
+            lights.set(light, true);
         }
+        Ok(lights)
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
@@ -117,29 +118,21 @@ mod part_one {
 
         for line in reader::get_lines(data_path)? {
             let parts = line.split(' ').collect::<Vec<&str>>();
-            let (mut pattern_lookup, mut parts_iter) = (HashSet::new(), parts.iter());
-            let mut processing_queue = VecDeque::from(vec![(Lights::default(), 0)]);
-            let button_count = parts_iter.len() - 2; // -1 for first and -1 for last elements.
-
-            let desired_pattern =
-                Lights::from_light_pattern(parts_iter.next().expect("This should never be None"))?;
+            if parts.len() < 2 {
+                return Err("Invalid data line format!".into());
+            }
 
-            let buttons = parts_iter
-                .take(button_count)
-                .map(Lights::from_button)
+            let desired_pattern = parse_light_pattern(parts[0])?;
+            let buttons = parts[1..parts.len() - 1]
+                .iter()
+                .map(|button| parse_button(button, desired_pattern.len()))
                 .collect::<Result<Vec<_>, _>>()?;
 
-            'outer: while let Some((lights, steps)) = processing_queue.pop_front() {
-                for new_lights in buttons.iter().map(|b| lights.combine(b)) {
-                    if pattern_lookup.insert(new_lights) {
-                        if new_lights == desired_pattern {
-                            total_steps += 1 + steps;
-                            break 'outer;
-                        }
-                        processing_queue.push_back((new_lights, steps + 1));
-                    }
-                }
-            }
+            let presses = Gf2Matrix::from_columns(desired_pattern.len(), &buttons)
+                .solve(&desired_pattern)
+                .ok_or("The light pattern can't be reached!")?
+                .min_weight();
+            total_steps += presses.count_ones() as u64;
         }
 
         Ok(total_steps)
@@ -243,149 +236,77 @@ https://www.reddit.com/r/adventofcode/comments/1pk87hl/2025_day_10_part_2_bifurc
 
 I have translated their original (Not optimized) solution into rust, and plan to spend some time to try and
 improve it myself as a exercise to compensate for not technically "solving" it myself.
+
+Update:
+Coming back to the math solution with a shared linear algebra module.
+Each counter gives one equation where the unknowns are how many times each block is pressed:
+c1: b5 + b6 = 3
+c2: b2 + b6 = 5
+c3: b3 + b4 + b5 = 4
+c4: b1 + b2 + b4 = 7
+Row reducing this system with exact fractions splits the blocks into pivot blocks and free blocks.
+Once the free blocks are given a value, the pivot blocks follow directly from the equations.
+There are usually only a couple of free blocks, and like noted above, no block can be pressed more
+times than its lowest counter. So every combination of free block presses within those limits is
+tried, keeping the ones where all blocks get a whole, non-negative number of presses. The lowest
+total amongst them is the answer.
+
+Update:
+Trying every combination of the free blocks grows quickly with the amount of free blocks. This is
+really an integer linear programming problem: minimise the sum of presses, where the presses are
+whole and non-negative, and the counters must match. The shared integer programming module solves
+that by first allowing fractional presses, which the simplex method handles quickly. Any block
+with a fractional amount of presses splits the problem into two, one where it is pressed at most
+the rounded down amount and one with at least the rounded up amount. A branch is dropped as soon
+as its fractional answer can't beat the best whole answer found so far.
 */
 mod part_two {
+    use crate::integer_programming::minimise_sum;
+    use crate::linear_algebra::{Matrix, Rational};
     use crate::reader;
-    use std::{array, collections::HashMap, error::Error, iter::zip, time::Instant};
-
-    // #[rustfmt::skip] Disables auto formatting for the connected method, making it possible to
-    // make it more compact. Although it should probably only ever be used in cases where very
-    // simple code would otherwise take a really large amount of space.
-    // For example each of the two macro_rules! below would otherwise take 5 rows each.
-    #[rustfmt::skip] macro_rules! combination_end { () => { vec![vec![]]}; }
-    #[rustfmt::skip] macro_rules! no_possible_combinations { () => { vec![]}; }
-
-    #[derive(Default, PartialEq, Eq, Debug, Hash, Clone, Copy)]
-    struct Joltage {
-        values: [u16; 10],
-    }
-
-    impl From<[u16; 10]> for Joltage {
-        fn from(values: [u16; 10]) -> Self {
-            Joltage { values }
-        }
-    }
-
-    impl Joltage {
-        fn from_joltage_pattern(data: &str) -> Result<Joltage, Box<dyn Error>> {
-            let mut new_lights = Joltage::default();
-            for (i, int_str) in data[1..data.len() - 1].split(',').enumerate() {
-                new_lights.values[i] = int_str.parse()?;
-            }
-
-            Ok(new_lights)
-        }
-
-        fn from_button(data: &str) -> Result<Joltage, Box<dyn Error>> {
-            let mut new_lights = Joltage::default();
-            for int_str in data[1..data.len() - 1].split(',') {
-                new_lights.values[int_str.parse::<usize>()?] = 1;
-            }
-
-            Ok(new_lights)
-        }
+    use std::error::Error;
 
-        fn add_values(&mut self, rhs: &Joltage) {
-            self.values = array::from_fn(|i| self.values[i] + rhs.values[i])
-        }
-
-        fn subtract_and_divide(&self, rhs: &Joltage) -> Joltage {
-            array::from_fn(|i| (self.values[i] - rhs.values[i]) / 2).into()
-        }
-
-        fn lights(&self) -> [u16; 10] {
-            array::from_fn(|i| self.values[i] % 2)
-        }
-    }
-
-    fn get_combinations<T: Clone>(blocks: &[T], combination_len: usize) -> Vec<Vec<T>> {
-        if combination_len == 0 {
-            return combination_end!(); // Equal to a Vec containing one empty Vec.
-        }
-
-        if blocks.len() < combination_len {
-            return no_possible_combinations!(); // Equal to a empty Vec
-        }
-
-        let mut result = Vec::new();
-
-        for i in 0..=blocks.len() - combination_len {
-            let head = blocks[i].clone();
-            for mut tail in get_combinations(&blocks[i + 1..], combination_len - 1) {
-                let mut combo = vec![head.clone()];
-                combo.append(&mut tail);
-                result.push(combo);
-            }
-        }
-
-        result
+    fn parse_values(data: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+        let values = data[1..data.len() - 1].split(',');
+        Ok(values
+            .map(|v| v.parse())
+            .collect::<Result<Vec<usize>, _>>()?)
     }
 
-    fn get_patterns(blocks: Vec<Joltage>) -> HashMap<[u16; 10], HashMap<Joltage, u16>> {
-        let mut patterns: HashMap<[u16; 10], HashMap<Joltage, u16>> = HashMap::new();
-
-        for pattern_len in 0..blocks.len() + 1 {
-            for buttons in get_combinations(&blocks, pattern_len) {
-                let mut pattern = Joltage::default();
-                buttons.iter().for_each(|b| pattern.add_values(b));
-
-                patterns
-                    .entry(pattern.lights())
-                    .or_default()
-                    .entry(pattern)
-                    .or_insert(pattern_len as u16);
+    /// Finds the fewest presses that bring every counter to its target.
+    fn fewest_presses(blocks: &[Vec<usize>], targets: &[usize]) -> Result<u64, Box<dyn Error>> {
+        // One row per counter and one column per block, with a 1 where the block increases it.
+        let mut counters = Matrix::new(targets.len(), blocks.len());
+        for (block_index, block) in blocks.iter().enumerate() {
+            for counter in block {
+                if *counter >= targets.len() {
+                    return Err("Block increases a counter that doesn't exist!".into());
+                }
+                counters[(*counter, block_index)] = Rational::from(1i64);
             }
         }
+        let targets: Vec<Rational> = targets.iter().map(|t| Rational::from(*t as i64)).collect();
 
-        patterns
-    }
-
-    fn solver(pattern_costs: &HashMap<[u16; 10], HashMap<Joltage, u16>>, target: Joltage) -> u16 {
-        if target.values.iter().sum::<u16>() == 0 {
-            return 0;
-        }
-        let mut answer = 10000;
-        let goal_pattern = array::from_fn(|i| target.values[i] % 2);
-        let Some(patterns) = pattern_costs.get(&goal_pattern) else {
-            return answer;
-        };
-        for (pattern, pattern_cost) in patterns.iter() {
-            if zip(pattern.values, target.values).all(|(p, d)| p <= d && p % 2 == d % 2) {
-                let new_goal = target.subtract_and_divide(pattern);
-                answer = answer.min(*pattern_cost + (2 * solver(pattern_costs, new_goal)));
-            }
-        }
-        answer
+        let presses = minimise_sum(&counters, &targets).ok_or("The targets are unreachable!")?;
+        Ok(presses.iter().sum::<i64>() as u64)
     }
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
         let mut total_steps = 0;
 
         for line in reader::get_lines(data_path)? {
-            #[cfg(debug_assertions)] // Only compile this if in Debug mode
-            let start_time = Instant::now();
-
             let parts = line.split(' ').collect::<Vec<&str>>();
-            let (mut blocks, mut cache) = (Vec::new(), None);
-
-            for s in parts.iter().skip(1) {
-                if let Some(v) = cache {
-                    blocks.push(Joltage::from_button(v)?);
-                }
-                cache = Some(s);
+            if parts.len() < 2 {
+                return Err("Invalid data line format!".into());
             }
-            let desired_pattern =
-                Joltage::from_joltage_pattern(cache.ok_or("Invalid data line format!")?)?;
-
-            let steps = solver(&get_patterns(blocks), desired_pattern);
-            total_steps += steps as u64;
-
-            #[cfg(debug_assertions)] // Only compile this if in Debug mode
-            println!(
-                "Found {} steps in {:?} time",
-                steps,
-                Instant::now() - start_time,
-            );
+
+            let blocks = parts[1..parts.len() - 1]
+                .iter()
+                .map(|block| parse_values(block))
+                .collect::<Result<Vec<_>, _>>()?;
+            let targets = parse_values(parts[parts.len() - 1])?;
+
+            total_steps += fewest_presses(&blocks, &targets)?;
         }
         Ok(total_steps)
     }
@@ -0,0 +1,316 @@
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph has a cycle where a acyclic graph was required. Holds the nodes of the cycle in
+    /// the order they are connected, the last node has a edge back to the first.
+    Cycle(Vec<usize>),
+    /// More must visit nodes were requested than the path counting supports.
+    TooManyMustVisit(usize),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Cycle(nodes) => write!(f, "Graph contains the cycle {:?}", nodes),
+            GraphError::TooManyMustVisit(count) => write!(
+                f,
+                "{} must visit nodes requested but at most {} are supported",
+                count, MAX_MUST_VISIT
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// Path counting keeps one counter per combination of visited must visit nodes, so the amount of
+/// counters per node grows as 2^n.
+const MAX_MUST_VISIT: usize = 16;
+
+/// Adjacency list graph over dense node ids, such as the ones handed out by the interner.
+///
+/// Nodes are created on demand when an edge refers to them, so the graph always holds the nodes
+/// 0..node_count().
+#[derive(Debug, Default, Clone)]
+pub struct Graph {
+    edges: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_nodes(node_count: usize) -> Self {
+        Graph {
+            edges: vec![Vec::new(); node_count],
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// Makes sure the node exists, adding any missing nodes below it as well.
+    pub fn add_node(&mut self, node: usize) {
+        if node >= self.edges.len() {
+            self.edges.resize(node + 1, Vec::new());
+        }
+    }
+
+    /// Adds a directed edge from one node to another.
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.add_node(from.max(to));
+        self.edges[from].push(to);
+    }
+
+    /// Adds edges in both directions, for undirected graphs.
+    pub fn connect(&mut self, a: usize, b: usize) {
+        self.add_edge(a, b);
+        self.add_edge(b, a);
+    }
+
+    /// Returns the nodes the node has edges to.
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        match self.edges.get(node) {
+            Some(neighbours) => neighbours,
+            None => &[],
+        }
+    }
+
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.neighbours(from).contains(&to)
+    }
+
+    pub fn nodes(&self) -> std::ops::Range<usize> {
+        0..self.edges.len()
+    }
+
+    /// Orders all nodes so that every edge points from a earlier node to a later one.
+    pub fn topological_order(&self) -> Result<Vec<usize>, GraphError> {
+        self.depth_first_order(self.nodes(), |_| true)
+    }
+
+    /// Orders the provided nodes using only the edges between them. Edges to nodes outside of
+    /// the list are ignored, which lets a subset be ordered even if the full graph has cycles.
+    pub fn topological_order_within(&self, nodes: &[usize]) -> Result<Vec<usize>, GraphError> {
+        let mut included = vec![false; self.node_count()];
+        for node in nodes {
+            self.assert_node_exists(*node);
+            included[*node] = true;
+        }
+        self.depth_first_order(nodes.iter().copied(), |node| included[node])
+    }
+
+    /// Returns a cycle in the graph if one exists.
+    pub fn find_cycle(&self) -> Option<Vec<usize>> {
+        match self.topological_order() {
+            Err(GraphError::Cycle(cycle)) => Some(cycle),
+            _ => None,
+        }
+    }
+
+    /// Counts the paths leading from one node to another.
+    pub fn count_paths(&self, from: usize, to: usize) -> Result<u64, GraphError> {
+        self.count_paths_through(from, to, &[])
+    }
+
+    /// Counts the paths leading from one node to another which pass all the must visit nodes, in
+    /// any order.
+    ///
+    /// Only the part of the graph reachable from the start has to be acyclic. Every node keeps a
+    /// count per combination of must visit nodes passed so far, which are added forwards along
+    /// the edges in topological order.
+    pub fn count_paths_through(
+        &self,
+        from: usize,
+        to: usize,
+        must_visit: &[usize],
+    ) -> Result<u64, GraphError> {
+        if must_visit.len() > MAX_MUST_VISIT {
+            return Err(GraphError::TooManyMustVisit(must_visit.len()));
+        }
+        self.assert_node_exists(from.max(to));
+
+        let order = self.depth_first_order([from].into_iter(), |_| true)?;
+        let mask_of = |node: usize| -> usize {
+            must_visit
+                .iter()
+                .enumerate()
+                .filter(|(_, must)| **must == node)
+                .fold(0, |mask, (index, _)| mask | (1 << index))
+        };
+        let combinations = 1 << must_visit.len();
+
+        let mut counts: Vec<Vec<u64>> = vec![Vec::new(); self.node_count()];
+        counts[from] = vec![0; combinations];
+        counts[from][mask_of(from)] = 1;
+
+        for node in order {
+            if node == to || counts[node].is_empty() {
+                continue; // Paths end at the goal, they can't pass through it.
+            }
+            let current = counts[node].clone();
+            for neighbour in self.neighbours(node) {
+                let neighbour_mask = mask_of(*neighbour);
+                let neighbour_counts = &mut counts[*neighbour];
+                if neighbour_counts.is_empty() {
+                    *neighbour_counts = vec![0; combinations];
+                }
+                for (mask, count) in current.iter().enumerate() {
+                    neighbour_counts[mask | neighbour_mask] += count;
+                }
+            }
+        }
+
+        Ok(counts[to].last().copied().unwrap_or(0))
+    }
+
+    /// Depth first search from the roots through the included nodes, returning them in reverse
+    /// post order. Running into a node that is still being searched means there is a cycle, which
+    /// can be read straight from the search stack.
+    fn depth_first_order<I, F>(&self, roots: I, include: F) -> Result<Vec<usize>, GraphError>
+    where
+        I: Iterator<Item = usize>,
+        F: Fn(usize) -> bool,
+    {
+        #[derive(Clone, Copy, PartialEq)]
+        enum State {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+
+        let mut states = vec![State::Unvisited; self.node_count()];
+        let mut order = Vec::with_capacity(self.node_count());
+
+        for root in roots {
+            if states[root] != State::Unvisited {
+                continue;
+            }
+            // Each stack entry is a node together with the index of the next edge to follow.
+            let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+            states[root] = State::InProgress;
+
+            while let Some((node, edge_index)) = stack.last_mut() {
+                let node = *node;
+                let Some(neighbour) = self.neighbours(node).get(*edge_index).copied() else {
+                    states[node] = State::Done;
+                    order.push(node);
+                    stack.pop();
+                    continue;
+                };
+                *edge_index += 1;
+
+                if !include(neighbour) {
+                    continue;
+                }
+                match states[neighbour] {
+                    State::Done => {}
+                    State::Unvisited => {
+                        states[neighbour] = State::InProgress;
+                        stack.push((neighbour, 0));
+                    }
+                    State::InProgress => {
+                        let start = stack
+                            .iter()
+                            .position(|(stack_node, _)| *stack_node == neighbour)
+                            .expect("nodes in progress are always on the stack");
+                        let cycle = stack[start..].iter().map(|(node, _)| *node).collect();
+                        return Err(GraphError::Cycle(cycle));
+                    }
+                }
+            }
+        }
+
+        order.reverse();
+        Ok(order)
+    }
+
+    fn assert_node_exists(&self, node: usize) {
+        assert!(
+            node < self.node_count(),
+            "Node {} does not exist in a graph with {} nodes!",
+            node,
+            self.node_count()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Graph, GraphError};
+
+    #[test]
+    fn add_edge_creates_missing_nodes() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 3);
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.neighbours(0), &[3]);
+        assert!(graph.neighbours(3).is_empty());
+        assert!(graph.neighbours(7).is_empty());
+    }
+
+    #[test]
+    fn topological_order_respects_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge(3, 1);
+        graph.add_edge(1, 0);
+        graph.add_edge(2, 0);
+        graph.add_edge(3, 2);
+
+        let order = graph.topological_order().unwrap();
+        let position = |node| order.iter().position(|n| *n == node).unwrap();
+        assert!(position(3) < position(1) && position(3) < position(2));
+        assert!(position(1) < position(0) && position(2) < position(0));
+    }
+
+    #[test]
+    fn topological_order_reports_cycle() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 1);
+        graph.add_edge(1, 2);
+        graph.add_edge(2, 3);
+        graph.add_edge(3, 1);
+
+        assert_eq!(
+            graph.topological_order(),
+            Err(GraphError::Cycle(vec![1, 2, 3]))
+        );
+        // Ordering a subset without the back edge works fine.
+        assert_eq!(
+            graph.topological_order_within(&[2, 0, 1]),
+            Ok(vec![0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn count_paths_with_must_visit() {
+        // 0 -> 1 -> 3 -> 4
+        // 0 -> 2 -> 3
+        // 1 -> 2
+        let mut graph = Graph::new();
+        for (from, to) in [(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (3, 4)] {
+            graph.add_edge(from, to);
+        }
+
+        assert_eq!(graph.count_paths(0, 4), Ok(3));
+        assert_eq!(graph.count_paths_through(0, 4, &[2]), Ok(2));
+        assert_eq!(graph.count_paths_through(0, 4, &[1, 2]), Ok(1));
+        assert_eq!(graph.count_paths(4, 0), Ok(0));
+    }
+
+    #[test]
+    fn connect_adds_both_directions() {
+        let mut graph = Graph::new();
+        graph.connect(1, 2);
+        assert!(graph.has_edge(1, 2));
+        assert!(graph.has_edge(2, 1));
+        assert_eq!(graph.edge_count(), 2);
+    }
+}
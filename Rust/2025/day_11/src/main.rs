@@ -1,3 +1,7 @@
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod graph;
+#[allow(dead_code)] // Shared module, not all of it is needed here.
+mod interner;
 mod reader;
 
 #[cfg(test)]
@@ -62,88 +66,47 @@ took ages so I went back to searching the correct direction.
 A number of optimizations where also added to try and get past the ages long calculation, which
 works just as well for the normal search too. Although I am usure if they are actually needed
 now.
+
+Update 2:
+The device connections never loop, so the recursive search has been replaced with the shared graph
+path counting. It orders the devices topologically once and then pushes the path counts forwards
+along the connections, which visits each device exactly once.
 */
 mod part_one {
+    use crate::graph::Graph;
+    use crate::interner::Interner;
     use crate::reader;
-    use std::{
-        collections::{HashMap, HashSet},
-        error::Error,
-    };
-
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-    struct DeviceId(u16);
-
-    const OUT: DeviceId = DeviceId(10003);
-    const YOU: DeviceId = DeviceId(16608);
-
-    impl DeviceId {
-        fn new(id_string: &str) -> Result<DeviceId, Box<dyn Error>> {
-            let (mut multiplier, mut result) = (1, 0);
-            for c in id_string.chars().rev() {
-                if !c.is_ascii_lowercase() {
-                    return Err(format!(
-                        "E1: Invalid character [{}] in data string [{}]!",
-                        c, id_string
-                    )
-                    .into());
-                }
-                result += (c as u8 - b'a') as u16 * multiplier;
-                multiplier *= 26;
-            }
-            Ok(DeviceId(result))
-        }
-    }
+    use std::error::Error;
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
-        let mut devices: HashMap<DeviceId, (Vec<DeviceId>, Option<u64>)> = HashMap::new();
+        let (devices, interner) = load_devices(data_path)?;
 
-        for line in reader::get_lines(data_path)? {
-            let mut parts = line.split(": ");
-            let source = DeviceId::new(parts.next().ok_or("E2: Invalid data format!")?)?;
-
-            let (connections, _) = devices.entry(source).or_default();
-            for part in parts.next().ok_or("")?.split(" ") {
-                connections.push(DeviceId::new(part)?);
-            }
-        }
+        let you = interner
+            .get("you")
+            .ok_or("E3: Device [you] does not exist!")?;
+        let out = interner
+            .get("out")
+            .ok_or("E3: Device [out] does not exist!")?;
 
-        solver(YOU, OUT, &mut HashSet::new(), &mut devices)
+        Ok(devices.count_paths(you, out)?)
     }
 
-    /// Recursive solver that counts how many unique paths exist from current to goal.
-    ///
-    /// path_trace is used to keep track of the current path to prevent loops.
-    /// connections holds the ids and any already computed path counts from said ids.
-    fn solver(
-        current: DeviceId,
-        goal: DeviceId,
-        path_trace: &mut HashSet<DeviceId>,
-        connections: &mut HashMap<DeviceId, (Vec<DeviceId>, Option<u64>)>,
-    ) -> Result<u64, Box<dyn Error>> {
-        let (connected_ids, paths_to_goal) = connections.get(&current).ok_or(format!(
-            "E3: [{:?}] does not exist in the connections hashmap!",
-            current
-        ))?;
-
-        if let Some(paths) = paths_to_goal {
-            path_trace.remove(&current);
-            return Ok(*paths);
-        }
+    /// Reads the device connections into a graph, with the device names turned into node ids.
+    fn load_devices(data_path: &str) -> Result<(Graph, Interner), Box<dyn Error>> {
+        let mut devices = Graph::new();
+        let mut interner = Interner::new();
 
-        let mut result = 0;
-        for connected_id in connected_ids.clone() {
-            if connected_id == goal {
-                result += 1;
-            } else if path_trace.insert(connected_id) {
-                result += solver(connected_id, goal, path_trace, connections)?;
+        for line in reader::get_lines(data_path)? {
+            let mut parts = line.split(": ");
+            let source = interner.intern(parts.next().ok_or("E2: Invalid data format!")?);
+
+            devices.add_node(source);
+            for part in parts.next().ok_or("E2: Invalid data format!")?.split(" ") {
+                devices.add_edge(source, interner.intern(part));
             }
         }
 
-        path_trace.remove(&current);
-        connections.get_mut(&current).unwrap().1 = Some(result);
-        // Unwrap is OK since we exit safely before this if current doesn't exist in connections.
-
-        Ok(result)
+        Ok((devices, interner))
     }
 }
 
@@ -208,123 +171,51 @@ Results:
 4643476320 is too low ??? What!? Quite understandable that the calculation took ages if there
 are more than 4643476320 paths...
 303012373210128 was the correct answer. Thats a lot of paths!
+
+Update 5:
+The graph path counting now supports must visit nodes directly. Each device keeps one path count
+per combination of "dac" and "fft" passed, so both orders are handled in a single pass.
 */
 mod part_two {
+    use crate::graph::Graph;
+    use crate::interner::Interner;
     use crate::reader;
-    use std::{
-        collections::{HashMap, HashSet},
-        error::Error,
-    };
-
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-    struct DeviceId(u16);
-
-    const SVR: DeviceId = DeviceId(12731);
-    const DAC: DeviceId = DeviceId(2030);
-    const FFT: DeviceId = DeviceId(3529);
-    const OUT: DeviceId = DeviceId(10003);
-
-    impl DeviceId {
-        fn new(id_string: &str) -> Result<DeviceId, Box<dyn Error>> {
-            let mut multiplier = 1;
-            let mut result = 0;
-            for c in id_string.chars().rev() {
-                if !c.is_ascii_lowercase() {
-                    return Err(format!(
-                        "E1: Invalid character [{}] in data string [{}]!",
-                        c, id_string
-                    )
-                    .into());
-                }
-                result += (c as u8 - b'a') as u16 * multiplier;
-                multiplier *= 26;
-            }
-            Ok(DeviceId(result))
-        }
-    }
+    use std::error::Error;
 
     pub fn calculate(data_path: &str) -> Result<u64, Box<dyn Error>> {
-        let mut devices: HashMap<DeviceId, (Vec<DeviceId>, Option<u64>)> = HashMap::new();
-
-        for line in reader::get_lines(data_path)? {
-            let mut parts = line.split(": ");
-            let source = DeviceId::new(parts.next().ok_or("E2: Invalid data format!")?)?;
-
-            let (connections, _) = devices.entry(source).or_default();
-            for part in parts.next().ok_or("")?.split(" ") {
-                connections.push(DeviceId::new(part)?);
-            }
-        }
-
-        let mut result = 0;
-        result += count_paths(vec![SVR, DAC, FFT, OUT], &devices)?;
-        result += count_paths(vec![SVR, FFT, DAC, OUT], &devices)?;
-        Ok(result)
+        let (devices, interner) = load_devices(data_path)?;
+
+        let device = |name: &str| {
+            interner
+                .get(name)
+                .ok_or(format!("E3: Device [{}] does not exist!", name))
+        };
+        let (svr, dac, fft, out) = (
+            device("svr")?,
+            device("dac")?,
+            device("fft")?,
+            device("out")?,
+        );
+
+        Ok(devices.count_paths_through(svr, out, &[dac, fft])?)
     }
 
-    /// Counts how many unique paths exist from the first to last manadatory nodes that also pass
-    /// the middle nodes in the correct order.
-    fn count_paths(
-        mandatory_nodes_in_order: Vec<DeviceId>,
-        devices: &HashMap<DeviceId, (Vec<DeviceId>, Option<u64>)>,
-    ) -> Result<u64, Box<dyn Error>> {
-        let mut paths = 1; // Since we multiply the paths we can't start with 0.
-
-        // Multiplies the path count from node 1 to 2, 2 to 3 and so on together to get the result.
-        for index in 0..mandatory_nodes_in_order.len() - 1 {
-            let mut path_trace: HashSet<DeviceId> = HashSet::new();
-            for node in mandatory_nodes_in_order.iter().take(index) {
-                path_trace.insert(*node);
-            }
-            for node in mandatory_nodes_in_order.iter().skip(index + 2) {
-                path_trace.insert(*node);
-            }
+    /// Reads the device connections into a graph, with the device names turned into node ids.
+    fn load_devices(data_path: &str) -> Result<(Graph, Interner), Box<dyn Error>> {
+        let mut devices = Graph::new();
+        let mut interner = Interner::new();
 
-            paths *= solver(
-                mandatory_nodes_in_order[index],
-                mandatory_nodes_in_order[index + 1],
-                &mut path_trace,
-                &mut devices.clone(),
-            )?;
-        }
-
-        Ok(paths)
-    }
-
-    /// Recursive solver that counts how many unique paths exist from current to goal.
-    ///
-    /// path_trace is used to keep track of the current path to prevent loops.
-    /// connections holds the ids and any already computed path counts from said ids.
-    fn solver(
-        current: DeviceId,
-        goal: DeviceId,
-        path_trace: &mut HashSet<DeviceId>,
-        connections: &mut HashMap<DeviceId, (Vec<DeviceId>, Option<u64>)>,
-    ) -> Result<u64, Box<dyn Error>> {
-        let (connected_ids, paths_to_goal) = connections.get(&current).ok_or(format!(
-            "E3: [{:?}] does not exist in the connections hashmap!",
-            current
-        ))?;
-
-        if let Some(paths) = paths_to_goal {
-            path_trace.remove(&current);
-            return Ok(*paths);
-        }
+        for line in reader::get_lines(data_path)? {
+            let mut parts = line.split(": ");
+            let source = interner.intern(parts.next().ok_or("E2: Invalid data format!")?);
 
-        let mut result = 0;
-        for connected_id in connected_ids.clone() {
-            if connected_id == goal {
-                result += 1;
-            } else if path_trace.insert(connected_id) {
-                result += solver(connected_id, goal, path_trace, connections)?;
+            devices.add_node(source);
+            for part in parts.next().ok_or("E2: Invalid data format!")?.split(" ") {
+                devices.add_edge(source, interner.intern(part));
             }
         }
 
-        path_trace.remove(&current);
-        connections.get_mut(&current).unwrap().1 = Some(result);
-        // Unwrap is OK since we exit safely before this if current doesn't exist in connections.
-
-        Ok(result)
+        Ok((devices, interner))
     }
 }
 